@@ -1,4 +1,5 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, Utc};
 use structopt::StructOpt;
 use url::Url;
 
@@ -6,7 +7,8 @@ use std::path::{Path, PathBuf};
 
 use crate::Config;
 use crate::{ConsoleOutput, RSSActionCmd};
-use crate::{Feed, Filter};
+use crate::{BuiltinAction, DownloadAction, BUILTIN_ACTION_PREFIX};
+use crate::{Disabled, Feed, Filter, FilterId, FilterSelector, FilterStart, ImportMode, ReplayWindow, UpdateSelection};
 
 // NB This is basically an adaptor that takes f: A->B and g: B->C
 // (where A is the input, B is the output, and C is the Vec<String>)
@@ -55,13 +57,61 @@ enum SubArg {
     /// Add a feed or filter to the database
     Delete(DeleteArg),
 
+    #[structopt(name = "rename")]
+    /// Rename a feed
+    Rename(RenameArg),
+
+    #[structopt(name = "edit")]
+    /// Change a feed's url or a filter's keywords or script
+    Edit(EditArg),
+
+    #[structopt(name = "disable")]
+    /// Stop updating a feed or filter without deleting it
+    Disable(DisableArg),
+
+    #[structopt(name = "enable")]
+    /// Start updating a disabled feed or filter again
+    Enable(EnableArg),
+
+    #[structopt(name = "tag")]
+    /// Add tags to a feed, so that filters on the tags apply to it
+    Tag(TagArg),
+
+    #[structopt(name = "untag")]
+    /// Remove tags from a feed
+    Untag(UntagArg),
+
     #[structopt(name = "list")]
     /// Display feeds or filters
     List(ListArg),
 
+    #[structopt(name = "replay")]
+    /// Run a filter's script again on entries it has already seen, e.g. after fixing the script
+    Replay(ReplayArg),
+
+    #[structopt(name = "show")]
+    /// Download a feed and show its entries without running any scripts
+    Show(ShowArg),
+
+    #[structopt(name = "test")]
+    /// Try out a filter on a feed without running its script
+    Test(TestArg),
+
+    #[structopt(name = "export")]
+    /// Write all feeds and filters, with the times filters were last updated, as JSON
+    Export(ExportArg),
+
+    #[structopt(name = "import")]
+    /// Read feeds and filters written by `export`
+    Import(ImportArg),
+
+    #[structopt(name = "db")]
+    /// Manage the database
+    Db(DbArg),
+
     #[structopt(name = "update")]
     /// Run update, downloading feeds and matching against filters, running scripts that match
-    Update,
+    Update(UpdateArg),
 }
 
 // -- Add
@@ -88,18 +138,108 @@ enum AddSubArg {
 struct AddFeed {
     /// The name used to refer to this feed
     pub alias: String,
-    /// The url for this feed
+    /// The url for this feed. If it is a web page, the feed linked from the page is added
+    /// instead.
     pub url: String,
+    #[structopt(long)]
+    /// Download and parse the feed before adding it, showing its title and number of entries
+    pub validate: bool,
+    #[structopt(long)]
+    /// Add the url as given without downloading it, e.g. when the feed is not reachable yet
+    pub no_discover: bool,
 }
 
 #[derive(Debug, StructOpt)]
 struct AddFilter {
-    /// The alias of the feed to filter
+    /// The alias of the feed to filter, or the tag with --tag
     pub alias: String,
-    /// The path to the script to run on the matched entries
+    /// The path to the script to run on the matched entries, or a built-in action: `builtin:download`
     pub script_path: String,
     /// The keywords to filter the entries with
     pub keywords: Vec<String>,
+    #[structopt(long)]
+    /// A unique name to refer to the filter by
+    pub name: Option<String>,
+    #[structopt(long, parse(try_from_str = parse_filter_start))]
+    /// Only process entries published after this, either `now` for the newest entry currently in
+    /// the feed, an RFC 3339 date and time, or a date (YYYY-MM-DD) meaning midnight local time
+    pub from: Option<FilterStart>,
+    #[structopt(long)]
+    /// Only process the newest N matching entries currently in the feed, and new ones after them
+    pub backlog: Option<usize>,
+    #[structopt(long)]
+    /// Run the script at most N times per update. Further matching entries are processed by later
+    /// updates
+    pub max_actions: Option<usize>,
+    #[structopt(long)]
+    /// Only run one script per entry among the filters in this group
+    pub dedup_group: Option<String>,
+    #[structopt(long, allow_hyphen_values = true)]
+    /// Filters with a higher priority are run first. Defaults to 0
+    pub priority: Option<i64>,
+    #[structopt(long)]
+    /// Make lower-priority filters on the feed skip entries this filter matches
+    pub stop: bool,
+    #[structopt(long)]
+    /// Filter every feed with the tag given instead of the feed alias
+    pub tag: bool,
+    #[structopt(flatten)]
+    pub download: DownloadArgs,
+}
+
+/// Settings for the `builtin:download` action.
+#[derive(Debug, StructOpt)]
+struct DownloadArgs {
+    #[structopt(long)]
+    /// With builtin:download, the directory to save files in
+    pub download_dir: Option<PathBuf>,
+    #[structopt(long)]
+    /// With builtin:download, where each file is saved in the directory. Defaults to
+    /// `{feed}/{date}-{title}.{ext}`
+    pub path_template: Option<String>,
+    #[structopt(long)]
+    /// With builtin:download, fail downloads larger than this many bytes
+    pub max_size: Option<u64>,
+}
+
+impl DownloadArgs {
+    fn is_empty(&self) -> bool {
+        self.download_dir.is_none() && self.path_template.is_none() && self.max_size.is_none()
+    }
+}
+
+/// What a filter runs on matching entries.
+enum FilterAction {
+    Script(PathBuf),
+    Builtin(BuiltinAction),
+}
+
+/// Parse a filter's script argument, which is either a path or a built-in action such as
+/// `builtin:download`.
+fn parse_filter_action(script: &str, download: DownloadArgs) -> Result<FilterAction> {
+    match script.strip_prefix(BUILTIN_ACTION_PREFIX) {
+        None if !download.is_empty() =>
+            Err(anyhow!("--download-dir, --path-template and --max-size can only be given with builtin:download.")),
+        None => Ok(FilterAction::Script(PathBuf::from(script))),
+        Some("download") => {
+            let dir = download.download_dir
+                .ok_or_else(|| anyhow!("builtin:download needs a --download-dir to save files in."))?;
+            let dir = std::path::absolute(&dir)
+                .with_context(|| format!("Invalid download directory {}", dir.display()))?;
+            let action = DownloadAction::new(dir, download.path_template, download.max_size)?;
+            Ok(FilterAction::Builtin(BuiltinAction::Download(action)))
+        }
+        Some(name) => Err(anyhow!("Unknown built-in action `{}{}`. The built-in actions are: {}download",
+            BUILTIN_ACTION_PREFIX, name, BUILTIN_ACTION_PREFIX)),
+    }
+}
+
+fn parse_filter_start(s: &str) -> Result<FilterStart> {
+    if s == "now" {
+        return Ok(FilterStart::Now);
+    }
+    parse_time(s).map(FilterStart::Date)
+        .map_err(|_| anyhow!("`{}` is not `now`, an RFC 3339 date and time, or a YYYY-MM-DD date", s))
 }
 
 // -- Delete
@@ -126,15 +266,274 @@ enum DeleteSubArg {
 struct DeleteFeed {
     /// The name used to refer to the feed to be deleted
     pub alias: String,
+    #[structopt(long)]
+    /// Also delete the feed's filters
+    pub cascade: bool,
 }
 
 #[derive(Debug, StructOpt)]
 struct DeleteFilter {
-    /// The alias of the feed the filter to be deleted is on
-    pub alias: String,
-    /// The keywords to filter the entries with. Not all keywords from the filter need to be
-    /// present, but enough to uniquely identify the filter are required.
+    #[structopt(flatten)]
+    pub filter: FilterSelectorArgs,
+}
+
+/// Arguments for commands that refer to a single filter, either by id, name, or feed alias and
+/// keywords.
+#[derive(Debug, StructOpt)]
+struct FilterSelectorArgs {
+    /// The alias of the feed the filter is on
+    pub alias: Option<String>,
+    /// Keywords of the filter. Not all keywords from the filter need to be present, but enough to
+    /// uniquely identify the filter are required.
     pub keywords: Vec<String>,
+    #[structopt(long)]
+    /// The id of the filter, as shown by `list filters`, instead of the alias and keywords
+    pub id: Option<usize>,
+    #[structopt(long)]
+    /// The name of the filter, instead of the alias and keywords
+    pub name: Option<String>,
+}
+
+impl FilterSelectorArgs {
+    fn into_selector(self) -> Result<FilterSelector> {
+        let has_keywords = self.alias.is_some() || !self.keywords.is_empty();
+        match (self.id, self.name, self.alias) {
+            (Some(id), None, _) if !has_keywords => Ok(FilterSelector::Id(FilterId(id))),
+            (None, Some(name), _) if !has_keywords => Ok(FilterSelector::Name(name)),
+            (None, None, Some(alias)) => Ok(FilterSelector::Keywords(alias, self.keywords)),
+            (None, None, None) => Err(anyhow!("Give the filter's feed alias and keywords, --id, or --name.")),
+            _ => Err(anyhow!("Only one of the filter's feed alias and keywords, --id, or --name can be given.")),
+        }
+    }
+}
+
+// -- Rename
+
+#[derive(Debug, StructOpt)]
+struct RenameArg {
+    /// Rename a feed in the database.
+    #[structopt(subcommand)]
+    pub cmd: RenameSubArg,
+}
+
+#[derive(Debug, StructOpt)]
+enum RenameSubArg {
+    #[structopt(name = "feed")]
+    /// Change a feed's alias, keeping its filters
+    Feed(RenameFeed),
+}
+
+#[derive(Debug, StructOpt)]
+struct RenameFeed {
+    /// The current alias of the feed
+    pub alias: String,
+    /// The new alias of the feed
+    pub new_alias: String,
+}
+
+// -- Edit
+
+#[derive(Debug, StructOpt)]
+struct EditArg {
+    /// Edit a feed or filter in the database.
+    #[structopt(subcommand)]
+    pub cmd: EditSubArg,
+}
+
+// Parsed once per run, so the size of the filter variant doesn't matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, StructOpt)]
+enum EditSubArg {
+    #[structopt(name = "feed")]
+    /// Change a feed's url
+    Feed(EditFeed),
+
+    #[structopt(name = "filter")]
+    /// Change a filter's keywords or script, keeping the time it was last updated
+    Filter(EditFilter),
+}
+
+#[derive(Debug, StructOpt)]
+struct EditFeed {
+    /// The alias of the feed to edit
+    pub alias: String,
+    #[structopt(long)]
+    /// The new url for the feed
+    pub url: String,
+}
+
+#[derive(Debug, StructOpt)]
+struct EditFilter {
+    #[structopt(flatten)]
+    pub filter: FilterSelectorArgs,
+    #[structopt(long)]
+    /// Replace the filter's keywords
+    pub new_keywords: Option<Vec<String>>,
+    #[structopt(long)]
+    /// Replace the filter's script, or its built-in action, e.g. builtin:download
+    pub script: Option<String>,
+    #[structopt(flatten)]
+    pub download: DownloadArgs,
+    #[structopt(long)]
+    /// Set the filter's name
+    pub new_name: Option<String>,
+    #[structopt(long)]
+    /// Run the script at most N times per update
+    pub max_actions: Option<usize>,
+    #[structopt(long)]
+    /// Remove the limit on how many times the script is run per update
+    pub no_max_actions: bool,
+    #[structopt(long)]
+    /// Put the filter in this dedup group
+    pub dedup_group: Option<String>,
+    #[structopt(long)]
+    /// Take the filter out of its dedup group
+    pub no_dedup_group: bool,
+    #[structopt(long, allow_hyphen_values = true)]
+    /// Set the filter's priority. Filters with a higher priority are run first
+    pub priority: Option<i64>,
+    #[structopt(long)]
+    /// Make lower-priority filters on the feed skip entries this filter matches
+    pub stop: bool,
+    #[structopt(long)]
+    /// Stop making lower-priority filters skip entries this filter matches
+    pub no_stop: bool,
+}
+
+// -- Disable
+
+#[derive(Debug, StructOpt)]
+struct DisableArg {
+    /// Disable a feed or filter in the database.
+    #[structopt(subcommand)]
+    pub cmd: DisableSubArg,
+}
+
+#[derive(Debug, StructOpt)]
+enum DisableSubArg {
+    #[structopt(name = "feed")]
+    /// Stop downloading a feed and running its filters
+    Feed(DisableFeed),
+
+    #[structopt(name = "filter")]
+    /// Stop running a filter's script, keeping the time it was last updated
+    Filter(DisableFilter),
+}
+
+#[derive(Debug, StructOpt)]
+struct DisableFeed {
+    /// The alias of the feed to disable
+    pub alias: String,
+    #[structopt(flatten)]
+    pub disabled: DisabledArgs,
+}
+
+#[derive(Debug, StructOpt)]
+struct DisableFilter {
+    #[structopt(flatten)]
+    pub filter: FilterSelectorArgs,
+    #[structopt(flatten)]
+    pub disabled: DisabledArgs,
+}
+
+#[derive(Debug, StructOpt)]
+struct DisabledArgs {
+    #[structopt(long)]
+    /// A note on why it's disabled, shown by `list`
+    pub reason: Option<String>,
+    #[structopt(long, parse(try_from_str = parse_time))]
+    /// Enable it again automatically at this time, either an RFC 3339 date and time or a date
+    /// (YYYY-MM-DD) meaning midnight local time
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl DisabledArgs {
+    fn into_disabled(self) -> Disabled {
+        Disabled { reason: self.reason, until: self.until }
+    }
+}
+
+fn parse_time(s: &str) -> Result<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.into());
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|_| anyhow!("`{}` is not an RFC 3339 date and time or a YYYY-MM-DD date", s))?;
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map(|dt| dt.into())
+        .ok_or_else(|| anyhow!("Midnight on {} does not exist in the local timezone", date))
+}
+
+// -- Tag
+
+#[derive(Debug, StructOpt)]
+struct TagArg {
+    /// Add tags to a feed in the database.
+    #[structopt(subcommand)]
+    pub cmd: TagSubArg,
+}
+
+#[derive(Debug, StructOpt)]
+enum TagSubArg {
+    #[structopt(name = "feed")]
+    /// Add tags to a feed
+    Feed(FeedTags),
+}
+
+#[derive(Debug, StructOpt)]
+struct UntagArg {
+    /// Remove tags from a feed in the database.
+    #[structopt(subcommand)]
+    pub cmd: UntagSubArg,
+}
+
+#[derive(Debug, StructOpt)]
+enum UntagSubArg {
+    #[structopt(name = "feed")]
+    /// Remove tags from a feed
+    Feed(FeedTags),
+}
+
+#[derive(Debug, StructOpt)]
+struct FeedTags {
+    /// The alias of the feed
+    pub alias: String,
+    #[structopt(required = true)]
+    /// The tags
+    pub tags: Vec<String>,
+}
+
+// -- Enable
+
+#[derive(Debug, StructOpt)]
+struct EnableArg {
+    /// Enable a feed or filter in the database.
+    #[structopt(subcommand)]
+    pub cmd: EnableSubArg,
+}
+
+#[derive(Debug, StructOpt)]
+enum EnableSubArg {
+    #[structopt(name = "feed")]
+    /// Start downloading a disabled feed again
+    Feed(EnableFeed),
+
+    #[structopt(name = "filter")]
+    /// Start running a disabled filter's script again
+    Filter(EnableFilter),
+}
+
+#[derive(Debug, StructOpt)]
+struct EnableFeed {
+    /// The alias of the feed to enable
+    pub alias: String,
+}
+
+#[derive(Debug, StructOpt)]
+struct EnableFilter {
+    #[structopt(flatten)]
+    pub filter: FilterSelectorArgs,
 }
 
 // -- List args
@@ -157,6 +556,139 @@ enum ListSubArg {
     Filters
 }
 
+// -- Replay args
+
+#[derive(Debug, StructOpt)]
+struct ReplayArg {
+    #[structopt(flatten)]
+    pub filter: FilterSelectorArgs,
+    #[structopt(long, parse(try_from_str = parse_time))]
+    /// Replay matching entries published at or after this time, either an RFC 3339 date and time
+    /// or a date (YYYY-MM-DD) meaning midnight local time
+    pub since: Option<DateTime<Utc>>,
+    #[structopt(long)]
+    /// Replay the newest N matching entries
+    pub last: Option<usize>,
+    #[structopt(long)]
+    /// Show the results of earlier replays of the filter instead of replaying it
+    pub history: bool,
+}
+
+// -- Show args
+
+#[derive(Debug, StructOpt)]
+struct ShowArg {
+    /// Download a feed and show its entries.
+    #[structopt(subcommand)]
+    pub cmd: ShowSubArg,
+}
+
+#[derive(Debug, StructOpt)]
+enum ShowSubArg {
+    #[structopt(name = "feed")]
+    /// Show a feed's current entries with their dates, GUIDs and enclosures, entries that update
+    /// would reject, and which filters match each entry
+    Feed(ShowFeed),
+}
+
+#[derive(Debug, StructOpt)]
+struct ShowFeed {
+    /// The alias of the feed to show
+    pub alias: String,
+}
+
+// -- Update args
+
+#[derive(Debug, StructOpt)]
+struct UpdateArg {
+    #[structopt(long = "feed")]
+    /// Only download this feed and run its filters. Can be given more than once
+    pub feeds: Vec<String>,
+    #[structopt(long = "filter")]
+    /// Only run the filter with this id, as shown by `list filters`. Can be given more than once
+    pub filters: Vec<usize>,
+    #[structopt(long = "tag")]
+    /// Only download feeds with this tag and run their filters. Can be given more than once
+    pub tags: Vec<String>,
+}
+
+// -- Test args
+
+#[derive(Debug, StructOpt)]
+struct TestArg {
+    /// Try out a filter without running its script.
+    #[structopt(subcommand)]
+    pub cmd: TestSubArg,
+}
+
+#[derive(Debug, StructOpt)]
+enum TestSubArg {
+    #[structopt(name = "filter")]
+    /// Show which of a feed's entries a filter matches and which of them are new, without running
+    /// the filter's script or changing the database
+    Filter(TestFilter),
+}
+
+#[derive(Debug, StructOpt)]
+struct TestFilter {
+    /// The alias of the feed to test keywords on
+    pub alias: Option<String>,
+    /// The keywords to test. These don't have to belong to an existing filter.
+    pub keywords: Vec<String>,
+    #[structopt(long)]
+    /// Test an existing filter by id instead, as shown by `list filters`
+    pub id: Option<usize>,
+    #[structopt(long)]
+    /// Test an existing filter by name instead
+    pub name: Option<String>,
+    #[structopt(long)]
+    /// Read the feed from a local file instead of its url
+    pub from_file: Option<PathBuf>,
+}
+
+// -- Export and import args
+
+#[derive(Debug, StructOpt)]
+struct ExportArg {
+    #[structopt(long, short = "o")]
+    /// Write the export to this file instead of stdout
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+struct ImportArg {
+    /// The export file to read, or - to read from stdin
+    pub path: PathBuf,
+    #[structopt(long)]
+    /// Delete all feeds and filters before importing, instead of merging the export into the
+    /// database
+    pub replace: bool,
+}
+
+// -- Db args
+
+#[derive(Debug, StructOpt)]
+struct DbArg {
+    /// Manage the database.
+    #[structopt(subcommand)]
+    pub cmd: DbSubArg,
+}
+
+#[derive(Debug, StructOpt)]
+enum DbSubArg {
+    #[structopt(name = "migrate")]
+    /// Update the database to the current schema version. This is also done automatically by
+    /// every other command.
+    Migrate(DbMigrate),
+}
+
+#[derive(Debug, StructOpt)]
+struct DbMigrate {
+    #[structopt(long)]
+    /// Only show the database's schema version and the migrations it needs
+    pub check: bool,
+}
+
 impl RSSActionsArgs {
     pub fn get_cfg_dir(&self) -> Option<&Path> {
         self.config_dir.as_ref().map(Path::new)
@@ -169,22 +701,139 @@ impl RSSActionsArgs {
                     AddSubArg::Feed(feed_args) => {
                         let url = Url::parse(&feed_args.url)?;
                         let feed = Feed::new(url, &feed_args.alias)?;
-                        Box::new(crate::commands::AddFeedCmd(feed))
+                        Box::new(crate::commands::AddFeedWithDiscoveryCmd {
+                            feed,
+                            discover: !feed_args.no_discover,
+                            validate: feed_args.validate,
+                        })
                     },
                     AddSubArg::Filter(filter_args) => {
-                        let path = PathBuf::from(filter_args.script_path);
-                        let filter = Filter::new(&filter_args.alias, filter_args.keywords, path)?;
-                        Box::new(crate::commands::AddFilterCmd(filter))
+                        let alias = if filter_args.tag { "" } else { filter_args.alias.as_str() };
+                        let mut filter = match parse_filter_action(&filter_args.script_path, filter_args.download)? {
+                            FilterAction::Script(path) => Filter::new(alias, filter_args.keywords, path)?,
+                            FilterAction::Builtin(action) => Filter::with_action(alias, filter_args.keywords, action)?,
+                        };
+                        if filter_args.tag {
+                            filter.tag = Some(filter_args.alias);
+                        }
+                        filter.name = filter_args.name;
+                        filter.max_actions_per_run = filter_args.max_actions;
+                        filter.dedup_group = filter_args.dedup_group;
+                        filter.priority = filter_args.priority.unwrap_or_default();
+                        filter.stop = filter_args.stop;
+                        let start = match (filter_args.from, filter_args.backlog) {
+                            (Some(_), Some(_)) => return Err(anyhow!("Only one of --from or --backlog can be given.")),
+                            (from, backlog) => from.or(backlog.map(FilterStart::Backlog)),
+                        };
+                        match start {
+                            Some(start) => Box::new(crate::commands::AddFilterWithStartCmd { filter, start }),
+                            None => Box::new(crate::commands::AddFilterCmd(filter)),
+                        }
                     }
                 }
             }
             SubArg::Delete(delete_args) => {
                 match delete_args.cmd {
                     DeleteSubArg::Feed(feed_args) => {
-                        Box::new(crate::commands::DeleteFeedCmd(feed_args.alias))
+                        if feed_args.cascade {
+                            Box::new(crate::commands::DeleteFeedCascadeCmd(feed_args.alias))
+                        }
+                        else {
+                            Box::new(crate::commands::DeleteFeedCmd(feed_args.alias))
+                        }
                     },
                     DeleteSubArg::Filter(filter_args) => {
-                        Box::new(crate::commands::DeleteFilterCmd(filter_args.alias, filter_args.keywords))
+                        Box::new(crate::commands::DeleteSelectedFilterCmd(filter_args.filter.into_selector()?))
+                    }
+                }
+            }
+            SubArg::Rename(rename_args) => {
+                match rename_args.cmd {
+                    RenameSubArg::Feed(feed_args) => {
+                        Box::new(crate::commands::RenameFeedCmd(feed_args.alias, feed_args.new_alias))
+                    }
+                }
+            }
+            SubArg::Edit(edit_args) => {
+                match edit_args.cmd {
+                    EditSubArg::Feed(feed_args) => {
+                        let url = Url::parse(&feed_args.url)?;
+                        Box::new(crate::commands::EditFeedCmd(feed_args.alias, url))
+                    },
+                    EditSubArg::Filter(filter_args) => {
+                        let new_max_actions = match (filter_args.max_actions, filter_args.no_max_actions) {
+                            (Some(_), true) => return Err(anyhow!("Only one of --max-actions or --no-max-actions can be given.")),
+                            (Some(max_actions), false) => Some(Some(max_actions)),
+                            (None, true) => Some(None),
+                            (None, false) => None,
+                        };
+                        let new_dedup_group = match (filter_args.dedup_group, filter_args.no_dedup_group) {
+                            (Some(_), true) => return Err(anyhow!("Only one of --dedup-group or --no-dedup-group can be given.")),
+                            (Some(dedup_group), false) => Some(Some(dedup_group)),
+                            (None, true) => Some(None),
+                            (None, false) => None,
+                        };
+                        let (new_script_path, new_action) = match filter_args.script {
+                            Some(script) => match parse_filter_action(&script, filter_args.download)? {
+                                FilterAction::Script(path) => (Some(path), None),
+                                FilterAction::Builtin(action) => (None, Some(action)),
+                            },
+                            None if !filter_args.download.is_empty() =>
+                                return Err(anyhow!("--download-dir, --path-template and --max-size can only be given with --script builtin:download.")),
+                            None => (None, None),
+                        };
+                        let new_stop = match (filter_args.stop, filter_args.no_stop) {
+                            (true, true) => return Err(anyhow!("Only one of --stop or --no-stop can be given.")),
+                            (true, false) => Some(true),
+                            (false, true) => Some(false),
+                            (false, false) => None,
+                        };
+                        Box::new(crate::commands::EditFilterCmd {
+                            filter: filter_args.filter.into_selector()?,
+                            new_keywords: filter_args.new_keywords,
+                            new_script_path,
+                            new_action,
+                            new_name: filter_args.new_name,
+                            new_max_actions,
+                            new_dedup_group,
+                            new_priority: filter_args.priority,
+                            new_stop,
+                        })
+                    }
+                }
+            }
+            SubArg::Disable(disable_args) => {
+                match disable_args.cmd {
+                    DisableSubArg::Feed(feed_args) => {
+                        Box::new(crate::commands::DisableFeedCmd(feed_args.alias, feed_args.disabled.into_disabled()))
+                    },
+                    DisableSubArg::Filter(filter_args) => {
+                        Box::new(crate::commands::DisableFilterCmd(filter_args.filter.into_selector()?,
+                            filter_args.disabled.into_disabled()))
+                    }
+                }
+            }
+            SubArg::Enable(enable_args) => {
+                match enable_args.cmd {
+                    EnableSubArg::Feed(feed_args) => {
+                        Box::new(crate::commands::EnableFeedCmd(feed_args.alias))
+                    },
+                    EnableSubArg::Filter(filter_args) => {
+                        Box::new(crate::commands::EnableFilterCmd(filter_args.filter.into_selector()?))
+                    }
+                }
+            }
+            SubArg::Tag(tag_args) => {
+                match tag_args.cmd {
+                    TagSubArg::Feed(feed_args) => {
+                        Box::new(crate::commands::TagFeedCmd(feed_args.alias, feed_args.tags))
+                    }
+                }
+            }
+            SubArg::Untag(untag_args) => {
+                match untag_args.cmd {
+                    UntagSubArg::Feed(feed_args) => {
+                        Box::new(crate::commands::UntagFeedCmd(feed_args.alias, feed_args.tags))
                     }
                 }
             }
@@ -194,8 +843,60 @@ impl RSSActionsArgs {
                     ListSubArg::Filters => Box::new(crate::commands::ListFiltersCmd),
                 }
             },
-            SubArg::Update => {
-                Box::new(crate::commands::UpdateCmd)
+            SubArg::Replay(replay_args) => {
+                let selector = replay_args.filter.into_selector()?;
+                let window = match (replay_args.since, replay_args.last, replay_args.history) {
+                    (None, None, true) => return Ok(Box::new(crate::commands::ReplayHistoryCmd(selector))),
+                    (Some(since), None, false) => ReplayWindow::Since(since),
+                    (None, Some(last), false) => ReplayWindow::Last(last),
+                    _ => return Err(anyhow!("Give exactly one of --since, --last or --history.")),
+                };
+                Box::new(crate::commands::ReplayCmd(selector, window))
+            }
+            SubArg::Show(show_args) => {
+                match show_args.cmd {
+                    ShowSubArg::Feed(feed_args) => Box::new(crate::commands::ShowFeedCmd(feed_args.alias)),
+                }
+            }
+            SubArg::Test(test_args) => {
+                match test_args.cmd {
+                    TestSubArg::Filter(filter_args) => {
+                        let selector = FilterSelectorArgs {
+                            alias: filter_args.alias,
+                            keywords: filter_args.keywords,
+                            id: filter_args.id,
+                            name: filter_args.name,
+                        }.into_selector()?;
+                        Box::new(crate::commands::TestFilterCmd { filter: selector, from_file: filter_args.from_file })
+                    }
+                }
+            }
+            SubArg::Export(export_args) => {
+                Box::new(crate::commands::ExportCmd(export_args.output))
+            }
+            SubArg::Import(import_args) => {
+                let mode = if import_args.replace { ImportMode::Replace } else { ImportMode::Merge };
+                Box::new(crate::commands::ImportCmd(import_args.path, mode))
+            }
+            SubArg::Db(db_args) => {
+                match db_args.cmd {
+                    DbSubArg::Migrate(migrate_args) => {
+                        Box::new(crate::commands::DbMigrateCmd { check: migrate_args.check })
+                    }
+                }
+            }
+            SubArg::Update(update_args) => {
+                let selection = UpdateSelection {
+                    feeds: update_args.feeds,
+                    filters: update_args.filters.into_iter().map(FilterId).collect(),
+                    tags: update_args.tags,
+                };
+                if selection.is_all() {
+                    Box::new(crate::commands::UpdateCmd)
+                }
+                else {
+                    Box::new(crate::commands::UpdateSelectedCmd(selection))
+                }
             }
         };
 
@@ -1,10 +1,36 @@
-use crate::{Feed, Filter};
+use std::path::PathBuf;
+
+use url::Url;
+
+use crate::{BuiltinAction, Disabled, Feed, Filter, FilterSelector, FilterStart, ImportMode, ReplayWindow, UpdateSelection};
 
 pub struct ListFeedsCmd;
 pub struct ListFiltersCmd;
 pub struct AddFeedCmd(pub Feed);
+/// Adds a feed after checking its url. If the url is a web page rather than a feed, the feed
+/// linked from the page is added instead.
+pub struct AddFeedWithDiscoveryCmd {
+    pub feed: Feed,
+    /// Look for a feed linked from the url if it is a web page.
+    pub discover: bool,
+    /// Download and parse the feed before adding it.
+    pub validate: bool,
+}
 pub struct AddFilterCmd(pub Filter);
+/// Adds a filter that starts processing the feed's entries from `start` rather than running its
+/// script on every matching entry already in the feed.
+pub struct AddFilterWithStartCmd {
+    pub filter: Filter,
+    pub start: FilterStart,
+}
 pub struct UpdateCmd;
+/// Runs update on only the selected feeds and filters.
+pub struct UpdateSelectedCmd(pub UpdateSelection);
+/// Runs a filter's script again on matching entries in the window, e.g. after fixing the script,
+/// without changing the filter's last updated time.
+pub struct ReplayCmd(pub FilterSelector, pub ReplayWindow);
+/// Lists the stored results of replaying a filter.
+pub struct ReplayHistoryCmd(pub FilterSelector);
 /// Feed alias, filter keywords to match on
 pub struct DeleteFilterCmd(pub String, pub Vec<String>);
 impl DeleteFilterCmd {
@@ -12,5 +38,63 @@ impl DeleteFilterCmd {
         DeleteFilterCmd(alias.into(), filters.iter().map(|&s| s.into()).collect())
     }
 }
+/// Deletes a single filter referred to by id, name, or feed alias and keywords.
+pub struct DeleteSelectedFilterCmd(pub FilterSelector);
 /// Feed alias
 pub struct DeleteFeedCmd(pub String);
+/// Feed alias. Deletes the feed's filters along with it.
+pub struct DeleteFeedCascadeCmd(pub String);
+/// Feed alias, new alias
+pub struct RenameFeedCmd(pub String, pub String);
+/// Feed alias, new url
+pub struct EditFeedCmd(pub String, pub Url);
+/// Changes the keywords, script and/or name of a filter, keeping its last updated time.
+pub struct EditFilterCmd {
+    pub filter: FilterSelector,
+    pub new_keywords: Option<Vec<String>>,
+    /// A new script, replacing the filter's built-in action if it has one.
+    pub new_script_path: Option<PathBuf>,
+    /// A new built-in action, replacing the filter's script.
+    pub new_action: Option<BuiltinAction>,
+    pub new_name: Option<String>,
+    /// A new limit on script runs per update, or `Some(None)` to remove the limit.
+    pub new_max_actions: Option<Option<usize>>,
+    /// A new dedup group, or `Some(None)` to take the filter out of its group.
+    pub new_dedup_group: Option<Option<String>>,
+    pub new_priority: Option<i64>,
+    pub new_stop: Option<bool>,
+}
+/// Feed alias, and why and until when it's disabled
+pub struct DisableFeedCmd(pub String, pub Disabled);
+/// Feed alias
+pub struct EnableFeedCmd(pub String);
+/// Feed alias, tags to add to it
+pub struct TagFeedCmd(pub String, pub Vec<String>);
+/// Feed alias, tags to remove from it
+pub struct UntagFeedCmd(pub String, pub Vec<String>);
+/// Disables a single filter, keeping its last updated time.
+pub struct DisableFilterCmd(pub FilterSelector, pub Disabled);
+pub struct EnableFilterCmd(pub FilterSelector);
+/// Brings the database's schema up to date, or only shows which migrations are pending if `check`
+/// is set.
+pub struct DbMigrateCmd {
+    pub check: bool,
+}
+/// Writes all feeds and filters as JSON to the path, or returns the document if there is none.
+pub struct ExportCmd(pub Option<PathBuf>);
+/// Reads feeds and filters written by `ExportCmd` from the path, or stdin if it's `-`.
+pub struct ImportCmd(pub PathBuf, pub ImportMode);
+/// Shows which entries of a feed a filter matches and which of those are new, without running
+/// its script or changing the database.
+pub struct TestFilterCmd {
+    /// An existing filter by id or name, or a feed alias and keywords to try out a filter that
+    /// hasn't been added yet. Keywords aren't looked up, so they don't need to match an existing
+    /// filter.
+    pub filter: FilterSelector,
+    /// Read the feed from this file instead of the feed's url. The feed doesn't need to have been
+    /// added yet in that case.
+    pub from_file: Option<PathBuf>,
+}
+/// Downloads a feed the same way `update` does and shows its entries, which of them `update`
+/// would reject, and which filters match them, without running any scripts.
+pub struct ShowFeedCmd(pub String);
@@ -1,6 +1,10 @@
-use anyhow::Result;
+use std::path::PathBuf;
 
-use crate::db::{RSSActionsDb, RSSActionsTx};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+
+use crate::{Feed, FeedEntry, Filter, FilterSelector, FilterStart, ReplayWindow};
+use crate::db::{RSSActionsDb, RSSActionsTx, SCHEMA_VERSION};
 use crate::config::Config;
 
 pub mod inputs;
@@ -15,24 +19,33 @@ pub trait RSSActionCmd {
     /// Executes the command, opening the database and returning the output details object of the
     /// executed command.
     fn execute(&self, cfg: &Config) -> Result<Self::CmdOutput> {
-        let mut db = RSSActionsDb::open(&cfg.db_path)?;
-        let mut tx = db.transaction()?;
+        with_transaction(cfg, |tx| self.action(cfg, tx))
+    }
 
-        let result = self.action(&mut tx);
+    fn action(&self, cfg: &Config, tx: &mut RSSActionsTx) -> Result<Self::CmdOutput>;
+}
 
-        if result.is_ok() {
-            tx.commit()?;
-        }
+/// Open the database and run `f` in a transaction, which is committed if `f` succeeds.
+///
+/// Commands that download feeds override `execute` to read what they need in one transaction and
+/// write in another, so that the database isn't locked while they wait on the network and an
+/// update running at the same time can still save its progress.
+fn with_transaction<T>(cfg: &Config, f: impl FnOnce(&mut RSSActionsTx) -> Result<T>) -> Result<T> {
+    let mut db = RSSActionsDb::open(&cfg.db_path)?;
+    let mut tx = db.transaction()?;
+
+    let result = f(&mut tx);
 
-        result
+    if result.is_ok() {
+        tx.commit()?;
     }
 
-    fn action(&self, tx: &mut RSSActionsTx) -> Result<Self::CmdOutput>;
+    result
 }
 
 impl RSSActionCmd for ListFeedsCmd {
     type CmdOutput = ListFeedsOutput;
-    fn action(&self, tx: &mut RSSActionsTx) -> Result<ListFeedsOutput> {
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<ListFeedsOutput> {
         let feeds = tx.fetch_feeds()?;
 
         Ok(ListFeedsOutput { feeds })
@@ -41,7 +54,7 @@ impl RSSActionCmd for ListFeedsCmd {
 
 impl RSSActionCmd for AddFeedCmd {
     type CmdOutput = AddFeedOutput;
-    fn action(&self, tx: &mut RSSActionsTx) -> Result<AddFeedOutput> {
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<AddFeedOutput> {
         let feed = &self.0;
         tx.store_feed(&feed.alias, &feed.url)?;
 
@@ -49,9 +62,74 @@ impl RSSActionCmd for AddFeedCmd {
     }
 }
 
+impl RSSActionCmd for AddFeedWithDiscoveryCmd {
+    type CmdOutput = AddFeedWithDiscoveryOutput;
+
+    /// The url is checked before the transaction that adds the feed. See `with_transaction`.
+    fn execute(&self, cfg: &Config) -> Result<AddFeedWithDiscoveryOutput> {
+        let mut feed = self.feed.clone();
+        let mut discovered_from = None;
+        let mut other_feeds = Vec::new();
+
+        // Feeds read with an html or json source are expected to be web pages or not RSS.
+        let feed_cfg = cfg.feed_config(&feed.alias);
+        let has_source = feed_cfg.html.is_some() || feed_cfg.json.is_some();
+
+        if self.discover && !has_source {
+            let linked_feeds = crate::fetch::discover_feeds(cfg, &feed)
+                .with_context(|| format!("Could not check the url {} of feed {}", feed.url, feed.alias))?;
+
+            if let Some(linked_feeds) = linked_feeds {
+                let (supported, unsupported): (Vec<_>, Vec<_>) = linked_feeds.into_iter()
+                    .partition(|linked| linked.format == FeedFormat::Rss);
+
+                let mut supported = supported.into_iter();
+                let chosen = match supported.next() {
+                    Some(chosen) => chosen,
+                    None if unsupported.is_empty() => {
+                        return Err(anyhow!("{} is not an RSS feed and does not link to any feeds.", feed.url));
+                    }
+                    None => {
+                        let found: Vec<_> = unsupported.iter()
+                            .map(|linked| format!("{} ({})", linked.url, linked.format))
+                            .collect();
+                        return Err(anyhow!("{} is not an RSS feed and only links to feeds in unsupported formats: {}",
+                            feed.url, found.join(", ")));
+                    }
+                };
+
+                discovered_from = Some(feed.url.clone());
+                feed = Feed::new(chosen.url, &feed.alias)?;
+                other_feeds = supported.collect();
+            }
+        }
+
+        let validation = if self.validate {
+            let fetched = crate::fetch::fetch_feed(cfg, &feed)?;
+            let entries = fetched.entries.len();
+            let errors = fetched.entries.iter().filter(|res| res.is_err()).count();
+            if let Some(err) = fetched.entries.into_iter().find_map(Result::err) {
+                return Err(err.context(format!("{} entries in feed {} had data errors", errors, feed.alias)));
+            }
+            Some(FeedValidation { title: fetched.title, entries })
+        }
+        else {
+            None
+        };
+
+        with_transaction(cfg, |tx| tx.store_feed(&feed.alias, &feed.url))?;
+
+        Ok(AddFeedWithDiscoveryOutput { feed, discovered_from, other_feeds, validation })
+    }
+
+    fn action(&self, _cfg: &Config, _tx: &mut RSSActionsTx) -> Result<AddFeedWithDiscoveryOutput> {
+        unreachable!("AddFeedWithDiscoveryCmd overrides execute")
+    }
+}
+
 impl RSSActionCmd for ListFiltersCmd {
     type CmdOutput = ListFiltersOutput;
-    fn action(&self, tx: &mut RSSActionsTx) -> Result<ListFiltersOutput> {
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<ListFiltersOutput> {
         let filters = tx.fetch_filters()?;
 
         Ok(ListFiltersOutput { filters })
@@ -60,24 +138,81 @@ impl RSSActionCmd for ListFiltersCmd {
 
 impl RSSActionCmd for AddFilterCmd {
     type CmdOutput = AddFilterOutput;
-    fn action(&self, tx: &mut RSSActionsTx) -> Result<AddFilterOutput> {
-        let filter = &self.0;
-        tx.store_filter(filter)?;
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<AddFilterOutput> {
+        let mut filter = self.0.clone();
+        filter.validate()?;
+        filter.id = Some(tx.store_filter(&filter)?);
 
-        Ok(AddFilterOutput(filter.clone()))
+        Ok(AddFilterOutput(filter))
+    }
+}
+
+impl RSSActionCmd for AddFilterWithStartCmd {
+    type CmdOutput = AddFilterOutput;
+
+    /// The feed is downloaded before the transaction that adds the filter. See `with_transaction`.
+    fn execute(&self, cfg: &Config) -> Result<AddFilterOutput> {
+        let mut filter = self.filter.clone();
+        if filter.tag.is_some() {
+            return Err(anyhow!("A filter on a tag can't start partway through its feeds."));
+        }
+        let entries = match self.start {
+            FilterStart::Date(_) => Vec::new(),
+            FilterStart::Now | FilterStart::Backlog(_) => {
+                let feed = with_transaction(cfg, |tx| {
+                    tx.fetch_feeds()?.into_iter()
+                        .find(|feed| feed.alias == filter.alias)
+                        .ok_or_else(|| anyhow!("Couldn't find a feed with alias {}.", filter.alias))
+                })?;
+                let fetched = crate::fetch::fetch_feed(cfg, &feed)
+                    .context("Could not read the feed to find where the filter starts")?;
+                // Entries with missing data are never processed by update, so they can be ignored.
+                fetched.entries.into_iter().filter_map(Result::ok).collect()
+            }
+        };
+        filter.last_updated = filter.start_time(self.start, Utc::now(), &entries);
+
+        AddFilterCmd(filter).execute(cfg)
+    }
+
+    fn action(&self, _cfg: &Config, _tx: &mut RSSActionsTx) -> Result<AddFilterOutput> {
+        unreachable!("AddFilterWithStartCmd overrides execute")
     }
 }
 
 impl RSSActionCmd for UpdateCmd {
     type CmdOutput = UpdateOutput;
-    fn action(&self, tx: &mut RSSActionsTx) -> Result<UpdateOutput> {
-        crate::update::update(tx)
+
+    /// Update commits each filter's progress separately rather than running in one transaction.
+    /// The update lock is held until it's done, so that another update can't read the filters'
+    /// last updated times before they're saved.
+    fn execute(&self, cfg: &Config) -> Result<UpdateOutput> {
+        UpdateSelectedCmd(Default::default()).execute(cfg)
+    }
+
+    fn action(&self, _cfg: &Config, _tx: &mut RSSActionsTx) -> Result<UpdateOutput> {
+        unreachable!("UpdateCmd overrides execute")
+    }
+}
+
+impl RSSActionCmd for UpdateSelectedCmd {
+    type CmdOutput = UpdateOutput;
+
+    /// See `UpdateCmd::execute`.
+    fn execute(&self, cfg: &Config) -> Result<UpdateOutput> {
+        let _lock = crate::update::UpdateLock::acquire(&cfg.db_path)?;
+        let mut db = RSSActionsDb::open(&cfg.db_path)?;
+        crate::update::update(cfg, &mut db, &self.0)
+    }
+
+    fn action(&self, _cfg: &Config, _tx: &mut RSSActionsTx) -> Result<UpdateOutput> {
+        unreachable!("UpdateSelectedCmd overrides execute")
     }
 }
 
 impl RSSActionCmd for DeleteFilterCmd {
     type CmdOutput = DeleteFilterOutput;
-    fn action(&self, tx: &mut RSSActionsTx) -> Result<DeleteFilterOutput> {
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<DeleteFilterOutput> {
         let alias = &self.0;
         let keywords = &self.1;
         tx.delete_filter(alias, keywords)?;
@@ -86,12 +221,439 @@ impl RSSActionCmd for DeleteFilterCmd {
     }
 }
 
+impl RSSActionCmd for DeleteSelectedFilterCmd {
+    type CmdOutput = DeleteSelectedFilterOutput;
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<DeleteSelectedFilterOutput> {
+        let filter = tx.delete_selected_filter(&self.0)?;
+
+        Ok(DeleteSelectedFilterOutput(filter))
+    }
+}
+
 impl RSSActionCmd for DeleteFeedCmd {
     type CmdOutput = DeleteFeedOutput;
-    fn action(&self, tx: &mut RSSActionsTx) -> Result<DeleteFeedOutput> {
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<DeleteFeedOutput> {
         let alias = &self.0;
         tx.delete_feed(alias)?;
 
         Ok(DeleteFeedOutput(alias.clone()))
     }
 }
+
+impl RSSActionCmd for DeleteFeedCascadeCmd {
+    type CmdOutput = DeleteFeedCascadeOutput;
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<DeleteFeedCascadeOutput> {
+        let alias = &self.0;
+        let filters = tx.delete_feed_cascade(alias)?;
+
+        Ok(DeleteFeedCascadeOutput(alias.clone(), filters))
+    }
+}
+
+impl RSSActionCmd for RenameFeedCmd {
+    type CmdOutput = RenameFeedOutput;
+    fn action(&self, cfg: &Config, tx: &mut RSSActionsTx) -> Result<RenameFeedOutput> {
+        let alias = &self.0;
+        let new_alias = &self.1;
+        if new_alias.is_empty() {
+            return Err(anyhow!("A feed's alias must not be empty."));
+        }
+        // The feed would be parsed as RSS under its new alias.
+        let feed_cfg = cfg.feed_config(alias);
+        let source = if feed_cfg.html.is_some() {
+            Some("html")
+        }
+        else if feed_cfg.json.is_some() {
+            Some("json")
+        }
+        else {
+            None
+        };
+        if let Some(source) = source {
+            return Err(anyhow!("Feed {} is read with the {} source in the [feeds.{}] section of the config file. \
+                Rename the section to [feeds.{}] first, so that the renamed feed is still read with it.",
+                alias, source, alias, new_alias));
+        }
+        tx.rename_feed(alias, new_alias)?;
+
+        Ok(RenameFeedOutput {
+            old_alias: alias.clone(),
+            new_alias: new_alias.clone(),
+            has_feed_config: cfg.feeds.contains_key(alias),
+        })
+    }
+}
+
+impl RSSActionCmd for EditFeedCmd {
+    type CmdOutput = EditFeedOutput;
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<EditFeedOutput> {
+        let feed = Feed::new(self.1.clone(), &self.0)?;
+        tx.edit_feed_url(&feed.alias, &feed.url)?;
+
+        Ok(EditFeedOutput(feed))
+    }
+}
+
+impl RSSActionCmd for EditFilterCmd {
+    type CmdOutput = EditFilterOutput;
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<EditFilterOutput> {
+        if self.new_keywords.is_none() && self.new_script_path.is_none() && self.new_action.is_none()
+                && self.new_name.is_none() && self.new_max_actions.is_none() && self.new_dedup_group.is_none()
+                && self.new_priority.is_none() && self.new_stop.is_none() {
+            return Err(anyhow!("Nothing to edit. Give new keywords, a new script or built-in action, a new name, a new action limit, \
+                a new dedup group, a new priority, or whether the filter stops lower-priority filters."));
+        }
+        if let Some(script_path) = &self.new_script_path {
+            crate::validate_script_path(script_path)?;
+        }
+        if let Some(action) = &self.new_action {
+            if self.new_script_path.is_some() {
+                return Err(anyhow!("A filter can run either a script or a built-in action, not both."));
+            }
+            action.validate()?;
+        }
+        if let Some(name) = &self.new_name {
+            crate::validate_filter_name(name)?;
+        }
+
+        if self.new_max_actions == Some(Some(0)) {
+            return Err(anyhow!("The limit on script runs per update must be at least 1."));
+        }
+        if let Some(Some(dedup_group)) = &self.new_dedup_group {
+            crate::validate_dedup_group(dedup_group)?;
+        }
+
+        let filter = tx.edit_filter(&self.filter, |filter| {
+            if let Some(new_keywords) = &self.new_keywords {
+                filter.keywords = new_keywords.clone();
+            }
+            if let Some(new_script_path) = &self.new_script_path {
+                filter.script_path = new_script_path.clone();
+                filter.action = None;
+            }
+            if let Some(new_action) = &self.new_action {
+                filter.script_path = PathBuf::new();
+                filter.action = Some(new_action.clone());
+            }
+            if let Some(new_name) = &self.new_name {
+                filter.name = Some(new_name.clone());
+            }
+            if let Some(new_max_actions) = self.new_max_actions {
+                filter.max_actions_per_run = new_max_actions;
+            }
+            if let Some(new_dedup_group) = &self.new_dedup_group {
+                filter.dedup_group = new_dedup_group.clone();
+            }
+            if let Some(new_priority) = self.new_priority {
+                filter.priority = new_priority;
+            }
+            if let Some(new_stop) = self.new_stop {
+                filter.stop = new_stop;
+            }
+        })?;
+
+        Ok(EditFilterOutput(filter))
+    }
+}
+
+/// Check that a feed or filter isn't being disabled until a time that has already passed, which
+/// would do nothing.
+fn validate_disabled(disabled: &crate::Disabled) -> Result<()> {
+    if !disabled.is_active(Utc::now()) {
+        return Err(anyhow!("The time to disable until has already passed."));
+    }
+    Ok(())
+}
+
+impl RSSActionCmd for DisableFeedCmd {
+    type CmdOutput = DisableFeedOutput;
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<DisableFeedOutput> {
+        validate_disabled(&self.1)?;
+        let feed = tx.set_feed_disabled(&self.0, Some(&self.1))?;
+
+        Ok(DisableFeedOutput(feed))
+    }
+}
+
+impl RSSActionCmd for EnableFeedCmd {
+    type CmdOutput = EnableFeedOutput;
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<EnableFeedOutput> {
+        let feed = tx.set_feed_disabled(&self.0, None)?;
+
+        Ok(EnableFeedOutput(feed))
+    }
+}
+
+impl RSSActionCmd for TagFeedCmd {
+    type CmdOutput = TagFeedOutput;
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<TagFeedOutput> {
+        for tag in &self.1 {
+            crate::validate_tag(tag)?;
+        }
+        let feed = tx.add_feed_tags(&self.0, &self.1)?;
+
+        Ok(TagFeedOutput(feed))
+    }
+}
+
+impl RSSActionCmd for UntagFeedCmd {
+    type CmdOutput = UntagFeedOutput;
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<UntagFeedOutput> {
+        let feed = tx.remove_feed_tags(&self.0, &self.1)?;
+
+        Ok(UntagFeedOutput(feed))
+    }
+}
+
+impl RSSActionCmd for DisableFilterCmd {
+    type CmdOutput = DisableFilterOutput;
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<DisableFilterOutput> {
+        validate_disabled(&self.1)?;
+        let filter = tx.set_filter_disabled(&self.0, Some(&self.1))?;
+
+        Ok(DisableFilterOutput(filter))
+    }
+}
+
+impl RSSActionCmd for EnableFilterCmd {
+    type CmdOutput = EnableFilterOutput;
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<EnableFilterOutput> {
+        let filter = tx.set_filter_disabled(&self.0, None)?;
+
+        Ok(EnableFilterOutput(filter))
+    }
+}
+
+impl RSSActionCmd for DbMigrateCmd {
+    type CmdOutput = DbMigrateOutput;
+
+    /// Opening the database normally would migrate it before the command runs, so this opens it
+    /// without migrating instead.
+    fn execute(&self, cfg: &Config) -> Result<DbMigrateOutput> {
+        if self.check {
+            // Don't create the database just to check it.
+            let from_version = if cfg.db_path.is_file() {
+                RSSActionsDb::open_unmigrated(&cfg.db_path)?.schema_version()?
+            }
+            else {
+                0
+            };
+            return Ok(DbMigrateOutput {
+                check: true,
+                from_version,
+                to_version: SCHEMA_VERSION,
+                migrations: crate::db::pending_migrations(from_version),
+                backup_path: None,
+            });
+        }
+
+        // Migrations rebuild tables, so they can't run while an update is saving filters.
+        let _lock = crate::update::UpdateLock::acquire(&cfg.db_path)?;
+        let mut db = RSSActionsDb::open_unmigrated(&cfg.db_path)?;
+        let migrated = db.migrate(&cfg.db_path)?;
+
+        Ok(DbMigrateOutput {
+            check: false,
+            from_version: migrated.from_version,
+            to_version: SCHEMA_VERSION,
+            migrations: migrated.applied,
+            backup_path: migrated.backup_path,
+        })
+    }
+
+    fn action(&self, _cfg: &Config, _tx: &mut RSSActionsTx) -> Result<DbMigrateOutput> {
+        unreachable!("DbMigrateCmd overrides execute")
+    }
+}
+
+impl RSSActionCmd for ExportCmd {
+    type CmdOutput = ExportOutput;
+    fn action(&self, cfg: &Config, tx: &mut RSSActionsTx) -> Result<ExportOutput> {
+        let document = crate::export::export(cfg, tx)?;
+
+        if let Some(path) = &self.0 {
+            let json = serde_json::to_string_pretty(&document)?;
+            std::fs::write(path, json + "\n")
+                .with_context(|| format!("Could not write the export to {}", path.display()))?;
+        }
+
+        Ok(ExportOutput { document, path: self.0.clone() })
+    }
+}
+
+impl RSSActionCmd for ImportCmd {
+    type CmdOutput = ImportOutput;
+
+    /// Importing holds the update lock, since replacing the database would delete the feeds and
+    /// filters a running update is saving, and merging moves filters' last updated times.
+    fn execute(&self, cfg: &Config) -> Result<ImportOutput> {
+        let path = &self.0;
+        let json = if path.as_os_str() == "-" {
+            std::io::read_to_string(std::io::stdin())
+                .context("Could not read the export from stdin")?
+        }
+        else {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Could not read the export from {}", path.display()))?
+        };
+        let document: crate::ExportDocument = serde_json::from_str(&json)
+            .with_context(|| format!("{} is not an rss-actions export", path.display()))?;
+
+        let _lock = crate::update::UpdateLock::acquire(&cfg.db_path)?;
+        with_transaction(cfg, |tx| crate::export::import(cfg, tx, &document, self.1))
+    }
+
+    fn action(&self, _cfg: &Config, _tx: &mut RSSActionsTx) -> Result<ImportOutput> {
+        unreachable!("ImportCmd overrides execute")
+    }
+}
+
+impl RSSActionCmd for TestFilterCmd {
+    type CmdOutput = TestFilterOutput;
+
+    /// The feed is downloaded after the transaction that reads the filter has ended. See
+    /// `with_transaction`.
+    fn execute(&self, cfg: &Config) -> Result<TestFilterOutput> {
+        let (filter, feed) = with_transaction(cfg, |tx| self.find_filter_and_feed(tx))?;
+
+        let fetched = crate::fetch::fetch_feed(cfg, &feed)?;
+
+        let (entries, errors): (Vec<_>, Vec<_>) = fetched.entries.into_iter().partition(Result::is_ok);
+        let entry_errors = errors.into_iter().filter_map(Result::err).collect();
+        let mut entries: Vec<_> = entries.into_iter().filter_map(Result::ok)
+            .map(|entry| {
+                let entry_match = entry_match(&filter, &entry);
+                (entry, entry_match)
+            })
+            .collect();
+        entries.sort_by_key(|(entry, _)| entry.pub_date);
+
+        Ok(TestFilterOutput { filter, entries, entry_errors })
+    }
+
+    fn action(&self, _cfg: &Config, _tx: &mut RSSActionsTx) -> Result<TestFilterOutput> {
+        unreachable!("TestFilterCmd overrides execute")
+    }
+}
+
+impl TestFilterCmd {
+    /// The filter to test and the feed to read its entries from.
+    fn find_filter_and_feed(&self, tx: &mut RSSActionsTx) -> Result<(Filter, Feed)> {
+        let filter = match &self.filter {
+            FilterSelector::Keywords(alias, keywords) => Filter {
+                id: None,
+                name: None,
+                alias: alias.clone(),
+                tag: None,
+                keywords: keywords.clone(),
+                script_path: Default::default(),
+                action: None,
+                last_updated: None,
+                disabled: None,
+                max_actions_per_run: None,
+                dedup_group: None,
+                priority: 0,
+                stop: false,
+            },
+            selector => tx.find_filter(selector)?,
+        };
+        if let (Some(tag), None) = (&filter.tag, &self.from_file) {
+            return Err(anyhow!("The {} is on the tag `{}`. Use `show feed` to see what it matches on one of \
+                the tag's feeds, or give a feed file with --from-file.", self.filter, tag));
+        }
+
+        let feed = tx.fetch_feeds()?.into_iter()
+            .find(|feed| feed.alias == filter.alias);
+        let feed = match (&self.from_file, feed) {
+            (Some(path), _) => {
+                let path = std::fs::canonicalize(path)
+                    .with_context(|| format!("Could not read the feed file {}", path.display()))?;
+                let url = url::Url::from_file_path(&path)
+                    .map_err(|_| anyhow!("Could not make a file url from {}", path.display()))?;
+                Feed::new(url, &filter.alias)?
+            }
+            (None, Some(feed)) => feed,
+            (None, None) => return Err(anyhow!("Couldn't find a feed with alias {}.", filter.alias)),
+        };
+
+        Ok((filter, feed))
+    }
+}
+
+impl RSSActionCmd for ReplayCmd {
+    type CmdOutput = ReplayOutput;
+
+    /// Like update, replay runs scripts outside of a transaction and holds the update lock so that
+    /// an update can't run the same scripts at the same time.
+    fn execute(&self, cfg: &Config) -> Result<ReplayOutput> {
+        if let ReplayWindow::Last(0) = self.1 {
+            return Err(anyhow!("The number of entries to replay must be at least 1."));
+        }
+        let _lock = crate::update::UpdateLock::acquire(&cfg.db_path)?;
+        let mut db = RSSActionsDb::open(&cfg.db_path)?;
+        crate::update::replay(cfg, &mut db, &self.0, self.1)
+    }
+
+    fn action(&self, _cfg: &Config, _tx: &mut RSSActionsTx) -> Result<ReplayOutput> {
+        unreachable!("ReplayCmd overrides execute")
+    }
+}
+
+impl RSSActionCmd for ReplayHistoryCmd {
+    type CmdOutput = ReplayHistoryOutput;
+    fn action(&self, _cfg: &Config, tx: &mut RSSActionsTx) -> Result<ReplayHistoryOutput> {
+        let filter = tx.find_filter(&self.0)?;
+        let records = tx.fetch_replay_results(filter.id.expect("filters from the db have ids"))?;
+        Ok(ReplayHistoryOutput { filter, records })
+    }
+}
+
+impl RSSActionCmd for ShowFeedCmd {
+    type CmdOutput = ShowFeedOutput;
+
+    /// Like `TestFilterCmd`, the feed is downloaded after the transaction that reads it and its
+    /// filters has ended.
+    fn execute(&self, cfg: &Config) -> Result<ShowFeedOutput> {
+        let (feed, filters) = with_transaction(cfg, |tx| {
+            let feed = tx.fetch_feeds()?.into_iter()
+                .find(|feed| feed.alias == self.0)
+                .ok_or_else(|| anyhow!("Couldn't find a feed with alias {}.", self.0))?;
+            let mut filters: Vec<Filter> = tx.fetch_feed_filters()?.into_iter()
+                .filter(|filter| filter.alias == feed.alias)
+                .collect();
+            filters.sort_by_key(|filter| filter.id);
+            Ok((feed, filters))
+        })?;
+
+        let fetched = crate::fetch::fetch_feed(cfg, &feed)?;
+        let entries = fetched.entries.into_iter()
+            .map(|entry| entry.map(|entry| {
+                let matching_filters = filters.iter()
+                    .map(|filter| (filter.clone(), entry_match(filter, &entry)))
+                    .filter(|(_, entry_match)| !matches!(entry_match, EntryMatch::MissingKeywords(_)))
+                    .collect();
+                ShownEntry { entry, matching_filters }
+            }))
+            .collect();
+
+        Ok(ShowFeedOutput { feed, title: fetched.title, entries })
+    }
+
+    fn action(&self, _cfg: &Config, _tx: &mut RSSActionsTx) -> Result<ShowFeedOutput> {
+        unreachable!("ShowFeedCmd overrides execute")
+    }
+}
+
+/// Whether a filter matches an entry and would run its script on it.
+fn entry_match(filter: &Filter, entry: &FeedEntry) -> EntryMatch {
+    let missing = filter.missing_keywords(&entry.title);
+    if !missing.is_empty() {
+        EntryMatch::MissingKeywords(missing)
+    }
+    else if filter.is_new_entry(entry.pub_date) {
+        EntryMatch::New
+    }
+    else {
+        EntryMatch::AlreadySeen
+    }
+}
@@ -1,7 +1,12 @@
+use std::path::PathBuf;
+
 use anyhow::Result;
 use chrono::*;
 
-use crate::{Feed, Filter};
+use url::Url;
+
+use crate::{Disabled, ExportDocument, Feed, FeedEntry, Filter, ImportMode, ReplayRecord, ReplayWindow};
+use crate::ActionOutput;
 use crate::update::ProcessOutput; // (String, String, ExitStatus)
 
 #[derive(Debug)]
@@ -17,15 +22,154 @@ pub struct ListFiltersOutput {
 #[derive(Debug)]
 pub struct AddFeedOutput(pub Feed);
 
+#[derive(Debug)]
+pub struct AddFeedWithDiscoveryOutput {
+    pub feed: Feed,
+    /// The page the feed was found on, if the feed's url was a web page.
+    pub discovered_from: Option<Url>,
+    /// Other supported feeds linked from the page that were not added.
+    pub other_feeds: Vec<DiscoveredFeed>,
+    /// The feed's title and number of entries, if it was validated.
+    pub validation: Option<FeedValidation>,
+}
+
+/// A feed linked from a web page.
+#[derive(Debug, Clone)]
+pub struct DiscoveredFeed {
+    pub url: Url,
+    pub format: FeedFormat,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Rss,
+    Atom,
+    Json,
+}
+
+#[derive(Debug)]
+pub struct FeedValidation {
+    pub title: Option<String>,
+    pub entries: usize,
+}
+
 #[derive(Debug)]
 pub struct AddFilterOutput(pub Filter);
 
 #[derive(Debug)]
 pub struct DeleteFilterOutput(pub String, pub Vec<String>);
 
+#[derive(Debug)]
+pub struct DeleteSelectedFilterOutput(pub Filter);
+
 #[derive(Debug)]
 pub struct DeleteFeedOutput(pub String);
 
+/// Feed alias, the filters deleted with it
+#[derive(Debug)]
+pub struct DeleteFeedCascadeOutput(pub String, pub Vec<Filter>);
+
+#[derive(Debug)]
+pub struct RenameFeedOutput {
+    pub old_alias: String,
+    pub new_alias: String,
+    /// Whether the config file has settings for the old alias, which need to be renamed by hand.
+    pub has_feed_config: bool,
+}
+
+#[derive(Debug)]
+pub struct EditFeedOutput(pub Feed);
+
+#[derive(Debug)]
+pub struct EditFilterOutput(pub Filter);
+
+#[derive(Debug)]
+pub struct DisableFeedOutput(pub Feed);
+
+#[derive(Debug)]
+pub struct EnableFeedOutput(pub Feed);
+
+#[derive(Debug)]
+pub struct TagFeedOutput(pub Feed);
+
+#[derive(Debug)]
+pub struct UntagFeedOutput(pub Feed);
+
+#[derive(Debug)]
+pub struct DisableFilterOutput(pub Filter);
+
+#[derive(Debug)]
+pub struct EnableFilterOutput(pub Filter);
+
+#[derive(Debug)]
+pub struct DbMigrateOutput {
+    /// Whether the migrations were only checked rather than applied.
+    pub check: bool,
+    pub from_version: usize,
+    pub to_version: usize,
+    /// Descriptions of the migrations applied, or pending if checking.
+    pub migrations: Vec<String>,
+    pub backup_path: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct ExportOutput {
+    pub document: ExportDocument,
+    /// The file the document was written to, if it wasn't written to stdout.
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct ImportOutput {
+    pub mode: ImportMode,
+    /// The number of feeds and filters deleted before importing, when replacing.
+    pub deleted: Option<(usize, usize)>,
+    pub added_feeds: usize,
+    pub added_filters: usize,
+    /// Existing filters whose last updated time was moved forward to the imported one.
+    pub updated_filters: usize,
+    /// Descriptions of imported feeds and filters that were skipped because they conflict with the
+    /// database.
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct TestFilterOutput {
+    pub filter: Filter,
+    /// The feed's entries sorted by date, and whether the filter would run its script on them.
+    pub entries: Vec<(FeedEntry, EntryMatch)>,
+    /// Entries that are missing data. If there are any, `update` skips the whole feed.
+    pub entry_errors: Vec<anyhow::Error>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryMatch {
+    /// The entry matches and is newer than the filter's last update, so the script would run.
+    New,
+    /// The entry matches but isn't newer than the filter's last update.
+    AlreadySeen,
+    /// The entry's title is missing these keywords.
+    MissingKeywords(Vec<String>),
+}
+
+#[derive(Debug)]
+pub struct ShowFeedOutput {
+    pub feed: Feed,
+    /// The feed's title, if it has one.
+    pub title: Option<String>,
+    /// The feed's entries in the order they appear in the feed. Entries that are missing data are
+    /// errors, and if there are any, `update` skips the whole feed.
+    pub entries: Vec<Result<ShownEntry>>,
+}
+
+#[derive(Debug)]
+pub struct ShownEntry {
+    pub entry: FeedEntry,
+    /// The feed's filters whose keywords match the entry, and whether the entry is new to them.
+    pub matching_filters: Vec<(Filter, EntryMatch)>,
+}
+
 #[derive(Debug)]
 pub struct UpdateOutput {
     /// Feeds that fail to download or parse have their filters skipped but are reported with Errors.
@@ -33,11 +177,39 @@ pub struct UpdateOutput {
     /// Filters with scripts that fail to execute on at least one of the feed's entries are
     /// reported with Errors.
     pub executed_filters: Vec<(Filter, Result<Vec<ProcessOutput>>)>,
+    /// Filters that weren't run because they or their feed are disabled.
+    pub skipped_filters: Vec<Filter>,
+    /// Filters that reached their or the global limit on script runs, with the number of matching
+    /// entries left for later updates.
+    pub deferred: Vec<(Filter, usize)>,
+    /// Entries that filters skipped because another filter in their dedup group already processed
+    /// them.
+    pub deduplicated: Vec<(Filter, FeedEntry)>,
+    /// The files saved by filters with the built-in downloader.
+    pub downloads: Vec<(Filter, PathBuf)>,
     pub successes: usize,
     pub failures: usize,
     pub updates: usize,
 }
 
+#[derive(Debug)]
+pub struct ReplayOutput {
+    pub filter: Filter,
+    pub window: ReplayWindow,
+    /// The alias of each replayed entry's feed and the entry, sorted by feed and then date, with
+    /// the output of the script or action or why it failed.
+    pub results: Vec<(String, FeedEntry, Result<ActionOutput>)>,
+    /// How many of the entries are newer than the filter's last update on their feed.
+    pub still_new: usize,
+}
+
+#[derive(Debug)]
+pub struct ReplayHistoryOutput {
+    pub filter: Filter,
+    /// The stored replay results, oldest first.
+    pub records: Vec<ReplayRecord>,
+}
+
 pub trait ConsoleOutput {
     fn output(&self) -> Vec<String>;
 }
@@ -54,8 +226,16 @@ impl ConsoleOutput for ListFeedsOutput {
         "Current feeds:".into(),
         "".into()];
 
+        let now = Utc::now();
         for feed in feeds {
-            output.push(format!("{}\t{}", feed.alias, feed.url));
+            let mut line = format!("{}\t{}", feed.alias, feed.url);
+            if !feed.tags.is_empty() {
+                line.push_str(&format!("\ttags: {}", feed.tags.join(", ")));
+            }
+            if let Some(disabled) = feed.disabled.as_ref().filter(|_| feed.is_disabled(now)) {
+                line.push_str(&format!("\t{}", disabled));
+            }
+            output.push(line);
         }
 
         output
@@ -72,6 +252,7 @@ impl ConsoleOutput for ListFiltersOutput {
             "Current filters:".into(),
             "".into()];
 
+        let now = Utc::now();
         for filter in &self.filters {
             let last_updated = match filter.last_updated {
                 Some(utc_dt) => {
@@ -81,10 +262,24 @@ impl ConsoleOutput for ListFiltersOutput {
                 None => { "Never updated".into() }
             };
 
+            let id = filter.id.map_or("".into(), |id| id.to_string());
+            let name = filter.name.as_deref().unwrap_or("-");
             let keywords = filter.keywords.join(", ");
-            let script = filter.script_path.file_name().map_or("".into(), |s| s.to_string_lossy());
+            let script = match &filter.action {
+                Some(action) => action.to_string().into(),
+                None => filter.script_path.file_name().map_or("".into(), |s| s.to_string_lossy()),
+            };
 
-            output.push(format!("{}\t{}\t{}\t{}", filter.alias, keywords, script, last_updated));
+            // Filters on a tag have a last updated time for each feed, shown by `show feed`.
+            let (target, last_updated) = match &filter.tag {
+                Some(tag) => (format!("tag:{}", tag), "-".into()),
+                None => (filter.alias.clone(), last_updated),
+            };
+            let mut line = format!("{}\t{}\t{}\t{}\t{}\t{}", id, name, target, keywords, script, last_updated);
+            if let Some(disabled) = filter.disabled.as_ref().filter(|_| filter.is_disabled(now)) {
+                line.push_str(&format!("\t{}", disabled));
+            }
+            output.push(line);
         }
 
         output
@@ -97,17 +292,78 @@ impl ConsoleOutput for AddFeedOutput {
     }
 }
 
+impl ConsoleOutput for AddFeedWithDiscoveryOutput {
+    fn output(&self) -> Vec<String> {
+        let mut output = Vec::new();
+        if let Some(page) = &self.discovered_from {
+            output.push(format!("Found feed {} on page {}", self.feed.url, page));
+        }
+        if let Some(validation) = &self.validation {
+            output.push(format!("Feed title: {}", validation.title.as_deref().unwrap_or("(none)")));
+            output.push(format!("Feed entries: {}", validation.entries));
+        }
+        output.push(format!("Successfully added feed {}", self.feed.alias));
+
+        if !self.other_feeds.is_empty() {
+            output.push("".into());
+            output.push("Other feeds found on the page:".into());
+            for other in &self.other_feeds {
+                output.push(format!("{}\t{}", other.url, other.title.as_deref().unwrap_or("")));
+            }
+        }
+
+        output
+    }
+}
+
+impl std::fmt::Display for FeedFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            FeedFormat::Rss => "RSS",
+            FeedFormat::Atom => "Atom",
+            FeedFormat::Json => "JSON Feed",
+        };
+        f.write_str(name)
+    }
+}
+
 impl ConsoleOutput for AddFilterOutput {
     fn output(&self) -> Vec<String> {
         let filter = &self.0;
-        vec![format!("Successfully added filter on feed {}", filter.alias),
-             format!("Keywords: {}", filter.keywords.join(", "))]
+        let mut output = vec![format!("Successfully added filter on {}", filter.target()),
+             format!("Keywords: {}", filter.keywords.join(", "))];
+        if let Some(action) = &filter.action {
+            output.push(format!("Action: {}", action));
+        }
+        if let Some(id) = filter.id {
+            output.push(format!("Id: {}", id));
+        }
+        if let Some(name) = &filter.name {
+            output.push(format!("Name: {}", name));
+        }
+        if let Some(max_actions) = filter.max_actions_per_run {
+            output.push(format!("Max script runs per update: {}", max_actions));
+        }
+        if let Some(dedup_group) = &filter.dedup_group {
+            output.push(format!("Dedup group: {}", dedup_group));
+        }
+        if filter.priority != 0 {
+            output.push(format!("Priority: {}", filter.priority));
+        }
+        if filter.stop {
+            output.push("Lower-priority filters on the feed skip entries this filter matches".into());
+        }
+        if let Some(last_updated) = filter.last_updated {
+            let local_dt: DateTime<Local> = last_updated.into();
+            output.push(format!("Only entries published after {} will be processed", local_dt));
+        }
+        output
     }
 }
 
 impl ConsoleOutput for UpdateOutput {
     fn output(&self) -> Vec<String> {
-        if self.executed_filters.is_empty() {
+        if self.executed_filters.is_empty() && self.skipped_filters.is_empty() {
             return vec!["No filters in the database to update.".into()];
         }
         let mut output = Vec::new();
@@ -115,6 +371,34 @@ impl ConsoleOutput for UpdateOutput {
         output.push(format!("{} filters processed successfully.", self.successes));
         output.push(format!("{} filters updated.", self.updates));
         output.push(format!("{} filters failed to process.", self.failures));
+        if !self.skipped_filters.is_empty() {
+            output.push(format!("{} filters skipped because they or their feeds are disabled.",
+                self.skipped_filters.len()));
+        }
+        if !self.deferred.is_empty() {
+            let deferred: usize = self.deferred.iter().map(|(_, count)| count).sum();
+            output.push(format!("{} entries deferred to later updates because filters reached the limit on script runs:",
+                deferred));
+            for (filter, count) in &self.deferred {
+                output.push(format!("  filter {} on feed {}: {} entries",
+                    filter.id.map_or("".into(), |id| id.to_string()), filter.alias, count));
+            }
+        }
+        if !self.deduplicated.is_empty() {
+            output.push(format!("{} entries skipped because another filter in their dedup group already processed them:",
+                self.deduplicated.len()));
+            for (filter, entry) in &self.deduplicated {
+                output.push(format!("  filter {} on feed {}: {}",
+                    filter.id.map_or("".into(), |id| id.to_string()), filter.alias, entry.title));
+            }
+        }
+        if !self.downloads.is_empty() {
+            output.push(format!("{} files downloaded:", self.downloads.len()));
+            for (filter, path) in &self.downloads {
+                output.push(format!("  filter {} on feed {}: {}",
+                    filter.id.map_or("".into(), |id| id.to_string()), filter.alias, path.display()));
+            }
+        }
 
         let mut errors = Vec::new();
         for (_, res) in &self.executed_feeds {
@@ -136,6 +420,80 @@ impl ConsoleOutput for UpdateOutput {
     }
 }
 
+impl ConsoleOutput for ReplayOutput {
+    fn output(&self) -> Vec<String> {
+        let filter = &self.filter;
+        let window = match self.window {
+            ReplayWindow::Since(since) => {
+                let local_since: DateTime<Local> = since.into();
+                format!("since {}", local_since)
+            }
+            ReplayWindow::Last(n) => format!("the last {}", n),
+        };
+        if self.results.is_empty() {
+            return vec![format!("No entries {} matched the filter on {} with keywords: {}",
+                window, filter.target(), filter.keywords.join(", "))];
+        }
+
+        let mut output = vec![format!("Replayed entries {} for the filter on {} with keywords: {}",
+            window, filter.target(), filter.keywords.join(", ")), "".into()];
+        let mut errors = Vec::new();
+        for (alias, entry, res) in &self.results {
+            let local_date: DateTime<Local> = entry.pub_date.into();
+            let status = match res {
+                Ok(_) => "ok",
+                Err(err) => {
+                    errors.push(format!("{:?}", err));
+                    "failed"
+                }
+            };
+            let mut line = match filter.tag {
+                Some(_) => format!("{}\t{}\t{}\t{}", status, alias, local_date, entry.title),
+                None => format!("{}\t{}\t{}", status, local_date, entry.title),
+            };
+            if let Ok(ActionOutput::Downloaded(path)) = res {
+                line.push_str(&format!("\t{}", path.display()));
+            }
+            output.push(line);
+        }
+
+        output.push("".into());
+        output.push(format!("{} scripts succeeded, {} failed.", self.results.len() - errors.len(), errors.len()));
+        if self.still_new > 0 {
+            output.push(format!("{} of the entries are newer than the filter's last update, so update will run the script on them again.",
+                self.still_new));
+        }
+        output.extend(errors);
+
+        output
+    }
+}
+
+impl ConsoleOutput for ReplayHistoryOutput {
+    fn output(&self) -> Vec<String> {
+        let filter = &self.filter;
+        if self.records.is_empty() {
+            return vec![format!("The filter on {} with keywords: {} hasn't been replayed.",
+                filter.target(), filter.keywords.join(", "))];
+        }
+
+        let mut output = vec![format!("Replays of the filter on {} with keywords: {}",
+            filter.target(), filter.keywords.join(", ")), "".into()];
+        for record in &self.records {
+            let replayed_at: DateTime<Local> = record.replayed_at.into();
+            let local_date: DateTime<Local> = record.pub_date.into();
+            let status = if record.error.is_some() { "failed" } else { "ok" };
+            let mut line = format!("{}\t{}\t{}\t{}\t{}", replayed_at, status, record.feed, local_date, record.title);
+            if let Some(error) = &record.error {
+                line.push_str(&format!("\t{}", error));
+            }
+            output.push(line);
+        }
+
+        output
+    }
+}
+
 impl ConsoleOutput for DeleteFilterOutput {
     fn output(&self) -> Vec<String> {
         let alias = &self.0;
@@ -145,9 +503,314 @@ impl ConsoleOutput for DeleteFilterOutput {
     }
 }
 
+impl ConsoleOutput for DeleteSelectedFilterOutput {
+    fn output(&self) -> Vec<String> {
+        let filter = &self.0;
+        vec![format!("Successfully deleted filter {} on {}",
+                filter.id.map_or("".into(), |id| id.to_string()), filter.target()),
+             format!("Keywords: {}", filter.keywords.join(", "))]
+    }
+}
+
 impl ConsoleOutput for DeleteFeedOutput {
     fn output(&self) -> Vec<String> {
         let alias = &self.0;
         vec![format!("Successfully deleted feed {}", alias)]
     }
 }
+
+impl ConsoleOutput for DeleteFeedCascadeOutput {
+    fn output(&self) -> Vec<String> {
+        let alias = &self.0;
+        let filters = &self.1;
+        let mut output = vec![format!("Successfully deleted feed {} and {} filters", alias, filters.len())];
+
+        for filter in filters {
+            let keywords = filter.keywords.join(", ");
+            output.push(format!("{}\t{}\t{}", filter.alias, keywords, filter.script_path.to_string_lossy()));
+        }
+
+        output
+    }
+}
+
+impl ConsoleOutput for RenameFeedOutput {
+    fn output(&self) -> Vec<String> {
+        let mut output = vec![format!("Successfully renamed feed {} to {}", self.old_alias, self.new_alias)];
+        if self.has_feed_config {
+            output.push(format!("The config file has a [feeds.{}] section, rename it to [feeds.{}] to keep using its settings.",
+                self.old_alias, self.new_alias));
+        }
+        output
+    }
+}
+
+impl ConsoleOutput for EditFeedOutput {
+    fn output(&self) -> Vec<String> {
+        let feed = &self.0;
+        vec![format!("Successfully changed the url of feed {} to {}", feed.alias, feed.url)]
+    }
+}
+
+impl ConsoleOutput for EditFilterOutput {
+    fn output(&self) -> Vec<String> {
+        let filter = &self.0;
+        let mut output = vec![format!("Successfully edited filter on {}", filter.target()),
+             format!("Keywords: {}", filter.keywords.join(", ")),
+             match &filter.action {
+                 Some(action) => format!("Action: {}", action),
+                 None => format!("Script: {}", filter.script_path.to_string_lossy()),
+             }];
+        if let Some(name) = &filter.name {
+            output.push(format!("Name: {}", name));
+        }
+        if let Some(max_actions) = filter.max_actions_per_run {
+            output.push(format!("Max script runs per update: {}", max_actions));
+        }
+        if let Some(dedup_group) = &filter.dedup_group {
+            output.push(format!("Dedup group: {}", dedup_group));
+        }
+        if filter.priority != 0 {
+            output.push(format!("Priority: {}", filter.priority));
+        }
+        if filter.stop {
+            output.push("Lower-priority filters on the feed skip entries this filter matches".into());
+        }
+        output
+    }
+}
+
+/// The lines describing why and until when something was disabled.
+fn disabled_details(disabled: Option<&Disabled>) -> Vec<String> {
+    let mut output = Vec::new();
+    if let Some(until) = disabled.and_then(|disabled| disabled.until) {
+        let local_until: DateTime<Local> = until.into();
+        output.push(format!("Until: {}", local_until));
+    }
+    if let Some(reason) = disabled.and_then(|disabled| disabled.reason.as_ref()) {
+        output.push(format!("Reason: {}", reason));
+    }
+    output
+}
+
+impl ConsoleOutput for DisableFeedOutput {
+    fn output(&self) -> Vec<String> {
+        let feed = &self.0;
+        let mut output = vec![format!("Successfully disabled feed {}", feed.alias)];
+        output.extend(disabled_details(feed.disabled.as_ref()));
+        output
+    }
+}
+
+impl ConsoleOutput for EnableFeedOutput {
+    fn output(&self) -> Vec<String> {
+        vec![format!("Successfully enabled feed {}", self.0.alias)]
+    }
+}
+
+impl ConsoleOutput for TagFeedOutput {
+    fn output(&self) -> Vec<String> {
+        let feed = &self.0;
+        vec![format!("Successfully tagged feed {}", feed.alias),
+             format!("Tags: {}", feed.tags.join(", "))]
+    }
+}
+
+impl ConsoleOutput for UntagFeedOutput {
+    fn output(&self) -> Vec<String> {
+        let feed = &self.0;
+        let tags = if feed.tags.is_empty() { "(none)".into() } else { feed.tags.join(", ") };
+        vec![format!("Successfully untagged feed {}", feed.alias),
+             format!("Tags: {}", tags)]
+    }
+}
+
+impl ConsoleOutput for DisableFilterOutput {
+    fn output(&self) -> Vec<String> {
+        let filter = &self.0;
+        let mut output = vec![format!("Successfully disabled filter {} on {}",
+                filter.id.map_or("".into(), |id| id.to_string()), filter.target()),
+             format!("Keywords: {}", filter.keywords.join(", "))];
+        output.extend(disabled_details(filter.disabled.as_ref()));
+        output
+    }
+}
+
+impl ConsoleOutput for EnableFilterOutput {
+    fn output(&self) -> Vec<String> {
+        let filter = &self.0;
+        vec![format!("Successfully enabled filter {} on {}",
+                filter.id.map_or("".into(), |id| id.to_string()), filter.target()),
+             format!("Keywords: {}", filter.keywords.join(", "))]
+    }
+}
+
+impl ConsoleOutput for DbMigrateOutput {
+    fn output(&self) -> Vec<String> {
+        if self.migrations.is_empty() {
+            return vec![format!("The database is up to date at schema version {}.", self.to_version)];
+        }
+
+        let mut output = Vec::new();
+        if self.check {
+            output.push(format!("The database is at schema version {} and needs {} migrations to reach version {}:",
+                self.from_version, self.migrations.len(), self.to_version));
+        }
+        else {
+            output.push(format!("Migrated the database from schema version {} to {}:", self.from_version, self.to_version));
+        }
+        for migration in &self.migrations {
+            output.push(format!("  {}", migration));
+        }
+        if let Some(backup_path) = &self.backup_path {
+            output.push(format!("The database was backed up to {}", backup_path.display()));
+        }
+        output
+    }
+}
+
+impl ConsoleOutput for ExportOutput {
+    fn output(&self) -> Vec<String> {
+        match &self.path {
+            Some(path) => vec![format!("Exported {} feeds and {} filters to {}",
+                self.document.feeds.len(), self.document.filters.len(), path.display())],
+            None => {
+                let json = serde_json::to_string_pretty(&self.document)
+                    .expect("export documents can always be serialized");
+                json.lines().map(String::from).collect()
+            }
+        }
+    }
+}
+
+impl ConsoleOutput for ImportOutput {
+    fn output(&self) -> Vec<String> {
+        let mut output = Vec::new();
+        if let Some((feeds, filters)) = self.deleted {
+            output.push(format!("Deleted {} feeds and {} filters", feeds, filters));
+        }
+        output.push(format!("Imported {} feeds and {} filters", self.added_feeds, self.added_filters));
+        if self.mode == ImportMode::Merge {
+            output.push(format!("Updated the last updated time of {} filters", self.updated_filters));
+        }
+
+        if !self.conflicts.is_empty() {
+            output.push("".into());
+            output.push(format!("Skipped {} conflicts:", self.conflicts.len()));
+            for conflict in &self.conflicts {
+                output.push(format!("  {}", conflict));
+            }
+        }
+
+        output
+    }
+}
+
+impl ConsoleOutput for TestFilterOutput {
+    fn output(&self) -> Vec<String> {
+        let filter = &self.filter;
+        let last_updated = match filter.last_updated {
+            Some(utc_dt) => {
+                let local_dt: DateTime<Local> = utc_dt.into();
+                local_dt.to_string()
+            }
+            None => { "Never updated".into() }
+        };
+        let mut output = vec![format!("Filter on feed {} with keywords: {}", filter.alias, filter.keywords.join(", ")),
+            format!("Last updated: {}", last_updated),
+            "".into()];
+
+        for (entry, entry_match) in &self.entries {
+            let local_date: DateTime<Local> = entry.pub_date.into();
+            let (status, detail) = match entry_match {
+                EntryMatch::New => ("new", entry.link.clone()),
+                EntryMatch::AlreadySeen => ("seen", "not newer than the filter's last update".into()),
+                EntryMatch::MissingKeywords(missing) => ("no match", format!("missing {}", missing.join(", "))),
+            };
+            output.push(format!("{}\t{}\t{}\t{}", status, local_date, entry.title, detail));
+        }
+
+        let count = |wanted: fn(&EntryMatch) -> bool| self.entries.iter().filter(|(_, m)| wanted(m)).count();
+        output.push("".into());
+        output.push(format!("{} new entries would be passed to the script, {} matching entries were already seen, {} entries did not match.",
+            count(|m| *m == EntryMatch::New), count(|m| *m == EntryMatch::AlreadySeen),
+            count(|m| matches!(m, EntryMatch::MissingKeywords(_)))));
+
+        if !self.entry_errors.is_empty() {
+            output.push(format!("{} entries had data errors, so update would skip this feed:", self.entry_errors.len()));
+            for err in &self.entry_errors {
+                output.push(format!("  {:#}", err));
+            }
+        }
+
+        output
+    }
+}
+
+impl ConsoleOutput for ShowFeedOutput {
+    fn output(&self) -> Vec<String> {
+        let feed = &self.feed;
+        let mut output = vec![match &self.title {
+            Some(title) => format!("Feed {}: {}", feed.alias, title),
+            None => format!("Feed {}", feed.alias),
+        }];
+        output.push(format!("Url: {}", feed.url));
+        if let Some(disabled) = feed.disabled.as_ref().filter(|_| feed.is_disabled(Utc::now())) {
+            output.push(format!("This feed is {}, so update doesn't download it.", disabled));
+        }
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            output.push("".into());
+            let shown = match entry {
+                Ok(shown) => shown,
+                Err(err) => {
+                    output.push(format!("{}. Rejected: {:#}", i + 1, err));
+                    continue;
+                }
+            };
+
+            let entry = &shown.entry;
+            let local_date: DateTime<Local> = entry.pub_date.into();
+            output.push(format!("{}. {}", i + 1, entry.title));
+            output.push(format!("   Date: {}", local_date));
+            output.push(format!("   Link: {}", entry.link));
+            if let Some(guid) = &entry.guid {
+                output.push(format!("   GUID: {}", guid));
+            }
+            if let Some(enclosure) = &entry.enclosure {
+                match enclosure.length {
+                    Some(length) => output.push(format!("   Enclosure: {} ({}, {} bytes)",
+                        enclosure.url, enclosure.mime_type, length)),
+                    None => output.push(format!("   Enclosure: {} ({})", enclosure.url, enclosure.mime_type)),
+                }
+            }
+
+            if shown.matching_filters.is_empty() {
+                output.push("   Matching filters: none".into());
+            }
+            else {
+                let filters: Vec<String> = shown.matching_filters.iter()
+                    .map(|(filter, entry_match)| {
+                        let id = filter.id.map(|id| id.to_string()).unwrap_or_default();
+                        let name = filter.name.as_ref().map(|name| format!(" {}", name)).unwrap_or_default();
+                        let status = match entry_match {
+                            EntryMatch::New => "new",
+                            _ => "seen",
+                        };
+                        format!("{}{} ({})", id, name, status)
+                    })
+                    .collect();
+                output.push(format!("   Matching filters: {}", filters.join(", ")));
+            }
+        }
+
+        let rejected = self.entries.iter().filter(|entry| entry.is_err()).count();
+        output.push("".into());
+        output.push(format!("{} entries, {} rejected.", self.entries.len(), rejected));
+        if rejected > 0 {
+            output.push("Update skips feeds with rejected entries, so no filters would run on this feed.".into());
+        }
+
+        output
+    }
+}
@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+static RSSACTIONS_USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+);
+
+const DEFAULT_READ_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_MAX_BODY_BYTES: u64 = 16 * 1024 * 1024;
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// HTTP settings used when downloading feeds. The global settings are the `[http]` section of the
+/// config file, and each feed can override them in its `[feeds.<alias>.http]` section.
+///
+/// Every field is optional so that a feed's section only needs to contain the settings it
+/// overrides.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HttpConfig {
+    /// The User-Agent header sent with requests. Defaults to `rss-actions/<version>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Timeout for establishing a connection, in seconds. By default only the read timeout
+    /// applies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_secs: Option<u64>,
+    /// Timeout for the whole request including reading the response, in seconds. Defaults to 30.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_timeout_secs: Option<u64>,
+    /// Responses larger than this are rejected. Defaults to 16 MiB. Also applies to `file://` and
+    /// `exec:` feeds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_body_bytes: Option<u64>,
+    /// The maximum number of redirects to follow. Defaults to 10.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_redirects: Option<usize>,
+    /// Proxy URL used for all requests, e.g. `http://proxy.internal:3128` or
+    /// `socks5://127.0.0.1:1080`. Use `none` to connect directly, e.g. to override a global proxy
+    /// for an internal feed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// PEM files containing extra root certificates to trust, e.g. a private CA. Each file may
+    /// contain multiple certificates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_certificates: Option<Vec<PathBuf>>,
+    /// PEM file containing the client certificate chain for mutual TLS. Requires `client_key`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_certificate: Option<PathBuf>,
+    /// PEM file containing the PKCS#8 private key for `client_certificate`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<PathBuf>,
+    /// Accept invalid TLS certificates and hostnames. Only meant for testing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insecure: Option<bool>,
+}
+
+impl HttpConfig {
+    /// Returns these settings with any settings present in `overrides` replacing them.
+    pub fn merge(&self, overrides: &HttpConfig) -> HttpConfig {
+        HttpConfig {
+            user_agent: overrides.user_agent.clone().or_else(|| self.user_agent.clone()),
+            connect_timeout_secs: overrides.connect_timeout_secs.or(self.connect_timeout_secs),
+            read_timeout_secs: overrides.read_timeout_secs.or(self.read_timeout_secs),
+            max_body_bytes: overrides.max_body_bytes.or(self.max_body_bytes),
+            max_redirects: overrides.max_redirects.or(self.max_redirects),
+            proxy: overrides.proxy.clone().or_else(|| self.proxy.clone()),
+            root_certificates: overrides.root_certificates.clone().or_else(|| self.root_certificates.clone()),
+            client_certificate: overrides.client_certificate.clone().or_else(|| self.client_certificate.clone()),
+            client_key: overrides.client_key.clone().or_else(|| self.client_key.clone()),
+            insecure: overrides.insecure.or(self.insecure),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == HttpConfig::default()
+    }
+
+    pub fn user_agent(&self) -> &str {
+        self.user_agent.as_deref().unwrap_or(RSSACTIONS_USER_AGENT)
+    }
+
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout_secs.map(Duration::from_secs)
+    }
+
+    pub fn read_timeout(&self) -> Duration {
+        Duration::from_secs(self.read_timeout_secs.unwrap_or(DEFAULT_READ_TIMEOUT_SECS))
+    }
+
+    pub fn max_body_bytes(&self) -> u64 {
+        self.max_body_bytes.unwrap_or(DEFAULT_MAX_BODY_BYTES)
+    }
+
+    pub fn max_redirects(&self) -> usize {
+        self.max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS)
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Error, Result};
@@ -6,14 +7,109 @@ use serde::{Deserialize, Serialize};
 
 use directories::ProjectDirs;
 
+mod http;
+pub use http::HttpConfig;
+
+mod secret;
+pub use secret::{Secret, SecretRef};
+
+mod source;
+pub use source::{HtmlSource, JsonSource};
+
 /// Configuration file for RSS Actions
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Config {
     /// The path to the rss-actions database file. 
     pub db_path: PathBuf,
+    /// HTTP settings used for all feeds, unless overridden by the feed's own settings.
+    #[serde(default, skip_serializing_if = "HttpConfig::is_empty")]
+    pub http: HttpConfig,
+    /// The most scripts run by all filters together in one update. Matching entries past the limit
+    /// are processed by later updates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_actions_per_run: Option<usize>,
+    /// How many days entries processed by a dedup group are remembered. Defaults to 90.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedup_retention_days: Option<u32>,
+    /// Per-feed settings, keyed by feed alias. Written as `[feeds.<alias>]` sections in the config
+    /// file.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub feeds: BTreeMap<String, FeedConfig>,
+}
+
+/// Settings for a single feed that don't belong in the database, e.g. credentials for private
+/// feeds or HTTP settings that differ from the global ones.
+///
+/// Credentials are given as `SecretRef`s, so only a reference to where the secret lives is stored
+/// in the config file. They are resolved by the updater when the feed is fetched.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct FeedConfig {
+    /// Sent as an `Authorization: Bearer` header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bearer_token: Option<SecretRef>,
+    /// Sent as HTTP basic authentication.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub basic_auth: Option<BasicAuth>,
+    /// Extra request headers with secret values, e.g. API keys. Keyed by header name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub secret_headers: BTreeMap<String, SecretRef>,
+    /// Overrides for the global HTTP settings.
+    #[serde(default, skip_serializing_if = "HttpConfig::is_empty")]
+    pub http: HttpConfig,
+    /// Arguments passed to the program of an `exec:` feed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exec_args: Vec<String>,
+    /// How long the program of an `exec:` feed may run before it is killed, in seconds. Defaults
+    /// to 30.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec_timeout_secs: Option<u64>,
+    /// Scrape entries from the HTML page at the feed's url instead of parsing it as RSS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<HtmlSource>,
+    /// Read entries from the JSON document at the feed's url instead of parsing it as RSS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json: Option<JsonSource>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BasicAuth {
+    pub username: String,
+    pub password: SecretRef,
 }
 
 impl Config {
+    /// Creates a config with the given database path and default values for everything else. Does
+    /// not write anything to disk.
+    pub fn new(db_path: PathBuf) -> Config {
+        Config {
+            db_path,
+            http: HttpConfig::default(),
+            max_actions_per_run: None,
+            dedup_retention_days: None,
+            feeds: BTreeMap::new(),
+        }
+    }
+
+    /// How long entries processed by a dedup group are remembered.
+    pub fn dedup_retention(&self) -> chrono::Duration {
+        chrono::Duration::days(self.dedup_retention_days.unwrap_or(90).into())
+    }
+
+    /// The settings for the feed with the given alias, or the defaults if the config file has no
+    /// section for it.
+    pub fn feed_config(&self, alias: &str) -> FeedConfig {
+        self.feeds.get(alias).cloned().unwrap_or_default()
+    }
+
+    /// The HTTP settings for the feed with the given alias, i.e. the global settings with the
+    /// feed's overrides applied.
+    pub fn feed_http_config(&self, alias: &str) -> HttpConfig {
+        match self.feeds.get(alias) {
+            Some(feed_cfg) => self.http.merge(&feed_cfg.http),
+            None => self.http.clone(),
+        }
+    }
+
     /// Creates a new config in the default directory, possibly creating the directory as well if
     /// it does not exist. Additionally creates the data directory in which the database is stored
     /// in by default.
@@ -29,9 +125,7 @@ impl Config {
             .with_context(|| format!("Unable to create database dir: {:?}", &db_path))?;
         db_path.push("rss-actions.db");
 
-        let cfg = Config {
-            db_path,
-        };
+        let cfg = Config::new(db_path);
 
         let mut config_file: PathBuf = project_dirs.config_dir().into();
         config_file.push("config.toml");
@@ -80,6 +174,20 @@ impl Config {
             .with_context(|| format!("Failed to open config file: {:?}", config_file))?;
        
         toml::from_str(&config_data)
+            .map_err(|err| without_source_excerpt(err, &config_data))
             .with_context(|| format!("Failed to read config file: {:?}", config_file))
     }
 }
+
+/// toml's errors quote the offending line of the config file, which could be a secret that was
+/// written into the file directly instead of as a `SecretRef`. Report just the message and the
+/// line number instead.
+fn without_source_excerpt(err: toml::de::Error, config_data: &str) -> Error {
+    match err.span() {
+        Some(span) => {
+            let line = config_data[..span.start].matches('\n').count() + 1;
+            anyhow!("{} (line {})", err.message().trim_end(), line)
+        }
+        None => anyhow!("{}", err.message().trim_end()),
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Error, Result};
+
+use serde::{Deserialize, Serialize};
+
+/// A reference to a secret that lives outside of rss-actions' config file and database, e.g. a
+/// feed's access token. Only the reference is ever stored; the secret itself is read when it is
+/// needed and then dropped.
+///
+/// In the config file a reference is written as one of:
+///
+/// - `env:NAME` to read the environment variable `NAME`
+/// - `file:/path/to/secret` to read a file, e.g. `/run/secrets/token`
+/// - `cmd:some command` to run a command with `sh -c` and use its stdout
+///
+/// Trailing newlines are removed from file contents and command output.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum SecretRef {
+    Env(String),
+    File(PathBuf),
+    Command(String),
+}
+
+/// A resolved secret value. The `Debug` impl does not print the value so that it can't end up in
+/// error messages or logs by accident.
+pub struct Secret(String);
+
+impl Secret {
+    /// The secret value itself. Don't print it.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+impl SecretRef {
+    /// Read the secret the reference points to.
+    ///
+    /// Error messages name the reference (e.g. the environment variable) but never contain the
+    /// secret value.
+    pub fn resolve(&self) -> Result<Secret> {
+        let value = match self {
+            SecretRef::Env(name) => {
+                std::env::var(name)
+                    .map_err(|_| anyhow!("The secret environment variable {} is not set or is not valid unicode.", name))?
+            }
+            SecretRef::File(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read secret file {:?}", path))?;
+                contents.trim_end_matches(['\r', '\n']).to_string()
+            }
+            SecretRef::Command(command) => {
+                let process = std::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .output()
+                    .with_context(|| format!("Failed to run secret command `{}`", command))?;
+
+                // Only stderr is reported, since stdout is the secret.
+                if !process.status.success() {
+                    return Err(anyhow!("Secret command `{}` failed with {}", command, process.status))
+                        .with_context(|| format!("stderr: \n{}", String::from_utf8_lossy(&process.stderr)));
+                }
+
+                let stdout = String::from_utf8(process.stdout)
+                    .map_err(|_| anyhow!("Secret command `{}` did not output valid unicode.", command))?;
+                stdout.trim_end_matches(['\r', '\n']).to_string()
+            }
+        };
+
+        if value.is_empty() {
+            return Err(anyhow!("The secret `{}` is empty.", self));
+        }
+
+        Ok(Secret(value))
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretRef::Env(name) => write!(f, "env:{}", name),
+            SecretRef::File(path) => write!(f, "file:{}", path.to_string_lossy()),
+            SecretRef::Command(command) => write!(f, "cmd:{}", command),
+        }
+    }
+}
+
+impl TryFrom<String> for SecretRef {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<SecretRef> {
+        // None of the errors here echo the value back: if it isn't a valid reference, the user may
+        // have put the secret itself in the config file.
+        let (kind, reference) = s.split_once(':')
+            .ok_or_else(|| anyhow!("Secrets must be given as `env:NAME`, `file:/path`, or `cmd:command`."))?;
+
+        if reference.is_empty() {
+            return Err(anyhow!("A secret reference is missing its name, path, or command."));
+        }
+
+        match kind {
+            "env" => Ok(SecretRef::Env(reference.into())),
+            "file" => Ok(SecretRef::File(reference.into())),
+            "cmd" => Ok(SecretRef::Command(reference.into())),
+            _ => Err(anyhow!("Unknown secret type. Secrets must be given as `env:NAME`, `file:/path`, or `cmd:command`.")),
+        }
+    }
+}
+
+impl From<SecretRef> for String {
+    fn from(secret: SecretRef) -> String {
+        secret.to_string()
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+/// Settings for a feed that is scraped from a web page instead of read from an RSS document.
+///
+/// The feed's url is fetched as usual and each element matching `item` becomes an entry. The
+/// other selectors are matched inside the item element.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HtmlSource {
+    /// CSS selector for the element containing a single entry.
+    pub item: String,
+    /// CSS selector for the entry's title. The element's text is used.
+    pub title: String,
+    /// CSS selector for the entry's link. The element's `href` attribute is used if it has one,
+    /// otherwise its text. Relative links are resolved against the feed's url.
+    pub link: String,
+    /// CSS selector for the entry's date. The element's `datetime` attribute is used if it has one,
+    /// e.g. for `<time>` elements, otherwise its text.
+    pub date: String,
+    /// chrono format string used to parse the date, e.g. `%B %d, %Y`. Dates without a time are
+    /// taken to be midnight UTC. If not given, the date must be RFC 3339 or RFC 2822.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+}
+
+/// Settings for a feed that is read from a JSON API instead of an RSS document.
+///
+/// Paths are a small subset of JSONPath: `$` is the document, `.name` or `["name"]` selects a
+/// field, `[0]` an array element and `[*]` or `.*` all elements. `items` is matched against the
+/// document, and the other paths against each item, where the leading `$.` may be left out.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct JsonSource {
+    /// Path to the entries, e.g. `$.items[*]`. If it selects a single array, the array's elements
+    /// are the entries.
+    pub items: String,
+    /// Path to the entry's title.
+    pub title: String,
+    /// Path to the entry's link. Relative links are resolved against the feed's url.
+    pub link: String,
+    /// Path to the entry's date.
+    pub date: String,
+    /// chrono format string used to parse the date, e.g. `%s` for unix timestamps. Dates without a
+    /// time are taken to be midnight UTC. If not given, the date must be RFC 3339 or RFC 2822.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+}
@@ -0,0 +1,219 @@
+use anyhow::{anyhow, Context, Result};
+
+use crate::db::RSSActionsTx;
+
+/// A change to the database schema.
+struct Migration {
+    description: &'static str,
+    sql: &'static str,
+}
+
+/// The schema migrations, in order. Applying migration `i` takes the database from version `i` to
+/// version `i + 1`. The version is stored in sqlite's `user_version` pragma, which is 0 for a new
+/// database.
+///
+/// Migrations must never be changed once released, only added to the end.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "create feeds and filters tables",
+        sql: "CREATE TABLE feeds (
+                id INTEGER PRIMARY KEY,
+                url TEXT NOT NULL,
+                alias TEXT NOT NULL UNIQUE
+            );
+            CREATE TABLE filters (
+                id INTEGER PRIMARY KEY,
+                feed_id INTEGER NOT NULL,
+                keywords TEXT NOT NULL,
+                script_path TEXT NOT NULL,
+                last_updated TEXT,
+                FOREIGN KEY (feed_id) REFERENCES feeds(id),
+                UNIQUE(feed_id,keywords,script_path)
+            );",
+    },
+    Migration {
+        description: "add filter names",
+        // A UNIQUE constraint can't be added to an existing table, but a unique index does the same.
+        sql: "ALTER TABLE filters ADD COLUMN name TEXT;
+            CREATE UNIQUE INDEX filters_name ON filters(name);",
+    },
+    Migration {
+        description: "add disabled state to feeds and filters",
+        sql: "ALTER TABLE feeds ADD COLUMN disabled INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE feeds ADD COLUMN disabled_reason TEXT;
+            ALTER TABLE feeds ADD COLUMN disabled_until TEXT;
+            ALTER TABLE filters ADD COLUMN disabled INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE filters ADD COLUMN disabled_reason TEXT;
+            ALTER TABLE filters ADD COLUMN disabled_until TEXT;",
+    },
+    Migration {
+        description: "add per-filter limits on script runs per update",
+        sql: "ALTER TABLE filters ADD COLUMN max_actions_per_run INTEGER;",
+    },
+    Migration {
+        description: "add filter dedup groups",
+        sql: "ALTER TABLE filters ADD COLUMN dedup_group TEXT;
+            CREATE TABLE dedup_entries (
+                dedup_group TEXT NOT NULL,
+                entry_key TEXT NOT NULL,
+                processed_at TEXT NOT NULL,
+                PRIMARY KEY (dedup_group, entry_key)
+            );",
+    },
+    Migration {
+        description: "add filter priority and stop flag",
+        sql: "ALTER TABLE filters ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE filters ADD COLUMN stop INTEGER NOT NULL DEFAULT 0;",
+    },
+    Migration {
+        description: "add feed tags and filters on tags",
+        // Filters on a tag have no feed, and sqlite can't drop the NOT NULL constraint on
+        // `feed_id` in place, so the filters table is rebuilt. Filters on a tag keep their last
+        // updated time for each feed in `filter_feed_state` instead of `filters.last_updated`.
+        sql: "CREATE TABLE filters_new (
+                id INTEGER PRIMARY KEY,
+                feed_id INTEGER,
+                keywords TEXT NOT NULL,
+                script_path TEXT NOT NULL,
+                last_updated TEXT,
+                name TEXT,
+                disabled INTEGER NOT NULL DEFAULT 0,
+                disabled_reason TEXT,
+                disabled_until TEXT,
+                max_actions_per_run INTEGER,
+                dedup_group TEXT,
+                priority INTEGER NOT NULL DEFAULT 0,
+                stop INTEGER NOT NULL DEFAULT 0,
+                tag TEXT,
+                FOREIGN KEY (feed_id) REFERENCES feeds(id),
+                UNIQUE(feed_id,keywords,script_path),
+                UNIQUE(tag,keywords,script_path),
+                CHECK ((feed_id IS NULL) != (tag IS NULL))
+            );
+            INSERT INTO filters_new
+                (id, feed_id, keywords, script_path, last_updated, name, disabled, disabled_reason,
+                 disabled_until, max_actions_per_run, dedup_group, priority, stop)
+              SELECT id, feed_id, keywords, script_path, last_updated, name, disabled, disabled_reason,
+                 disabled_until, max_actions_per_run, dedup_group, priority, stop
+              FROM filters;
+            DROP TABLE filters;
+            ALTER TABLE filters_new RENAME TO filters;
+            CREATE UNIQUE INDEX filters_name ON filters(name);
+            CREATE TABLE feed_tags (
+                feed_id INTEGER NOT NULL,
+                tag TEXT NOT NULL,
+                FOREIGN KEY (feed_id) REFERENCES feeds(id),
+                PRIMARY KEY (feed_id, tag)
+            );
+            CREATE TABLE filter_feed_state (
+                filter_id INTEGER NOT NULL,
+                feed_id INTEGER NOT NULL,
+                last_updated TEXT,
+                FOREIGN KEY (filter_id) REFERENCES filters(id),
+                FOREIGN KEY (feed_id) REFERENCES feeds(id),
+                PRIMARY KEY (filter_id, feed_id)
+            );",
+    },
+    Migration {
+        description: "add built-in filter actions",
+        // The action is stored as JSON, since each kind of action has its own settings.
+        sql: "ALTER TABLE filters ADD COLUMN action TEXT;",
+    },
+    Migration {
+        description: "add files saved by the built-in downloader",
+        sql: "CREATE TABLE downloads (
+                path TEXT PRIMARY KEY,
+                url TEXT NOT NULL,
+                downloaded_at TEXT NOT NULL
+            );",
+    },
+    Migration {
+        description: "add replay results",
+        // The rows are deleted along with their filter or feed, so they don't block deleting them
+        // through foreign keys.
+        sql: "CREATE TABLE replay_results (
+                id INTEGER PRIMARY KEY,
+                filter_id INTEGER NOT NULL,
+                feed_id INTEGER NOT NULL,
+                entry_title TEXT NOT NULL,
+                entry_link TEXT NOT NULL,
+                entry_date TEXT NOT NULL,
+                replayed_at TEXT NOT NULL,
+                error TEXT
+            );",
+    },
+];
+
+/// The schema version of a fully migrated database.
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// Descriptions of the migrations that would be applied to a database at `version`.
+pub fn pending_migrations(version: usize) -> Vec<String> {
+    MIGRATIONS.iter().skip(version)
+        .map(|migration| migration.description.to_string())
+        .collect()
+}
+
+impl<'conn> RSSActionsTx<'conn> {
+    /// The schema version of the database. 0 means the database is empty.
+    pub fn schema_version(&self) -> Result<usize> {
+        let version: usize = self.tx.pragma_query_value(None, "user_version", |row| row.get(0))
+            .context("failed to read the database schema version")?;
+
+        if version == 0 {
+            return self.unversioned_schema_version();
+        }
+        Ok(version)
+    }
+
+    /// Databases created before the schema was versioned have a `user_version` of 0 even though
+    /// they have tables, so their version is worked out from the columns they have.
+    fn unversioned_schema_version(&self) -> Result<usize> {
+        if !self.has_table("feeds")? {
+            Ok(0)
+        }
+        else if self.has_column("filters", "disabled")? {
+            Ok(3)
+        }
+        else if self.has_column("filters", "name")? {
+            Ok(2)
+        }
+        else {
+            Ok(1)
+        }
+    }
+
+    /// Apply the migrations after `version` and set the schema version to `SCHEMA_VERSION`.
+    pub fn migrate(&self, version: usize) -> Result<()> {
+        if version > SCHEMA_VERSION {
+            return Err(anyhow!("The database has schema version {}, but this version of rss-actions only \
+                supports up to version {}. Was it used with a newer version of rss-actions?",
+                version, SCHEMA_VERSION));
+        }
+
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            self.tx.execute_batch(migration.sql)
+                .with_context(|| format!("failed to migrate the database to version {}: {}",
+                        i + 1, migration.description))?;
+        }
+        self.tx.pragma_update(None, "user_version", SCHEMA_VERSION)
+            .context("failed to set the database schema version")?;
+
+        Ok(())
+    }
+
+    fn has_table(&self, table: &str) -> Result<bool> {
+        let count: usize = self.tx.query_row(
+            "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = ?1", [table], |row| row.get(0))
+            .with_context(|| format!("failed to check whether the {} table exists", table))?;
+        Ok(count > 0)
+    }
+
+    fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        let mut stmt = self.tx.prepare("SELECT name FROM pragma_table_info(?1)")?;
+        let columns = stmt.query_map([table], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .with_context(|| format!("failed to read columns of {} table", table))?;
+        Ok(columns.iter().any(|name| name == column))
+    }
+}
@@ -1,11 +1,13 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use rusqlite::{Connection, Transaction};
 
-mod create;
+mod migrations;
 mod transaction;
 
+pub use migrations::{pending_migrations, SCHEMA_VERSION};
+
 /// A DB connection. Opens connection to local sqlite database.
 #[derive(Debug)]
 pub struct RSSActionsDb {
@@ -18,8 +20,18 @@ pub struct RSSActionsTx<'conn> {
     tx: Transaction<'conn>,
 }
 
+/// The result of bringing a database's schema up to date.
+#[derive(Debug)]
+pub struct Migrated {
+    pub from_version: usize,
+    /// Descriptions of the migrations applied.
+    pub applied: Vec<String>,
+    /// Where the database was copied to before migrating, if it had any tables.
+    pub backup_path: Option<PathBuf>,
+}
+
 impl RSSActionsDb {
-    pub fn transaction(&mut self) -> Result<RSSActionsTx> {
+    pub fn transaction(&mut self) -> Result<RSSActionsTx<'_>> {
         let transaction = self.connection.transaction()?;
 
         let tx = RSSActionsTx {
@@ -29,27 +41,56 @@ impl RSSActionsDb {
         Ok(tx)
     }
 
+    /// Open the database, creating it or migrating it to the current schema version if needed.
     pub fn open(db_path: &Path) -> Result<RSSActionsDb> {
-        // check for existing db before calling sqlite's open because it creates the file.
-        let existing_db = db_path.is_file();
+        let mut db = RSSActionsDb::open_unmigrated(db_path)?;
+        db.migrate(db_path)?;
+
+        Ok(db)
+    }
+
+    /// Open the database without creating tables or migrating it, e.g. to check its version.
+    pub fn open_unmigrated(db_path: &Path) -> Result<RSSActionsDb> {
         let connection = Connection::open(db_path)?;
         connection.pragma_update(None, "foreign_keys", true)
             .context("failed to enable foreign keys pragma")?;
 
-        let mut db = RSSActionsDb {
+        Ok(RSSActionsDb {
             connection
-        };
+        })
+    }
+
+    /// The database's schema version. See `migrations::MIGRATIONS`.
+    pub fn schema_version(&mut self) -> Result<usize> {
+        self.transaction()?.schema_version()
+    }
+
+    /// Apply any pending migrations in a single transaction. Databases that already have tables
+    /// are copied to `<db_path>.v<version>.bak` first, in case a migration loses data.
+    pub fn migrate(&mut self, db_path: &Path) -> Result<Migrated> {
+        let from_version = self.schema_version()?;
+        let applied = pending_migrations(from_version);
+        let mut migrated = Migrated { from_version, applied, backup_path: None };
+        if from_version == SCHEMA_VERSION {
+            return Ok(migrated);
+        }
 
-        // If the db is new, create the tables.
-        if !existing_db {
-            let create_tx = db.transaction()?;
-            create_tx.create_tables()
-                .context("failed to create db tables in memory")?;
-            create_tx.commit()?;
+        if from_version > 0 && from_version < SCHEMA_VERSION {
+            let mut backup_path = db_path.as_os_str().to_owned();
+            backup_path.push(format!(".v{}.bak", from_version));
+            let backup_path = PathBuf::from(backup_path);
+            std::fs::copy(db_path, &backup_path)
+                .with_context(|| format!("failed to back up the database to {} before migrating it",
+                        backup_path.display()))?;
+            migrated.backup_path = Some(backup_path);
         }
 
+        let migrate_tx = self.transaction()?;
+        migrate_tx.migrate(from_version)
+            .context("failed to migrate db tables")?;
+        migrate_tx.commit()?;
 
-        Ok(db)
+        Ok(migrated)
     }
 
     #[cfg(test)]
@@ -64,7 +105,7 @@ impl RSSActionsDb {
         };
 
         let create_tx = db.transaction()?;
-        create_tx.create_tables()
+        create_tx.migrate(0)
             .context("failed to create db tables in memory")?;
 
         create_tx.commit()?;
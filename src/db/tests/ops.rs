@@ -28,7 +28,7 @@ fn add_update_fetch_filter_succeeds() {
         PathBuf::from("/bin/false")).unwrap();
 
     tx.store_feed(&feed.alias, &feed.url).unwrap();
-    tx.store_filter(&filter).unwrap();
+    filter.id = Some(tx.store_filter(&filter).unwrap());
 
     // Update filter check for success
     filter.update_time(chrono::Utc::now());
@@ -57,7 +57,7 @@ fn add_update_update_bad_fetch_succeeds() {
         PathBuf::from("/bin/false")).unwrap();
 
     tx.store_feed(&feed.alias, &feed.url).unwrap();
-    tx.store_filter(&filter).unwrap();
+    filter.id = Some(tx.store_filter(&filter).unwrap());
 
     // Update filter check for success
     filter.update_time(chrono::Utc::now());
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use anyhow::{anyhow, Context, Result};
@@ -6,10 +7,8 @@ use rusqlite::named_params;
 use url::Url;
 
 use crate::db::{RSSActionsTx};
-use crate::models::Feed;
-use crate::models::Filter;
-
-struct FilterId(pub usize);
+use crate::models::{Disabled, Feed, FeedEntry, ReplayRecord};
+use crate::models::{BuiltinAction, Filter, FilterId, FilterSelector};
 
 /// Sort the filters list and then join with two "unit separator" (code 1F) ascii characters into a
 /// single string to serialize in the database.
@@ -31,6 +30,23 @@ fn decode_filter_keywords(keywords_packed: &str) -> Vec<String> {
         .map(|s| s.into()).collect()
 }
 
+/// Serialize a filter's built-in action as JSON for the `action` column.
+fn encode_action(action: Option<&BuiltinAction>) -> Result<Option<String>> {
+    action.map(serde_json::to_string).transpose()
+        .context("Failed to serialize the filter's action")
+}
+
+/// Deserialize from `encode_action`.
+fn decode_action(action: Option<String>) -> Result<Option<BuiltinAction>> {
+    action.as_deref().map(serde_json::from_str).transpose()
+        .context("Failed to read the filter's action from db")
+}
+
+/// Build the disabled state from the `disabled`, `disabled_reason` and `disabled_until` columns.
+fn decode_disabled(disabled: bool, reason: Option<String>, until: Option<DateTime<Utc>>) -> Option<Disabled> {
+    disabled.then_some(Disabled { reason, until })
+}
+
 
 impl<'conn> RSSActionsTx<'conn> {
     pub fn store_feed(&self, alias: &str, url: &Url) -> Result<()> {
@@ -43,46 +59,125 @@ impl<'conn> RSSActionsTx<'conn> {
     }
 
     pub fn fetch_feeds(&self) -> Result<Vec<Feed>> {
-        let mut stmt = self.tx.prepare("SELECT url, alias FROM feeds")?;
+        let mut tags = self.fetch_feed_tags()?;
+        let mut stmt = self.tx.prepare(
+            "SELECT url, alias, disabled, disabled_reason, disabled_until FROM feeds")?;
         
-        return stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        return stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
             .context("Failed to fetch feeds from db")?
             .map(|res| {
-                let (url, alias): (String, String) = res.context("Failed to read feed from db")?;
+                let (url, alias, disabled, disabled_reason, disabled_until):
+                    (String, String, bool, Option<String>, Option<DateTime<Utc>>) =
+                    res.context("Failed to read feed from db")?;
                 let url = Url::parse(&url)
                     .with_context(|| format!("Failed to parse feed {} url from database", alias))?;
                 Ok(Feed {
                     url,
-                    alias
+                    disabled: decode_disabled(disabled, disabled_reason, disabled_until),
+                    tags: tags.remove(&alias).unwrap_or_default(),
+                    alias,
                 })
             }).collect();
 
     }
 
-    pub fn store_filter(&self, filter: &Filter) -> Result<()> {
+    /// Each feed's tags in alphabetical order, keyed on the feed alias.
+    fn fetch_feed_tags(&self) -> Result<HashMap<String, Vec<String>>> {
+        let mut stmt = self.tx.prepare(
+            "SELECT feeds.alias, feed_tags.tag
+             FROM feed_tags
+             JOIN feeds
+             ON feed_tags.feed_id = feeds.id
+             ORDER BY feed_tags.tag")?;
+
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .context("Failed to fetch feed tags from db")?;
+        for row in rows {
+            let (alias, tag): (String, String) = row.context("Failed to read feed tag from db")?;
+            tags.entry(alias).or_default().push(tag);
+        }
+        Ok(tags)
+    }
+
+    /// Add tags to a feed, ignoring ones it already has. Returns the changed feed.
+    pub fn add_feed_tags(&mut self, alias: &str, tags: &[String]) -> Result<Feed> {
+        self.find_feed(alias)?;
+        for tag in tags {
+            self.tx.execute(
+                "INSERT OR IGNORE INTO feed_tags
+                  (feed_id, tag) VALUES ((SELECT id FROM feeds WHERE feeds.alias = :alias), :tag)",
+                named_params!{":alias": &alias, ":tag": tag})
+                .with_context(|| format!("Failed to add tag {} to feed {} in db", tag, alias))?;
+        }
+        self.find_feed(alias)
+    }
+
+    /// Remove tags from a feed. Returns the changed feed.
+    pub fn remove_feed_tags(&mut self, alias: &str, tags: &[String]) -> Result<Feed> {
+        self.find_feed(alias)?;
+        for tag in tags {
+            let count = self.tx.execute(
+                "DELETE FROM feed_tags
+                WHERE
+                    feed_id = (SELECT id FROM feeds WHERE feeds.alias = :alias) AND
+                    tag = :tag",
+                named_params!{":alias": &alias, ":tag": tag})
+                .with_context(|| format!("Failed to remove tag {} from feed {} in db", tag, alias))?;
+            if count == 0 {
+                return Err(anyhow!("The feed `{}` doesn't have the tag `{}`.", alias, tag));
+            }
+        }
+        self.find_feed(alias)
+    }
+
+    fn find_feed(&self, alias: &str) -> Result<Feed> {
+        self.fetch_feeds()?.into_iter()
+            .find(|feed| feed.alias == alias)
+            .ok_or_else(|| anyhow!("No feed was found that matches name `{}`", alias))
+    }
+
+    /// Store a new filter, returning its id.
+    pub fn store_filter(&self, filter: &Filter) -> Result<FilterId> {
         let keywords = encode_filter_keywords(&filter.keywords);
 
         let res = self.tx.execute(
             "INSERT INTO filters
-             (feed_id, keywords, script_path, last_updated) VALUES
-             ((SELECT id FROM feeds WHERE feeds.alias = :alias),
-              :keywords, :script_path, :last_updated)",
-            named_params!{":alias": &filter.alias, ":keywords": keywords,
-                    ":script_path": &filter.script_path.to_string_lossy(), ":last_updated": &filter.last_updated})
+             (feed_id, tag, name, keywords, script_path, action, last_updated, disabled, disabled_reason,
+              disabled_until, max_actions_per_run, dedup_group, priority, stop) VALUES
+             ((SELECT id FROM feeds WHERE feeds.alias = :alias), :tag,
+              :name, :keywords, :script_path, :action, :last_updated, :disabled, :disabled_reason, :disabled_until,
+              :max_actions_per_run, :dedup_group, :priority, :stop)",
+            named_params!{":alias": &filter.alias, ":tag": &filter.tag, ":name": &filter.name, ":keywords": keywords,
+                    ":script_path": &filter.script_path.to_string_lossy(),
+                    ":action": encode_action(filter.action.as_ref())?, ":last_updated": &filter.last_updated,
+                    ":disabled": filter.disabled.is_some(),
+                    ":disabled_reason": filter.disabled.as_ref().and_then(|d| d.reason.as_ref()),
+                    ":disabled_until": filter.disabled.as_ref().and_then(|d| d.until),
+                    ":max_actions_per_run": filter.max_actions_per_run, ":dedup_group": &filter.dedup_group,
+                    ":priority": filter.priority, ":stop": filter.stop})
             .with_context(|| format!("Failed to insert filter {:?} {:?} {:?} into db", &filter.alias, &keywords, &filter.script_path))
-            .map(|_| ()); // ignore returned number of rows modified
+            .map(|_| FilterId(self.tx.last_insert_rowid() as usize));
 
         // TODO this is a hack but the alternative is to do another select and check explicitly
         // for each error message or perhaps check the rusqlite error type.
         // Add custom error messages for certain errors.
         if let Err(err) = res {
-            // Check whether the constraint failed via the feed id select
-            if err.chain().any(|e| e.to_string() == "NOT NULL constraint failed: filters.feed_id") {
+            // Check whether the constraint failed via the feed id select. A filter must have either
+            // a feed or a tag.
+            if err.chain().any(|e| e.to_string().starts_with("CHECK constraint failed")) {
                 return Err(err).with_context(|| format!("Couldn't find a feed with alias {}.", filter.alias));
             }
             else if err.chain().any(|e| e.to_string() == "UNIQUE constraint failed: filters.feed_id, filters.keywords, filters.script_path") {
                 return Err(err).context("You can't add another filter with the same feed alias, keyword, and script path.");
             }
+            else if err.chain().any(|e| e.to_string() == "UNIQUE constraint failed: filters.tag, filters.keywords, filters.script_path") {
+                return Err(err).context("You can't add another filter with the same tag, keyword, and script path.");
+            }
+            else if err.chain().any(|e| e.to_string() == "UNIQUE constraint failed: filters.name") {
+                return Err(err).with_context(|| format!("A filter named `{}` already exists.",
+                        filter.name.as_deref().unwrap_or_default()));
+            }
             else {
                 return Err(err);
             }
@@ -92,53 +187,113 @@ impl<'conn> RSSActionsTx<'conn> {
     }
 
     pub fn fetch_filters(&self) -> Result<Vec<Filter>> {
-        Ok(self.fetch_filters_with_ids()?
-            .into_iter().map(|(_db_id, filter)| filter)
-            .collect())
-    }
-
-    fn fetch_filters_with_ids(&self) -> Result<Vec<(FilterId, Filter)>> {
         let mut stmt = self.tx.prepare(
-            "SELECT filters.id, feeds.alias, filters.keywords, filters.script_path, filters.last_updated
+            "SELECT filters.id, filters.name, COALESCE(feeds.alias, ''), filters.keywords, filters.script_path,
+                filters.last_updated, filters.disabled, filters.disabled_reason, filters.disabled_until,
+                filters.max_actions_per_run, filters.dedup_group, filters.priority, filters.stop, filters.tag,
+                filters.action
              FROM filters
              LEFT JOIN feeds
              ON filters.feed_id = feeds.id
              ORDER BY filters.last_updated DESC")?;
 
-        return stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
+        return stmt.query_map([], |row| Ok((
+                (row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?),
+                (row.get(6)?, row.get(7)?, row.get(8)?, row.get(9)?, row.get(10)?),
+                (row.get(11)?, row.get(12)?, row.get(13)?, row.get(14)?))))
             .context("Failed to fetch filters from db")?
             .map(|res| {
-                let (filter_id, alias, keywords, script_path, last_updated): (usize, String, String, String, Option<DateTime<Utc>>) =
+                #[allow(clippy::type_complexity)]
+                let ((filter_id, name, alias, keywords, script_path, last_updated),
+                     (disabled, disabled_reason, disabled_until, max_actions_per_run, dedup_group),
+                     (priority, stop, tag, action)):
+                    ((usize, Option<String>, String, String, String, Option<DateTime<Utc>>),
+                     (bool, Option<String>, Option<DateTime<Utc>>, Option<usize>, Option<String>),
+                     (i64, bool, Option<String>, Option<String>)) =
                      res.context("Failed to read feed from db")?;
 
                 let keywords = decode_filter_keywords(&keywords);
                 let script_path = PathBuf::from(script_path);
-                Ok((FilterId(filter_id), Filter {
+                Ok(Filter {
+                    id: Some(FilterId(filter_id)),
+                    name,
                     alias,
+                    tag,
                     keywords,
                     script_path,
-                    last_updated
-                }))
+                    action: decode_action(action)?,
+                    last_updated,
+                    disabled: decode_disabled(disabled, disabled_reason, disabled_until),
+                    max_actions_per_run,
+                    dedup_group,
+                    priority,
+                    stop,
+                })
             }).collect();
 
     }
 
-    /// Update filter last_updated keyed on alias, keywords, and script path
+    /// The filters run on each feed: filters on a feed as they are, and filters on a tag once for
+    /// each feed with the tag, with that feed's alias and the filter's last updated time for it.
+    pub fn fetch_feed_filters(&self) -> Result<Vec<Filter>> {
+        let feeds = self.fetch_feeds()?;
+        let mut states = self.fetch_filter_feed_states()?;
+
+        let mut feed_filters = Vec::new();
+        for filter in self.fetch_filters()? {
+            let Some(tag) = &filter.tag else {
+                feed_filters.push(filter);
+                continue;
+            };
+            let filter_id = filter.id.expect("filters from the db have ids");
+            for feed in feeds.iter().filter(|feed| feed.tags.contains(tag)) {
+                feed_filters.push(Filter {
+                    alias: feed.alias.clone(),
+                    last_updated: states.remove(&(filter_id, feed.alias.clone())).flatten(),
+                    ..filter.clone()
+                });
+            }
+        }
+        Ok(feed_filters)
+    }
+
+    /// The last updated times of filters on tags, keyed on filter id and feed alias.
+    #[allow(clippy::type_complexity)]
+    fn fetch_filter_feed_states(&self) -> Result<HashMap<(FilterId, String), Option<DateTime<Utc>>>> {
+        let mut stmt = self.tx.prepare(
+            "SELECT filter_feed_state.filter_id, feeds.alias, filter_feed_state.last_updated
+             FROM filter_feed_state
+             JOIN feeds
+             ON filter_feed_state.feed_id = feeds.id")?;
+
+        return stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .context("Failed to fetch filter states from db")?
+            .map(|res| {
+                let (filter_id, alias, last_updated): (usize, String, Option<DateTime<Utc>>) =
+                    res.context("Failed to read filter state from db")?;
+                Ok(((FilterId(filter_id), alias), last_updated))
+            }).collect();
+    }
+
+    /// Update filter last_updated keyed on the filter's id. A filter on a tag is updated for the
+    /// feed in its `alias` only.
     pub fn update_filter(&mut self, filter: &Filter) -> Result<()> {
-        let keywords = encode_filter_keywords(&filter.keywords);
+        if filter.tag.is_some() {
+            return self.update_filter_feed_state(filter);
+        }
+        // The filter is found by id, since its feed, keywords or script may have been edited while
+        // an update was running its scripts.
+        let filter_id = filter.id.map(|id| id.0);
 
         let sp = self.tx.savepoint()?;
         let res = sp.execute(
             "UPDATE filters
             SET last_updated = :last_updated
             WHERE
-                feed_id = (SELECT id FROM feeds WHERE feeds.alias = :alias) AND
-                keywords = :keywords AND
-                script_path = :script_path",
-            named_params!{":alias": &filter.alias, ":keywords": keywords,
-                    ":script_path": &filter.script_path.to_string_lossy(), ":last_updated": &filter.last_updated})
-            .with_context(|| format!("Failed to update filter {:?} {:?} {:?} in db with new time {:?}",
-                    &filter.alias, &keywords, &filter.script_path, &filter.last_updated));
+                id = :filter_id",
+            named_params!{":filter_id": &filter_id, ":last_updated": &filter.last_updated})
+            .with_context(|| format!("Failed to update filter {:?} in db with new time {:?}",
+                    filter_id, &filter.last_updated));
 
         // Add custom error messages for certain errors.
         match res {
@@ -162,10 +317,67 @@ impl<'conn> RSSActionsTx<'conn> {
         return res.map(|_| ());
     }
 
+    fn update_filter_feed_state(&mut self, filter: &Filter) -> Result<()> {
+        let filter_id = filter.id.expect("filters from the db have ids");
+        self.tx.execute(
+            "INSERT OR REPLACE INTO filter_feed_state
+              (filter_id, feed_id, last_updated) VALUES
+              (:filter_id, (SELECT id FROM feeds WHERE feeds.alias = :alias), :last_updated)",
+            named_params!{":filter_id": &filter_id.0, ":alias": &filter.alias, ":last_updated": &filter.last_updated})
+            .with_context(|| format!("A database error occurred updating filter {} on feed {} with new time {:?}",
+                    filter_id, &filter.alias, &filter.last_updated))?;
+        Ok(())
+    }
+
     // TODO return the filter deleted as read from the database.
     // TODO check whether the feed exists and return a different error in that case
     pub fn delete_filter(&mut self, alias: &str, keywords: &[String]) -> Result<()> {
-        let filters = self.fetch_filters_with_ids()?;
+        let selector = FilterSelector::Keywords(alias.into(), keywords.to_vec());
+        self.delete_selected_filter(&selector)
+            .map(|_| ())
+    }
+
+    /// Delete the filter referred to by the selector, returning it.
+    pub fn delete_selected_filter(&mut self, selector: &FilterSelector) -> Result<Filter> {
+        let filter = self.find_filter(selector)?;
+        let filter_id = filter.id.expect("filters from the db have ids");
+
+        for table in ["filter_feed_state", "replay_results"] {
+            self.tx.execute(
+                &format!("DELETE FROM {} WHERE filter_id = :filter_id", table),
+                named_params!{":filter_id": &filter_id.0})
+                .with_context(|| format!("A database error occurred deleting {}", selector))?;
+        }
+        self.tx.execute(
+            "DELETE FROM filters
+            WHERE
+                id = :filter_id",
+            named_params!{":filter_id": &filter_id.0})
+            .with_context(|| format!("A database error occurred deleting {}", selector))?;
+
+        Ok(filter)
+    }
+
+    /// Find the single filter referred to by the selector.
+    pub fn find_filter(&self, selector: &FilterSelector) -> Result<Filter> {
+        match selector {
+            FilterSelector::Id(id) => {
+                self.fetch_filters()?.into_iter()
+                    .find(|filter| filter.id == Some(*id))
+                    .ok_or_else(|| anyhow!("No filter with id {} was found in the database.", id))
+            }
+            FilterSelector::Name(name) => {
+                self.fetch_filters()?.into_iter()
+                    .find(|filter| filter.name.as_ref() == Some(name))
+                    .ok_or_else(|| anyhow!("No filter named `{}` was found in the database.", name))
+            }
+            FilterSelector::Keywords(alias, keywords) => self.find_single_filter(alias, keywords),
+        }
+    }
+
+    /// Find the single filter on the feed `alias` that has all of `keywords`.
+    fn find_single_filter(&self, alias: &str, keywords: &[String]) -> Result<Filter> {
+        let filters = self.fetch_filters()?;
 
         // NOTE: originally I wanted to do this in the db with a `LIKE %XkeyX%XwordX%` kind of
         // query but that doesn't work because you can't put a % in a parameter and have it act as
@@ -184,12 +396,12 @@ impl<'conn> RSSActionsTx<'conn> {
         // to do that (it probably is but you'd want to measure at that point).
 
         let mut matching_filters = Vec::new();
-        for (id, filter) in filters {
+        for filter in filters {
             // all user-given keywords are in the filter we're checking
             let all_keywords_match = keywords.iter().all(|k| filter.keywords.contains(k));
 
             if filter.alias == alias && all_keywords_match {
-                matching_filters.push((id, filter));
+                matching_filters.push(filter);
             }
         }
 
@@ -202,23 +414,137 @@ impl<'conn> RSSActionsTx<'conn> {
                     &keywords.join(","), &alias));
         }
 
-        let single_matching_filter = matching_filters.pop().expect("checked for 0 and >1");
+        Ok(matching_filters.pop().expect("checked for 0 and >1"))
+    }
+
+    /// Change the filter referred to by the selector with `edit`, keeping its id and
+    /// `last_updated` time. The keywords, script or built-in action, name, action limit, dedup
+    /// group, priority, and stop flag can be changed. Returns the edited filter.
+    pub fn edit_filter(&mut self, selector: &FilterSelector, edit: impl FnOnce(&mut Filter)) -> Result<Filter> {
+        let mut filter = self.find_filter(selector)?;
+        let filter_id = filter.id.expect("filters from the db have ids");
+
+        edit(&mut filter);
+        filter.keywords = decode_filter_keywords(&encode_filter_keywords(&filter.keywords));
+
+        let encoded_keywords = encode_filter_keywords(&filter.keywords);
+        let res = self.tx.execute(
+            "UPDATE filters
+            SET keywords = :keywords, script_path = :script_path, action = :action, name = :name,
+                max_actions_per_run = :max_actions_per_run, dedup_group = :dedup_group,
+                priority = :priority, stop = :stop
+            WHERE
+                id = :filter_id",
+            named_params!{":filter_id": &filter_id.0, ":keywords": encoded_keywords,
+                    ":script_path": &filter.script_path.to_string_lossy(),
+                    ":action": encode_action(filter.action.as_ref())?, ":name": &filter.name,
+                    ":max_actions_per_run": filter.max_actions_per_run, ":dedup_group": &filter.dedup_group,
+                    ":priority": filter.priority, ":stop": filter.stop})
+            .with_context(|| format!("A database error occurred editing {}", selector));
+
+        if let Err(err) = res {
+            if err.chain().any(|e| e.to_string() == "UNIQUE constraint failed: filters.feed_id, filters.keywords, filters.script_path") {
+                return Err(err).context("There is already a filter with the same feed alias, keywords, and script path.");
+            }
+            else if err.chain().any(|e| e.to_string() == "UNIQUE constraint failed: filters.tag, filters.keywords, filters.script_path") {
+                return Err(err).context("There is already a filter with the same tag, keywords, and script path.");
+            }
+            else if err.chain().any(|e| e.to_string() == "UNIQUE constraint failed: filters.name") {
+                return Err(err).with_context(|| format!("A filter named `{}` already exists.",
+                        filter.name.as_deref().unwrap_or_default()));
+            }
+            return Err(err);
+        }
+
+        Ok(filter)
+    }
+
+    /// Disable the feed, or enable it if `disabled` is None. Returns the changed feed.
+    pub fn set_feed_disabled(&mut self, alias: &str, disabled: Option<&Disabled>) -> Result<Feed> {
+        let mut feed = self.fetch_feeds()?.into_iter()
+            .find(|feed| feed.alias == alias)
+            .ok_or_else(|| anyhow!("No feed was found that matches name `{}`", alias))?;
 
         self.tx.execute(
-            "DELETE FROM filters
+            "UPDATE feeds
+            SET disabled = :disabled, disabled_reason = :reason, disabled_until = :until
+            WHERE
+                alias = :alias",
+            named_params!{":alias": &alias, ":disabled": disabled.is_some(),
+                    ":reason": disabled.and_then(|d| d.reason.as_ref()), ":until": disabled.and_then(|d| d.until)})
+            .with_context(|| format!("A database error occurred changing whether feed `{}` is disabled", alias))?;
+
+        feed.disabled = disabled.cloned();
+        Ok(feed)
+    }
+
+    /// Disable the filter referred to by the selector, or enable it if `disabled` is None. Returns
+    /// the changed filter.
+    pub fn set_filter_disabled(&mut self, selector: &FilterSelector, disabled: Option<&Disabled>) -> Result<Filter> {
+        let mut filter = self.find_filter(selector)?;
+        let filter_id = filter.id.expect("filters from the db have ids");
+
+        self.tx.execute(
+            "UPDATE filters
+            SET disabled = :disabled, disabled_reason = :reason, disabled_until = :until
             WHERE
                 id = :filter_id",
-            named_params!{":filter_id": &single_matching_filter.0.0})
-            // .with_context(|| format!("Failed to filter alias {} keywords {:?}",
-            //         &alias, &keywords));
-            .with_context(|| format!("A database error occurred deleting filter with keywords {:?} on feed {}",
-                    &keywords, &alias))
-            .map(|_| ())
+            named_params!{":filter_id": &filter_id.0, ":disabled": disabled.is_some(),
+                    ":reason": disabled.and_then(|d| d.reason.as_ref()), ":until": disabled.and_then(|d| d.until)})
+            .with_context(|| format!("A database error occurred changing whether {} is disabled", selector))?;
+
+        filter.disabled = disabled.cloned();
+        Ok(filter)
+    }
+
+    /// Change the alias of a feed. Its filters are kept since they refer to the feed by id.
+    pub fn rename_feed(&mut self, alias: &str, new_alias: &str) -> Result<()> {
+        let res = self.tx.execute(
+            "UPDATE feeds
+            SET alias = :new_alias
+            WHERE
+                alias = :alias",
+            named_params!{":alias": &alias, ":new_alias": &new_alias})
+            .with_context(|| format!("A database error occurred renaming feed `{}` to `{}`", alias, new_alias));
+
+        match res {
+            Ok(0) => Err(anyhow!("No feed was found to rename that matches name `{}`", alias)),
+            Ok(_) => Ok(()),
+            Err(err) => {
+                if err.chain().any(|e| e.to_string() == "UNIQUE constraint failed: feeds.alias") {
+                    return Err(err).with_context(|| format!("A feed with the alias `{}` already exists.", new_alias));
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Change the url of a feed.
+    pub fn edit_feed_url(&mut self, alias: &str, url: &Url) -> Result<()> {
+        let count = self.tx.execute(
+            "UPDATE feeds
+            SET url = :url
+            WHERE
+                alias = :alias",
+            named_params!{":alias": &alias, ":url": url})
+            .with_context(|| format!("A database error occurred changing the url of feed `{}` to {}", alias, url))?;
+
+        if count == 0 {
+            return Err(anyhow!("No feed was found to edit that matches name `{}`", alias));
+        }
+        Ok(())
     }
 
     pub fn delete_feed(&mut self, alias: &str) -> Result<()> {
         self.tx.pragma_update(None, "foreign_keys", true)
             .context("failed to enable foreign keys pragma")?;
+        // The feed's tags, and the states and replays of filters on its tags, go with it.
+        for table in ["feed_tags", "filter_feed_state", "replay_results"] {
+            self.tx.execute(
+                &format!("DELETE FROM {} WHERE feed_id = (SELECT id FROM feeds WHERE feeds.alias = :alias)", table),
+                named_params!{":alias": &alias})
+                .with_context(|| format!("A database error occurred deleting feed `{}`", alias))?;
+        }
         let res = self.tx.execute(
             "DELETE FROM feeds
             WHERE
@@ -238,11 +564,166 @@ impl<'conn> RSSActionsTx<'conn> {
                 }
             }
             Err(err) => {
-                return Err(err)
-                    .with_context(|| format!("A database error occurred deleting feed `{}`", alias));
+                let err = err.context(format!("A database error occurred deleting feed `{}`", alias));
+                if err.chain().any(|e| e.to_string() == "FOREIGN KEY constraint failed") {
+                    let blocking_filters = self.fetch_filters()?.into_iter()
+                        .filter(|filter| filter.alias == alias)
+                        .map(|filter| format!("  keywords: {}\tscript: {}",
+                            filter.keywords.join(", "), filter.script_path.to_string_lossy()))
+                        .collect::<Vec<_>>();
+                    return Err(err).with_context(|| format!(
+                        "The feed `{}` still has {} filters. Delete them first, or use `delete feed --cascade` \
+                        to delete the feed and its filters together:\n{}",
+                        alias, blocking_filters.len(), blocking_filters.join("\n")));
+                }
+                return Err(err);
             }
         }
 
         Ok(())
     }
+
+    /// Delete all feeds and filters. Returns the number of feeds and filters deleted.
+    pub fn delete_all(&mut self) -> Result<(usize, usize)> {
+        self.tx.execute_batch("DELETE FROM filter_feed_state; DELETE FROM feed_tags; DELETE FROM dedup_entries;
+                DELETE FROM replay_results;")
+            .context("A database error occurred deleting all feed tags, dedup entries and replay results")?;
+        let filters = self.tx.execute("DELETE FROM filters", [])
+            .context("A database error occurred deleting all filters")?;
+        let feeds = self.tx.execute("DELETE FROM feeds", [])
+            .context("A database error occurred deleting all feeds")?;
+
+        Ok((feeds, filters))
+    }
+
+    /// The identity keys of entries already processed by a filter in a dedup group, as pairs of
+    /// dedup group and entry key.
+    pub fn fetch_dedup_keys(&self) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.tx.prepare("SELECT dedup_group, entry_key FROM dedup_entries")?;
+        let keys = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .context("Failed to fetch dedup keys from db")?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read dedup key from db")?;
+        Ok(keys)
+    }
+
+    /// The entries processed in each dedup group with the time they were processed, in the order
+    /// they were recorded.
+    pub fn fetch_dedup_entries(&self) -> Result<Vec<(String, String, DateTime<Utc>)>> {
+        let mut stmt = self.tx.prepare(
+            "SELECT dedup_group, entry_key, processed_at FROM dedup_entries ORDER BY rowid")?;
+        let entries = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .context("Failed to fetch dedup entries from db")?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read dedup entry from db")?;
+        Ok(entries)
+    }
+
+    /// Record that entries with these identity keys were processed by a filter in the dedup group.
+    pub fn store_dedup_keys(&self, keys: &[(String, String)], processed_at: DateTime<Utc>) -> Result<()> {
+        for (group, key) in keys {
+            self.tx.execute(
+                "INSERT OR IGNORE INTO dedup_entries
+                  (dedup_group, entry_key, processed_at) VALUES (:group, :key, :processed_at)",
+                named_params!{":group": group, ":key": key, ":processed_at": processed_at})
+                .with_context(|| format!("Failed to insert dedup key {} of group {} into db", key, group))?;
+        }
+        Ok(())
+    }
+
+    /// The paths the built-in downloader has saved files to, with the url each was downloaded
+    /// from.
+    pub fn fetch_downloads(&self) -> Result<Vec<(PathBuf, String)>> {
+        let mut stmt = self.tx.prepare("SELECT path, url FROM downloads")?;
+        let downloads = stmt.query_map([], |row| Ok((PathBuf::from(row.get::<_, String>(0)?), row.get(1)?)))
+            .context("Failed to fetch downloads from db")?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read download from db")?;
+        Ok(downloads)
+    }
+
+    /// Record that the built-in downloader saves the files at these paths from these urls.
+    pub fn store_downloads(&self, downloads: &[(PathBuf, String)], downloaded_at: DateTime<Utc>) -> Result<()> {
+        for (path, url) in downloads {
+            self.tx.execute(
+                "INSERT OR REPLACE INTO downloads
+                  (path, url, downloaded_at) VALUES (:path, :url, :downloaded_at)",
+                named_params!{":path": path.to_string_lossy(), ":url": url, ":downloaded_at": downloaded_at})
+                .with_context(|| format!("Failed to insert download {} into db", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Record the results of replaying a filter on entries, as feed alias, entry, and the error if
+    /// the script or action failed.
+    pub fn store_replay_results(&self, filter_id: FilterId, results: &[(String, FeedEntry, Option<String>)],
+            replayed_at: DateTime<Utc>) -> Result<()> {
+        for (alias, entry, error) in results {
+            self.tx.execute(
+                "INSERT INTO replay_results
+                  (filter_id, feed_id, entry_title, entry_link, entry_date, replayed_at, error) VALUES
+                  (:filter_id, (SELECT id FROM feeds WHERE feeds.alias = :alias), :title, :link, :pub_date,
+                   :replayed_at, :error)",
+                named_params!{":filter_id": &filter_id.0, ":alias": alias, ":title": &entry.title,
+                    ":link": &entry.link, ":pub_date": &entry.pub_date, ":replayed_at": replayed_at, ":error": error})
+                .with_context(|| format!("Failed to insert the replay of filter {} on `{}` into db", filter_id, entry.title))?;
+        }
+        Ok(())
+    }
+
+    /// The results of replaying the filter, oldest first.
+    pub fn fetch_replay_results(&self, filter_id: FilterId) -> Result<Vec<ReplayRecord>> {
+        let mut stmt = self.tx.prepare(
+            "SELECT feeds.alias, entry_title, entry_link, entry_date, replayed_at, error
+             FROM replay_results
+             INNER JOIN feeds
+             ON replay_results.feed_id = feeds.id
+             WHERE filter_id = :filter_id
+             ORDER BY replay_results.id")?;
+        let records = stmt.query_map(named_params!{":filter_id": &filter_id.0}, |row| Ok(ReplayRecord {
+                feed: row.get(0)?,
+                title: row.get(1)?,
+                link: row.get(2)?,
+                pub_date: row.get(3)?,
+                replayed_at: row.get(4)?,
+                error: row.get(5)?,
+            }))
+            .context("Failed to fetch replay results from db")?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read replay result from db")?;
+        Ok(records)
+    }
+
+    /// Forget entries processed by a dedup group before `before`. Returns the number of entries
+    /// forgotten.
+    pub fn prune_dedup_keys(&self, before: DateTime<Utc>) -> Result<usize> {
+        self.tx.execute("DELETE FROM dedup_entries WHERE processed_at < :before",
+            named_params!{":before": before})
+            .context("Failed to delete old dedup keys from db")
+    }
+
+    /// Delete a feed and all of its filters. Returns the deleted filters.
+    pub fn delete_feed_cascade(&mut self, alias: &str) -> Result<Vec<Filter>> {
+        let filters: Vec<Filter> = self.fetch_filters()?.into_iter()
+            .filter(|filter| filter.alias == alias)
+            .collect();
+
+        self.tx.execute(
+            "DELETE FROM replay_results
+            WHERE
+                filter_id IN (SELECT filters.id FROM filters INNER JOIN feeds ON filters.feed_id = feeds.id
+                              WHERE feeds.alias = :alias)",
+            named_params!{":alias": &alias})
+            .with_context(|| format!("A database error occurred deleting the filters of feed `{}`", alias))?;
+        self.tx.execute(
+            "DELETE FROM filters
+            WHERE
+                feed_id = (SELECT id FROM feeds WHERE feeds.alias = :alias)",
+            named_params!{":alias": &alias})
+            .with_context(|| format!("A database error occurred deleting the filters of feed `{}`", alias))?;
+
+        self.delete_feed(alias)?;
+
+        Ok(filters)
+    }
 }
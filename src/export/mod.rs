@@ -0,0 +1,362 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{BuiltinAction, Config, Disabled, Feed, Filter, HtmlSource, ImportOutput, JsonSource};
+use crate::db::RSSActionsTx;
+
+/// The version of the export format. Increase it when the format changes in a way that older
+/// versions of rss-actions can't read.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// All feeds and filters in the database, with the filters' state, as written by `export`.
+///
+/// Filters are written without their ids, which are assigned again when they're imported.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExportDocument {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub feeds: Vec<ExportedFeed>,
+    pub filters: Vec<ExportedFilter>,
+    /// The entries each dedup group has already processed. Older exports don't have them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dedup_entries: Vec<ExportedDedupEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExportedFeed {
+    pub alias: String,
+    pub url: Url,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<Disabled>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The feed's html or json source from the config file. Sources are only kept in the config
+    /// file, so importing the feed needs the same source there.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<HtmlSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json: Option<JsonSource>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExportedFilter {
+    /// The alias of the filter's feed. Empty for a filter on a tag.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub feed: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub keywords: Vec<String>,
+    /// Empty if the filter runs a built-in action.
+    pub script_path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<BuiltinAction>,
+    pub last_updated: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<Disabled>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_actions_per_run: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedup_group: Option<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub priority: i64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stop: bool,
+    /// The last updated time of a filter on a tag for each of the tag's feeds it has run on.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub feed_last_updated: BTreeMap<String, DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ExportedDedupEntry {
+    pub group: String,
+    /// The identity key of the entry, from its guid, link or title.
+    pub key: String,
+    pub processed_at: DateTime<Utc>,
+}
+
+fn is_zero(n: &i64) -> bool {
+    *n == 0
+}
+
+/// How an import treats the feeds and filters already in the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Add feeds and filters that aren't in the database yet, and move filters' last updated times
+    /// forward if the imported one is newer. Anything that conflicts with the database is skipped
+    /// and reported.
+    Merge,
+    /// Delete all feeds and filters and then import the document.
+    Replace,
+}
+
+pub fn export(cfg: &Config, tx: &RSSActionsTx) -> Result<ExportDocument> {
+    let feeds = tx.fetch_feeds()?.into_iter()
+        .map(|feed| {
+            let feed_cfg = cfg.feed_config(&feed.alias);
+            ExportedFeed {
+                alias: feed.alias,
+                url: feed.url,
+                disabled: feed.disabled,
+                tags: feed.tags,
+                html: feed_cfg.html,
+                json: feed_cfg.json,
+            }
+        })
+        .collect();
+    let feed_filters = tx.fetch_feed_filters()?;
+    let filters = tx.fetch_filters()?.into_iter()
+        .map(|filter| ExportedFilter {
+            feed_last_updated: feed_filters.iter()
+                .filter(|feed_filter| feed_filter.tag.is_some() && feed_filter.id == filter.id)
+                .filter_map(|feed_filter| feed_filter.last_updated.map(|time| (feed_filter.alias.clone(), time)))
+                .collect(),
+            feed: filter.alias,
+            tag: filter.tag,
+            name: filter.name,
+            keywords: filter.keywords,
+            script_path: filter.script_path,
+            action: filter.action,
+            last_updated: filter.last_updated,
+            disabled: filter.disabled,
+            max_actions_per_run: filter.max_actions_per_run,
+            dedup_group: filter.dedup_group,
+            priority: filter.priority,
+            stop: filter.stop,
+        })
+        .collect();
+    let dedup_entries = tx.fetch_dedup_entries()?.into_iter()
+        .map(|(group, key, processed_at)| ExportedDedupEntry { group, key, processed_at })
+        .collect();
+
+    Ok(ExportDocument { version: EXPORT_VERSION, exported_at: Utc::now(), feeds, filters, dedup_entries })
+}
+
+pub fn import(cfg: &Config, tx: &mut RSSActionsTx, document: &ExportDocument, mode: ImportMode)
+        -> Result<ImportOutput> {
+    if document.version > EXPORT_VERSION {
+        return Err(anyhow!("The export has format version {}, but this version of rss-actions only \
+            supports up to version {}.", document.version, EXPORT_VERSION));
+    }
+
+    let mut output = ImportOutput {
+        mode,
+        deleted: None,
+        added_feeds: 0,
+        added_filters: 0,
+        updated_filters: 0,
+        conflicts: Vec::new(),
+    };
+
+    if mode == ImportMode::Replace {
+        output.deleted = Some(tx.delete_all()?);
+    }
+
+    // Feeds
+
+    let existing_feeds = tx.fetch_feeds()?;
+    let mut conflicting_feeds = HashSet::new();
+    for imported in &document.feeds {
+        let feed = Feed::new(imported.url.clone(), &imported.alias)
+            .with_context(|| format!("The imported feed `{}` is invalid", imported.alias))?;
+
+        for tag in &imported.tags {
+            crate::validate_tag(tag)
+                .with_context(|| format!("The imported feed `{}` is invalid", imported.alias))?;
+        }
+        // Without its source the feed would be parsed as RSS. A feed exported without a source
+        // may have been exported before sources were, so the config file's source is used.
+        let feed_cfg = cfg.feed_config(&feed.alias);
+        let mismatched_source = if imported.html.is_some() && imported.html != feed_cfg.html {
+            Some("html")
+        }
+        else if imported.json.is_some() && imported.json != feed_cfg.json {
+            Some("json")
+        }
+        else {
+            None
+        };
+        if let Some(source) = mismatched_source {
+            return Err(anyhow!("The imported feed `{}` is read with the {} source in the export, which doesn't \
+                match the [feeds.{}.{}] section of the config file. Copy the source from the export into the \
+                config file before importing.", feed.alias, source, feed.alias, source));
+        }
+
+        match existing_feeds.iter().find(|existing| existing.alias == feed.alias) {
+            None => {
+                tx.store_feed(&feed.alias, &feed.url)?;
+                if let Some(disabled) = &imported.disabled {
+                    tx.set_feed_disabled(&feed.alias, Some(disabled))?;
+                }
+                tx.add_feed_tags(&feed.alias, &imported.tags)?;
+                output.added_feeds += 1;
+            }
+            // The same feed keeps its tags and gains the imported ones.
+            Some(existing) if existing.url == feed.url => {
+                tx.add_feed_tags(&feed.alias, &imported.tags)?;
+            }
+            Some(existing) => {
+                output.conflicts.push(format!("Feed {}: the imported url {} is different from the existing url {}",
+                    feed.alias, feed.url, existing.url));
+                conflicting_feeds.insert(feed.alias);
+            }
+        }
+    }
+
+    // Filters
+
+    let existing_filters = tx.fetch_filters()?;
+    for imported in &document.filters {
+        let target = match &imported.tag {
+            Some(tag) => format!("tag {}", tag),
+            None => format!("feed {}", imported.feed),
+        };
+        let script = match &imported.action {
+            Some(action) => action.to_string(),
+            None => imported.script_path.to_string_lossy().into_owned(),
+        };
+        let description = format!("Filter on {} with keywords `{}` and script {}",
+            target, imported.keywords.join(", "), script);
+
+        if imported.tag.is_none() && conflicting_feeds.contains(&imported.feed) {
+            output.conflicts.push(format!("{}: skipped because its feed conflicts", description));
+            continue;
+        }
+
+        // Keywords are stored sorted and without empty keywords, so compare them the same way.
+        let mut keywords = imported.keywords.clone();
+        keywords.retain(|keyword| !keyword.is_empty());
+        keywords.sort();
+        let filter = Filter {
+            id: None,
+            name: imported.name.clone(),
+            alias: imported.feed.clone(),
+            tag: imported.tag.clone(),
+            keywords,
+            script_path: imported.script_path.clone(),
+            action: imported.action.clone(),
+            last_updated: imported.last_updated,
+            disabled: imported.disabled.clone(),
+            max_actions_per_run: imported.max_actions_per_run,
+            dedup_group: imported.dedup_group.clone(),
+            priority: imported.priority,
+            stop: imported.stop,
+        };
+        filter.validate()
+            .with_context(|| format!("{} is invalid", description))?;
+
+        let existing = existing_filters.iter().find(|existing| existing.alias == filter.alias
+            && existing.tag == filter.tag && existing.keywords == filter.keywords
+            && existing.script_path == filter.script_path && existing.action == filter.action);
+
+        match existing {
+            Some(existing) if existing.tag.is_some() => {
+                output.conflicts.extend(field_conflicts(existing, imported).into_iter()
+                    .map(|conflict| format!("{}: {}", description, conflict)));
+                if import_feed_states(tx, existing, imported, &conflicting_feeds)? {
+                    output.updated_filters += 1;
+                }
+            }
+            Some(existing) => {
+                output.conflicts.extend(field_conflicts(existing, imported).into_iter()
+                    .map(|conflict| format!("{}: {}", description, conflict)));
+                if imported.last_updated > existing.last_updated {
+                    let mut updated = existing.clone();
+                    updated.last_updated = imported.last_updated;
+                    tx.update_filter(&updated)?;
+                    output.updated_filters += 1;
+                }
+            }
+            None => {
+                let name_taken = imported.name.is_some() && existing_filters.iter()
+                    .any(|existing| existing.name == imported.name);
+                if name_taken {
+                    output.conflicts.push(format!("{}: a filter named `{}` already exists", description,
+                        imported.name.as_deref().unwrap_or_default()));
+                    continue;
+                }
+
+                let filter = Filter {
+                    id: Some(tx.store_filter(&filter)
+                        .with_context(|| format!("{}: could not be imported", description))?),
+                    ..filter
+                };
+                if filter.tag.is_some() {
+                    import_feed_states(tx, &filter, imported, &conflicting_feeds)?;
+                }
+                output.added_filters += 1;
+            }
+        }
+    }
+
+    // Dedup entries. Entries that are already recorded keep their time.
+
+    for entry in &document.dedup_entries {
+        tx.store_dedup_keys(&[(entry.group.clone(), entry.key.clone())], entry.processed_at)?;
+    }
+
+    Ok(output)
+}
+
+/// The settings of an imported filter that differ from the existing filter it matches. The
+/// existing settings are kept.
+fn field_conflicts(existing: &Filter, imported: &ExportedFilter) -> Vec<String> {
+    fn describe<T: std::fmt::Display>(value: Option<T>) -> String {
+        match value {
+            Some(value) => format!("`{}`", value),
+            None => "none".into(),
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    let mut differs = |field: &str, imported: String, existing: String| {
+        if imported != existing {
+            conflicts.push(format!("the imported {} {} is different from the existing {} {}",
+                field, imported, field, existing));
+        }
+    };
+    differs("name", describe(imported.name.as_deref()), describe(existing.name.as_deref()));
+    let disabled = |disabled: &Option<Disabled>| match disabled {
+        Some(disabled) => format!("disabled{}{}",
+            disabled.until.map(|until| format!(" until {}", until)).unwrap_or_default(),
+            disabled.reason.as_ref().map(|reason| format!(" ({})", reason)).unwrap_or_default()),
+        None => "enabled".into(),
+    };
+    differs("state", disabled(&imported.disabled), disabled(&existing.disabled));
+    differs("max actions per run", describe(imported.max_actions_per_run), describe(existing.max_actions_per_run));
+    differs("dedup group", describe(imported.dedup_group.as_deref()), describe(existing.dedup_group.as_deref()));
+    differs("priority", imported.priority.to_string(), existing.priority.to_string());
+    differs("stop flag", imported.stop.to_string(), existing.stop.to_string());
+    conflicts
+}
+
+/// Move a filter on a tag forward on each of the tag's feeds where the imported last updated time
+/// is newer, skipping feeds that conflict with the imported ones. Returns whether any of them changed.
+fn import_feed_states(tx: &mut RSSActionsTx, filter: &Filter, imported: &ExportedFilter,
+        conflicting_feeds: &HashSet<String>) -> Result<bool> {
+    let feed_filters = tx.fetch_feed_filters()?;
+    let mut updated = false;
+    for (alias, &last_updated) in &imported.feed_last_updated {
+        if conflicting_feeds.contains(alias) {
+            continue;
+        }
+        // Feeds that don't have the tag here have no state to update.
+        let Some(existing) = feed_filters.iter()
+            .find(|feed_filter| feed_filter.id == filter.id && &feed_filter.alias == alias) else {
+            continue;
+        };
+        if Some(last_updated) > existing.last_updated {
+            let mut feed_filter = existing.clone();
+            feed_filter.last_updated = Some(last_updated);
+            tx.update_filter(&feed_filter)?;
+            updated = true;
+        }
+    }
+    Ok(updated)
+}
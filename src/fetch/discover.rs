@@ -0,0 +1,47 @@
+use anyhow::Result;
+use rss::Channel;
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::{Config, DiscoveredFeed, Feed, FeedFormat};
+
+/// Download the feed's url and, if it isn't an RSS feed, look for feeds linked from it with
+/// `<link rel="alternate">` elements.
+///
+/// Returns `None` if the url is an RSS feed, otherwise the linked feeds in the order they appear
+/// in the page, which may be empty.
+pub fn discover_feeds(cfg: &Config, feed: &Feed) -> Result<Option<Vec<DiscoveredFeed>>> {
+    let document = super::fetch_document(cfg, feed)?;
+
+    if Channel::read_from(&*document).is_ok() {
+        return Ok(None);
+    }
+
+    Ok(Some(find_feed_links(&document, &feed.url)))
+}
+
+fn find_feed_links(page: &[u8], page_url: &Url) -> Vec<DiscoveredFeed> {
+    let document = Html::parse_document(&String::from_utf8_lossy(page));
+    let selector = Selector::parse("link[rel][type][href]")
+        .expect("The feed link selector is valid");
+
+    document.select(&selector)
+        .filter(|link| {
+            link.attr("rel").unwrap_or_default()
+                .split_ascii_whitespace()
+                .any(|rel| rel.eq_ignore_ascii_case("alternate"))
+        })
+        .filter_map(|link| {
+            let format = match link.attr("type")?.trim().to_ascii_lowercase().as_str() {
+                "application/rss+xml" => FeedFormat::Rss,
+                "application/atom+xml" => FeedFormat::Atom,
+                "application/feed+json" | "application/json" => FeedFormat::Json,
+                _ => return None,
+            };
+            let url = page_url.join(link.attr("href")?.trim()).ok()?;
+            let title = link.attr("title").map(str::to_string);
+
+            Some(DiscoveredFeed { url, format, title })
+        })
+        .collect()
+}
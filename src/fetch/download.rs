@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use url::Url;
+
+use crate::{Config, DownloadAction, Feed, FeedEntry};
+
+use super::http;
+
+/// The most files with the same rendered path that get a numbered name, e.g. `title (2).mp3`.
+const MAX_NUMBERED_PATHS: usize = 1000;
+
+/// The paths the built-in downloader has saved files to and the url each came from, so that a file
+/// is only treated as already downloaded if it came from the same url.
+#[derive(Debug, Default)]
+pub struct DownloadedFiles {
+    urls: HashMap<PathBuf, String>,
+    /// Paths downloaded to since the last call to `take_pending` that haven't been saved to the
+    /// database yet.
+    pending: Vec<(PathBuf, String)>,
+}
+
+impl DownloadedFiles {
+    pub fn new(downloads: Vec<(PathBuf, String)>) -> DownloadedFiles {
+        DownloadedFiles { urls: downloads.into_iter().collect(), pending: Vec::new() }
+    }
+
+    /// The paths downloaded to since the last call, to be saved to the database.
+    pub fn take_pending(&mut self) -> Vec<(PathBuf, String)> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// The path to save the url to: the first of `path`, `path (2)`, `path (3)`, ... that was
+    /// already downloaded from the url or is free. Paths downloaded from another url, or with a
+    /// file that rss-actions didn't download, are skipped.
+    fn find_path(&self, path: PathBuf, url: &str) -> Result<PathBuf> {
+        for n in 1..=MAX_NUMBERED_PATHS {
+            let candidate = if n == 1 { path.clone() } else { numbered_path(&path, n) };
+            match self.urls.get(&candidate) {
+                Some(downloaded_url) if downloaded_url == url => return Ok(candidate),
+                Some(_) => continue,
+                None if candidate.exists() => continue,
+                None => return Ok(candidate),
+            }
+        }
+        Err(anyhow!("There are already {} files named like {}", MAX_NUMBERED_PATHS, path.display()))
+    }
+
+    /// Record that the url was downloaded to the path.
+    fn record(&mut self, path: &Path, url: &str) {
+        if self.urls.insert(path.to_path_buf(), url.into()).is_none() {
+            self.pending.push((path.to_path_buf(), url.into()));
+        }
+    }
+}
+
+/// Download the entry's enclosure, or its link if it has none, to the path given by the action's
+/// template, using the feed's HTTP settings. Returns the path the file was saved to.
+///
+/// The file is written to `<path>.part` and renamed once it's complete, so a download that was
+/// interrupted, e.g. by a timeout, is resumed with a range request the next time. A file that
+/// was already downloaded from the same url isn't downloaded again, and if the path is taken by a
+/// file from another url, e.g. an entry with the same title, a number is added to the name.
+pub fn download_entry(cfg: &Config, feed: &Feed, download: &DownloadAction, entry: &FeedEntry,
+        downloads: &mut DownloadedFiles) -> Result<PathBuf> {
+    let url = DownloadAction::url(entry);
+    let path = downloads.find_path(download.file_path(&feed.alias, entry), url)?;
+    if path.exists() {
+        return Ok(path);
+    }
+
+    // The path is only recorded once the file is there, so that a failed download doesn't keep
+    // another entry's file from using it.
+    let path = download_to(cfg, feed, download, url, path)?;
+    downloads.record(&path, url);
+    Ok(path)
+}
+
+/// Download the url to the path, resuming from its partial file if there is one.
+fn download_to(cfg: &Config, feed: &Feed, download: &DownloadAction, url: &str, path: PathBuf) -> Result<PathBuf> {
+    let url = Url::parse(url)
+        .with_context(|| format!("The entry's url {} is invalid", url))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(anyhow!("Only http and https urls can be downloaded: {}", url));
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create the download directory {}", dir.display()))?;
+    }
+    let part_path = part_path(&path);
+    let offset = fs::metadata(&part_path).map_or(0, |metadata| metadata.len());
+
+    // The feed's credentials are only sent to the server the feed is on, not e.g. to a CDN hosting
+    // its enclosures, including through redirects.
+    let feed_cfg = cfg.feed_config(&feed.alias);
+    let send_secrets = url.origin() == feed.url.origin();
+    let http_cfg = cfg.feed_http_config(&feed.alias);
+    let client = http::build_client(&http_cfg, send_secrets && !feed_cfg.secret_headers.is_empty())
+        .with_context(|| format!("Invalid HTTP settings for {} rss feed", feed.alias))?;
+    let mut request = client.get(url.clone());
+    if send_secrets {
+        request = http::add_feed_secrets(request, &feed_cfg)
+            .with_context(|| format!("Failed to read the credentials for {} rss feed", feed.alias))?;
+    }
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    let response = request.send()
+        .with_context(|| format!("Failed to download {}", url))?;
+
+    let (mut file, offset) = match response.status() {
+        StatusCode::PARTIAL_CONTENT => {
+            let file = OpenOptions::new().append(true).open(&part_path)
+                .with_context(|| format!("Failed to open {}", part_path.display()))?;
+            (file, offset)
+        }
+        // There's nothing past the end of the partial file, so it's complete.
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => return finish(&part_path, path),
+        // The server doesn't support ranges, so start over.
+        status if status.is_success() => {
+            let file = File::create(&part_path)
+                .with_context(|| format!("Failed to create {}", part_path.display()))?;
+            (file, 0)
+        }
+        status => return Err(anyhow!("Failed to download {}: the server responded with {}", url, status)),
+    };
+
+    let limit = download.max_bytes.map(|max_bytes| max_bytes.saturating_sub(offset));
+    if limit.is_some_and(|limit| response.content_length().is_some_and(|len| len > limit)) {
+        let _ = fs::remove_file(&part_path);
+        return Err(too_large(&url, download));
+    }
+
+    // Like feeds, the Content-Length can be missing or wrong, so stop reading past the limit.
+    let mut body: Box<dyn Read> = match limit {
+        Some(limit) => Box::new(response.take(limit.saturating_add(1))),
+        None => Box::new(response),
+    };
+    let written = std::io::copy(&mut body, &mut file)
+        .with_context(|| format!("Failed to download {} to {}", url, part_path.display()))?;
+    if limit.is_some_and(|limit| written > limit) {
+        drop(file);
+        let _ = fs::remove_file(&part_path);
+        return Err(too_large(&url, download));
+    }
+
+    finish(&part_path, path)
+}
+
+/// `path` with ` (n)` added to the file name before the extension.
+fn numbered_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{} ({}).{}", stem, n, ext.to_string_lossy()),
+        None => format!("{} ({})", stem, n),
+    };
+    path.with_file_name(name)
+}
+
+/// Where a file is downloaded to before it's complete.
+fn part_path(path: &Path) -> PathBuf {
+    let mut part_path = path.as_os_str().to_owned();
+    part_path.push(".part");
+    PathBuf::from(part_path)
+}
+
+fn finish(part_path: &Path, path: PathBuf) -> Result<PathBuf> {
+    fs::rename(part_path, &path)
+        .with_context(|| format!("Failed to move the finished download to {}", path.display()))?;
+    Ok(path)
+}
+
+fn too_large(url: &Url, download: &DownloadAction) -> anyhow::Error {
+    anyhow!("The download of {} is larger than the limit of {} bytes.", url, download.max_bytes.unwrap_or_default())
+}
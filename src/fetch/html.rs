@@ -0,0 +1,65 @@
+use anyhow::{anyhow, Context, Result};
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+
+use crate::{FeedEntry, HtmlSource};
+
+use super::{parse_entry_date, resolve_entry_link, FetchedFeed};
+
+/// Build entries from the elements of the page matching the source's item selector.
+///
+/// Fails if any of the selectors are invalid. Items with missing or invalid data are returned as
+/// errors so that they are handled like RSS entries with missing data.
+pub fn scrape_entries(page: &[u8], page_url: &Url, source: &HtmlSource) -> Result<FetchedFeed> {
+    let item = parse_selector("item", &source.item)?;
+    let title = parse_selector("title", &source.title)?;
+    let link = parse_selector("link", &source.link)?;
+    let date = parse_selector("date", &source.date)?;
+
+    let document = Html::parse_document(&String::from_utf8_lossy(page));
+
+    let page_title = Selector::parse("title").ok()
+        .and_then(|selector| document.select(&selector).next())
+        .map(element_text);
+
+    let entries = document.select(&item)
+        .map(|item| {
+            let title = select_first(item, &title, "title").map(element_text)?;
+            let link = select_first(item, &link, "link")
+                .and_then(|link| resolve_link(link, page_url))?;
+            let date = select_first(item, &date, "date")?;
+            let date_text = date.attr("datetime").map(str::to_string)
+                .unwrap_or_else(|| element_text(date));
+            let pub_date = parse_entry_date(&date_text, source.date_format.as_deref())
+                .context("Entry date did not parse correctly.")?;
+
+            Ok(FeedEntry { title, link, pub_date, guid: None, enclosure: None })
+        })
+        .collect();
+
+    Ok(FetchedFeed { title: page_title, entries })
+}
+
+fn parse_selector(name: &str, selector: &str) -> Result<Selector> {
+    Selector::parse(selector)
+        .map_err(|err| anyhow!("Invalid {} selector `{}`: {}", name, selector, err))
+}
+
+fn select_first<'a>(item: ElementRef<'a>, selector: &Selector, name: &str) -> Result<ElementRef<'a>> {
+    item.select(selector).next()
+        .ok_or_else(|| anyhow!("Entry {} is missing.", name))
+}
+
+/// The element's text with runs of whitespace collapsed to a single space.
+fn element_text(element: ElementRef) -> String {
+    element.text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn resolve_link(element: ElementRef, page_url: &Url) -> Result<String> {
+    let link = element.attr("href").map(str::to_string)
+        .unwrap_or_else(|| element_text(element));
+    resolve_entry_link(&link, page_url)
+}
@@ -0,0 +1,134 @@
+use anyhow::{anyhow, Context, Result};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::redirect;
+use reqwest::{Certificate, Identity, Proxy};
+
+use crate::{FeedConfig, HttpConfig};
+
+use super::{body_too_large, read_limited};
+
+/// Build an HTTP client with the given settings.
+///
+/// With `same_origin_redirects`, redirects to another origin fail. reqwest only removes the auth
+/// and cookie headers when a redirect goes to another host, so this keeps headers like a feed's
+/// `secret_headers` from being sent to whatever server the feed redirects to.
+pub fn build_client(http_cfg: &HttpConfig, same_origin_redirects: bool) -> Result<Client> {
+    let mut builder = Client::builder()
+        .user_agent(http_cfg.user_agent())
+        .timeout(http_cfg.read_timeout())
+        .redirect(redirect_policy(http_cfg.max_redirects(), same_origin_redirects));
+
+    if let Some(connect_timeout) = http_cfg.connect_timeout() {
+        builder = builder.connect_timeout(connect_timeout);
+    }
+
+    match http_cfg.proxy.as_deref() {
+        Some("none") => {
+            builder = builder.no_proxy();
+        }
+        Some(proxy_url) => {
+            let proxy = Proxy::all(proxy_url)
+                .with_context(|| format!("Invalid proxy url {}", proxy_url))?;
+            builder = builder.proxy(proxy);
+        }
+        None => {}
+    }
+
+    for cert_path in http_cfg.root_certificates.iter().flatten() {
+        let pem = std::fs::read(cert_path)
+            .with_context(|| format!("Failed to read root certificate file {:?}", cert_path))?;
+        let certs = Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("Failed to parse root certificate file {:?}", cert_path))?;
+        if certs.is_empty() {
+            return Err(anyhow!("No certificates found in root certificate file {:?}", cert_path));
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    match (&http_cfg.client_certificate, &http_cfg.client_key) {
+        (Some(cert_path), Some(key_path)) => {
+            let cert = std::fs::read(cert_path)
+                .with_context(|| format!("Failed to read client certificate file {:?}", cert_path))?;
+            let key = std::fs::read(key_path)
+                .with_context(|| format!("Failed to read client key file {:?}", key_path))?;
+            let identity = Identity::from_pkcs8_pem(&cert, &key)
+                .with_context(|| format!("Failed to load client certificate {:?} with key {:?}. \
+                                The key must be an unencrypted PKCS#8 PEM file.", cert_path, key_path))?;
+            builder = builder.identity(identity);
+        }
+        (Some(_), None) => return Err(anyhow!("A client certificate was given without a client key.")),
+        (None, Some(_)) => return Err(anyhow!("A client key was given without a client certificate.")),
+        (None, None) => {}
+    }
+
+    if http_cfg.insecure == Some(true) {
+        builder = builder
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true);
+    }
+
+    builder.build()
+        .context("Failed to build HTTP client")
+}
+
+/// Follow at most `max_redirects` redirects. reqwest's `Policy::limited` counts the original url as
+/// well, so it follows one fewer than the given number.
+fn redirect_policy(max_redirects: usize, same_origin_redirects: bool) -> redirect::Policy {
+    redirect::Policy::custom(move |attempt| {
+        let leaves_origin = attempt.previous().first()
+            .is_some_and(|first| first.origin() != attempt.url().origin());
+        if attempt.previous().len() > max_redirects {
+            attempt.error(anyhow!("too many redirects (more than {})", max_redirects))
+        }
+        else if same_origin_redirects && leaves_origin {
+            let err = anyhow!("redirected to {}, which is on a different server, so the feed's secret \
+                headers weren't sent there", attempt.url());
+            attempt.error(err)
+        }
+        else {
+            attempt.follow()
+        }
+    })
+}
+
+/// Resolve the feed's secret references and add them to the request. The secrets are only held
+/// for the duration of the request, and reqwest marks the auth headers as sensitive so they are
+/// not printed in its debug output.
+///
+/// The client must be built with `same_origin_redirects` if the feed has secret headers, see
+/// `build_client`.
+pub fn add_feed_secrets(mut request: RequestBuilder, feed_cfg: &FeedConfig) -> Result<RequestBuilder> {
+    if let Some(token) = &feed_cfg.bearer_token {
+        request = request.bearer_auth(token.resolve()?.expose());
+    }
+    if let Some(auth) = &feed_cfg.basic_auth {
+        request = request.basic_auth(&auth.username, Some(auth.password.resolve()?.expose()));
+    }
+    for (name, secret) in &feed_cfg.secret_headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("Invalid header name {}", name))?;
+        // HeaderValue's error doesn't include the value, so it's safe to pass along.
+        let mut header_value = HeaderValue::from_str(secret.resolve()?.expose())
+            .with_context(|| format!("The secret `{}` is not a valid value for the header {}", secret, name))?;
+        header_value.set_sensitive(true);
+
+        request = request.header(header_name, header_value);
+    }
+
+    Ok(request)
+}
+
+/// Read the response body, failing if it is larger than `max_bytes`. We check the Content-Length
+/// first so that we can fail without reading anything, but servers can omit it or lie so we also
+/// stop reading after `max_bytes`.
+pub fn read_body_limited(response: Response, max_bytes: u64) -> Result<Vec<u8>> {
+    if response.content_length().is_some_and(|len| len > max_bytes) {
+        return Err(body_too_large(max_bytes));
+    }
+
+    read_limited(response, max_bytes)
+        .context("Failed to read response body")
+}
@@ -0,0 +1,147 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use url::Url;
+
+use crate::{FeedEntry, JsonSource};
+
+use super::{parse_entry_date, resolve_entry_link, FetchedFeed};
+
+/// Build entries from the values of the document selected by the source's items path.
+///
+/// Fails if the document isn't JSON or any of the paths are invalid. Items with missing or invalid
+/// data are returned as errors so that they are handled like RSS entries with missing data.
+pub fn read_entries(document: &[u8], feed_url: &Url, source: &JsonSource) -> Result<FetchedFeed> {
+    let items_path = JsonPath::parse("items", &source.items)?;
+    let title_path = JsonPath::parse("title", &source.title)?;
+    let link_path = JsonPath::parse("link", &source.link)?;
+    let date_path = JsonPath::parse("date", &source.date)?;
+
+    let document: Value = serde_json::from_slice(document)
+        .context("The document is not valid JSON.")?;
+
+    let mut items = items_path.select(&document);
+    if let [Value::Array(array)] = items.as_slice() {
+        items = array.iter().collect();
+    }
+
+    let entries = items.into_iter()
+        .map(|item| {
+            let title = select_string(item, &title_path)?;
+            let link = select_string(item, &link_path)
+                .and_then(|link| resolve_entry_link(&link, feed_url))?;
+            let date = select_string(item, &date_path)?;
+            let pub_date = parse_entry_date(&date, source.date_format.as_deref())
+                .context("Entry date did not parse correctly.")?;
+
+            Ok(FeedEntry { title, link, pub_date, guid: None, enclosure: None })
+        })
+        .collect();
+
+    Ok(FetchedFeed { title: None, entries })
+}
+
+/// The first value selected by the path, as a string. Numbers are converted so that e.g. unix
+/// timestamps can be used as dates.
+fn select_string(item: &Value, path: &JsonPath) -> Result<String> {
+    match path.select(item).first() {
+        Some(Value::String(s)) => Ok(s.clone()),
+        Some(Value::Number(n)) => Ok(n.to_string()),
+        Some(Value::Null) | None => Err(anyhow!("Entry {} is missing.", path.name)),
+        Some(_) => Err(anyhow!("Entry {} at `{}` is not a string or number.", path.name, path.path)),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+    All,
+}
+
+/// A parsed path into a JSON document. See `JsonSource` for the supported syntax.
+#[derive(Debug)]
+struct JsonPath {
+    /// Which of the source's paths this is, for error messages.
+    name: &'static str,
+    path: String,
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    fn parse(name: &'static str, path: &str) -> Result<JsonPath> {
+        let segments = parse_segments(path.trim())
+            .map_err(|err| anyhow!("Invalid {} path `{}`: {}", name, path, err))?;
+        Ok(JsonPath { name, path: path.to_string(), segments })
+    }
+
+    /// All values in `value` selected by the path. Missing fields and out of range indices select
+    /// nothing.
+    fn select<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        let mut selected = vec![value];
+        for segment in &self.segments {
+            selected = selected.into_iter()
+                .flat_map(|value| -> Vec<&Value> {
+                    match (segment, value) {
+                        (Segment::Field(field), Value::Object(map)) => map.get(field).into_iter().collect(),
+                        (Segment::Index(i), Value::Array(array)) => array.get(*i).into_iter().collect(),
+                        (Segment::All, Value::Array(array)) => array.iter().collect(),
+                        (Segment::All, Value::Object(map)) => map.values().collect(),
+                        _ => Vec::new(),
+                    }
+                })
+                .collect();
+        }
+        selected
+    }
+}
+
+fn parse_segments(path: &str) -> Result<Vec<Segment>> {
+    let mut rest = path.strip_prefix('$').unwrap_or(path);
+    // Paths relative to an item may start with a bare field name.
+    let relative = rest.len() == path.len();
+
+    let mut segments = Vec::new();
+    let mut first = true;
+    while !rest.is_empty() {
+        if let Some(bracketed) = rest.strip_prefix('[') {
+            let end = bracketed.find(']')
+                .ok_or_else(|| anyhow!("unclosed `[`"))?;
+            let inner = bracketed[..end].trim();
+            segments.push(parse_bracketed(inner)?);
+            rest = &bracketed[end + 1..];
+        }
+        else {
+            let dotted = match rest.strip_prefix('.') {
+                Some(dotted) => dotted,
+                None if first && relative => rest,
+                None => return Err(anyhow!("expected `.` or `[` before `{}`", rest)),
+            };
+            let end = dotted.find(['.', '[']).unwrap_or(dotted.len());
+            let field = &dotted[..end];
+            if field.is_empty() {
+                return Err(anyhow!("empty field name"));
+            }
+            segments.push(if field == "*" { Segment::All } else { Segment::Field(field.to_string()) });
+            rest = &dotted[end..];
+        }
+        first = false;
+    }
+
+    if segments.is_empty() && relative {
+        return Err(anyhow!("the path is empty"));
+    }
+    Ok(segments)
+}
+
+fn parse_bracketed(inner: &str) -> Result<Segment> {
+    if inner == "*" {
+        return Ok(Segment::All);
+    }
+    for quote in ['"', '\''] {
+        if let Some(field) = inner.strip_prefix(quote).and_then(|s| s.strip_suffix(quote)) {
+            return Ok(Segment::Field(field.to_string()));
+        }
+    }
+    inner.parse().map(Segment::Index)
+        .map_err(|_| anyhow!("`[{}]` is not an index, quoted field name or `*`", inner))
+}
@@ -0,0 +1,90 @@
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use url::Url;
+
+use crate::{Feed, FeedConfig};
+
+use super::read_limited;
+
+const DEFAULT_EXEC_TIMEOUT_SECS: u64 = 30;
+
+/// Read a feed from a `file://` url.
+pub fn read_file_feed(url: &Url, max_bytes: u64) -> Result<Vec<u8>> {
+    let path = url.to_file_path()
+        .map_err(|_| anyhow!("The url {} is not a valid local file path", url))?;
+    let file = std::fs::File::open(&path)
+        .with_context(|| format!("Failed to open feed file {:?}", path))?;
+
+    read_limited(file, max_bytes)
+        .with_context(|| format!("Failed to read feed file {:?}", path))
+}
+
+/// Run the program from an `exec:` url and return its stdout. The program is killed if it runs
+/// for longer than the feed's `exec_timeout_secs`.
+///
+/// The path of the url is the program to run, e.g. `exec:/usr/local/bin/make-feed`, and arguments
+/// come from the feed's `exec_args` setting. The feed's alias is passed to the program in the
+/// `RSSACTIONS_FEED_ALIAS` environment variable.
+pub fn run_exec_feed(feed: &Feed, feed_cfg: &FeedConfig, max_bytes: u64) -> Result<Vec<u8>> {
+    let program = exec_program(&feed.url);
+    let timeout = Duration::from_secs(feed_cfg.exec_timeout_secs.unwrap_or(DEFAULT_EXEC_TIMEOUT_SECS));
+
+    let mut child = Command::new(&program)
+        .args(&feed_cfg.exec_args)
+        .env("RSSACTIONS_FEED_ALIAS", &feed.alias)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run feed program {}", program))?;
+
+    // Read the output in separate threads so the program doesn't block on a full pipe while we
+    // wait for it to exit.
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stdout_reader = std::thread::spawn(move || read_limited(stdout, max_bytes));
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stderr_reader = std::thread::spawn(move || {
+        let mut stderr_data = Vec::new();
+        // stderr is only used for error messages so we don't need all of it, but the rest is still
+        // read so the program doesn't block or get killed writing to a closed pipe.
+        let _ = stderr.by_ref().take(64 * 1024).read_to_end(&mut stderr_data);
+        let _ = std::io::copy(&mut stderr, &mut std::io::sink());
+        String::from_utf8_lossy(&stderr_data).into_owned()
+    });
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().context("Failed to wait for feed program")? {
+            break status;
+        }
+        if start.elapsed() > timeout {
+            // Errors are ignored because the program may have exited in the meantime.
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!("Feed program {} did not finish within {} seconds", program, timeout.as_secs()));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    let stdout = stdout_reader.join()
+        .map_err(|_| anyhow!("Failed to read output of feed program {}", program))?;
+    let stderr = stderr_reader.join()
+        .map_err(|_| anyhow!("Failed to read output of feed program {}", program))?;
+
+    if !status.success() {
+        return Err(anyhow!("Feed program {} failed with {}", program, status))
+            .with_context(|| format!("stderr: \n{}", stderr));
+    }
+
+    stdout.with_context(|| format!("Failed to read output of feed program {}", program))
+}
+
+/// The program to run for an `exec:` url, i.e. its percent-decoded path.
+fn exec_program(url: &Url) -> String {
+    percent_encoding::percent_decode_str(url.path())
+        .decode_utf8_lossy()
+        .into_owned()
+}
@@ -0,0 +1,139 @@
+use std::io::Read;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rss::Channel;
+use url::Url;
+
+use crate::{Config, Feed, FeedEntry};
+
+mod discover;
+pub use discover::discover_feeds;
+
+mod download;
+pub use download::{download_entry, DownloadedFiles};
+
+mod html;
+mod http;
+mod json;
+mod local;
+
+/// The entries of a downloaded feed, each of which may have failed to parse.
+#[derive(Debug)]
+pub struct FetchedFeed {
+    /// The feed's title, if it has one.
+    pub title: Option<String>,
+    pub entries: Vec<Result<FeedEntry>>,
+}
+
+/// Download and parse a feed, using the feed's settings from the config.
+///
+/// Feeds are usually fetched over HTTP, but `file://` urls are read from disk and `exec:` urls
+/// run a program and read the feed from its stdout. If the feed's config has an `html` or `json`
+/// section, entries are read from the document using its selectors or paths instead of parsing it
+/// as RSS.
+pub fn fetch_feed(cfg: &Config, feed: &Feed) -> Result<FetchedFeed> {
+    let feed_cfg = cfg.feed_config(&feed.alias);
+    let response = fetch_document(cfg, feed)?;
+
+    if feed_cfg.html.is_some() && feed_cfg.json.is_some() {
+        return Err(anyhow!("The {} feed has both an html and a json source configured. Only one can be used.",
+            feed.alias));
+    }
+
+    if let Some(source) = &feed_cfg.json {
+        return json::read_entries(&response, &feed.url, source)
+            .with_context(|| format!("Could not read {} json feed from url {}", feed.alias, feed.url));
+    }
+
+    if let Some(source) = &feed_cfg.html {
+        return html::scrape_entries(&response, &feed.url, source)
+            .with_context(|| format!("Could not scrape {} html feed from url {}", feed.alias, feed.url));
+    }
+
+    let channel = Channel::read_from(&*response)
+        .with_context(|| format!("Could not parse {} rss feed from url {}", feed.alias, feed.url))?;
+    return Ok(FetchedFeed {
+        title: Some(channel.title().to_string()),
+        entries: channel.items().iter().map(FeedEntry::new).collect(),
+    });
+}
+
+/// Download the document at the feed's url without parsing it.
+fn fetch_document(cfg: &Config, feed: &Feed) -> Result<Vec<u8>> {
+    let feed_cfg = cfg.feed_config(&feed.alias);
+    let http_cfg = cfg.feed_http_config(&feed.alias);
+
+    let response = match feed.url.scheme() {
+        "file" => local::read_file_feed(&feed.url, http_cfg.max_body_bytes())
+            .with_context(|| format!("Failed to read {} rss feed from url {}", feed.alias, feed.url))?,
+        "exec" => local::run_exec_feed(feed, &feed_cfg, http_cfg.max_body_bytes())
+            .with_context(|| format!("Failed to run {} rss feed from url {}", feed.alias, feed.url))?,
+        _ => {
+            let client = http::build_client(&http_cfg, !feed_cfg.secret_headers.is_empty())
+                .with_context(|| format!("Invalid HTTP settings for {} rss feed", feed.alias))?;
+
+            let request = http::add_feed_secrets(client.get(feed.url.clone()), &feed_cfg)
+                .with_context(|| format!("Failed to read the credentials for {} rss feed", feed.alias))?;
+
+            let response = request.send()
+                .with_context(|| format!("Failed to download {} rss feed from url {}", feed.alias, feed.url))?;
+            http::read_body_limited(response, http_cfg.max_body_bytes())
+                .with_context(|| format!("Failed to download {} rss feed from url {}", feed.alias, feed.url))?
+        }
+    };
+    Ok(response)
+}
+
+/// Parse an entry's date from a scraped or otherwise non-RSS feed.
+///
+/// With a chrono format string, the date is parsed as a date and time with a timezone, then
+/// without a timezone, then as a date alone. Dates without a timezone are taken to be UTC and
+/// dates without a time to be midnight. Without a format string, RFC 3339 and RFC 2822 are tried.
+pub(crate) fn parse_entry_date(date: &str, format: Option<&str>) -> Result<DateTime<Utc>> {
+    let date = date.trim();
+    let parsed = match format {
+        Some(format) => DateTime::parse_from_str(date, format).map(|dt| dt.with_timezone(&Utc))
+            .or_else(|_| NaiveDateTime::parse_from_str(date, format).map(|dt| dt.and_utc()))
+            .or_else(|_| NaiveDate::parse_from_str(date, format)
+                .map(|d| d.and_time(NaiveTime::MIN).and_utc()))
+            .ok(),
+        None => DateTime::parse_from_rfc3339(date)
+            .or_else(|_| DateTime::parse_from_rfc2822(date))
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok(),
+    };
+
+    parsed.ok_or_else(|| match format {
+        Some(format) => anyhow!("Entry date `{}` does not match the format `{}`.", date, format),
+        None => anyhow!("Entry date `{}` is not an RFC 3339 or RFC 2822 date.", date),
+    })
+}
+
+/// Resolve a possibly relative entry link against the feed's url.
+pub(crate) fn resolve_entry_link(link: &str, feed_url: &Url) -> Result<String> {
+    let link = link.trim();
+    if link.is_empty() {
+        return Err(anyhow!("Entry link is missing."));
+    }
+
+    let url = feed_url.join(link).or_else(|_| Url::parse(link))
+        .with_context(|| format!("Entry link `{}` is not a valid url.", link))?;
+    Ok(url.into())
+}
+
+/// Read all of `reader`, failing if there is more than `max_bytes` of data.
+fn read_limited(reader: impl Read, max_bytes: u64) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(max_bytes.saturating_add(1)).read_to_end(&mut data)?;
+
+    if data.len() as u64 > max_bytes {
+        return Err(body_too_large(max_bytes));
+    }
+    Ok(data)
+}
+
+fn body_too_large(max_bytes: u64) -> anyhow::Error {
+    anyhow!("The response is larger than the limit of {} bytes. \
+             The limit can be changed with the max_body_bytes http setting.", max_bytes)
+}
@@ -1,7 +1,11 @@
 #![allow(clippy::needless_return)]
 
 pub(crate) mod db;
+pub(crate) mod export;
+pub use export::{ExportDocument, ExportedDedupEntry, ExportedFeed, ExportedFilter, ImportMode};
+pub(crate) mod fetch;
 pub(crate) mod update;
+pub use update::{ActionOutput, ReplayWindow, UpdateRunning, UpdateSelection};
 
 pub mod cli;
 
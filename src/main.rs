@@ -7,7 +7,15 @@ fn main() -> anyhow::Result<()> {
     let cfg = rss_actions::Config::open(cfg_dir)?;
 
     let cmd = cli_args.to_cmd()?;
-    let output = cmd.execute_console(&cfg)?;
+    let output = match cmd.execute_console(&cfg) {
+        Ok(output) => output,
+        // Use EX_TEMPFAIL so that timers and scripts can tell this apart from a failed update.
+        Err(err) if err.downcast_ref::<rss_actions::UpdateRunning>().is_some() => {
+            eprintln!("{}", err);
+            std::process::exit(75);
+        }
+        Err(err) => return Err(err),
+    };
 
     for line in output {
         println!("{}", line);
@@ -0,0 +1,200 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::FeedEntry;
+
+/// The prefix that selects a built-in action in place of a filter's script path, e.g.
+/// `builtin:download`.
+pub const BUILTIN_ACTION_PREFIX: &str = "builtin:";
+
+/// Where downloaded files are saved in the download directory by default.
+pub const DEFAULT_DOWNLOAD_TEMPLATE: &str = "{feed}/{date}-{title}.{ext}";
+
+/// The placeholders that can be used in a download path template.
+const TEMPLATE_PLACEHOLDERS: [&str; 4] = ["feed", "date", "title", "ext"];
+
+/// An action built into rss-actions that a filter runs on matching entries instead of a script.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BuiltinAction {
+    Download(DownloadAction),
+}
+
+impl BuiltinAction {
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            BuiltinAction::Download(download) => download.validate(),
+        }
+    }
+}
+
+impl std::fmt::Display for BuiltinAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuiltinAction::Download(download) => write!(f, "{}download to {}",
+                BUILTIN_ACTION_PREFIX, download.directory.join(&download.path_template).display()),
+        }
+    }
+}
+
+/// Downloads an entry's enclosure, or its link if it has none, into a directory.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DownloadAction {
+    /// The directory files are saved in. It must be absolute, since update may be run from
+    /// anywhere, e.g. by cron.
+    pub directory: PathBuf,
+    /// The path of each file in the directory, with `{feed}`, `{date}`, `{title}` and `{ext}`
+    /// replaced by the entry's values.
+    pub path_template: String,
+    /// Downloads larger than this fail and are removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+}
+
+impl DownloadAction {
+    pub fn new(directory: PathBuf, path_template: Option<String>, max_bytes: Option<u64>) -> Result<DownloadAction> {
+        let download = DownloadAction {
+            directory,
+            path_template: path_template.unwrap_or_else(|| DEFAULT_DOWNLOAD_TEMPLATE.into()),
+            max_bytes,
+        };
+        download.validate()?;
+        Ok(download)
+    }
+
+    /// Check that the directory is absolute and that the template only uses known placeholders
+    /// and stays inside the directory.
+    pub fn validate(&self) -> Result<()> {
+        if !self.directory.is_absolute() {
+            return Err(anyhow!("The download directory must be an absolute path: {}", self.directory.display()));
+        }
+        if self.max_bytes == Some(0) {
+            return Err(anyhow!("The download size limit must be at least 1 byte."));
+        }
+
+        // Filling in the template with a plain value checks the placeholders and braces.
+        let path = fill_template(&self.path_template, |_| Some("x".into()))?;
+        let stays_inside = Path::new(&path).components().all(|component| matches!(component, Component::Normal(_)));
+        if path.is_empty() || !stays_inside {
+            return Err(anyhow!("The download path template must be a relative path inside the download \
+                directory: {}", self.path_template));
+        }
+        Ok(())
+    }
+
+    /// The url downloaded for the entry: its enclosure, or its link if it has none.
+    pub fn url(entry: &FeedEntry) -> &str {
+        entry.enclosure.as_ref().map_or(&entry.link, |enclosure| &enclosure.url)
+    }
+
+    /// Where the entry's file is saved. Each value is made safe to use as a file name, so an
+    /// entry's title can't add directories to the path.
+    pub fn file_path(&self, feed_alias: &str, entry: &FeedEntry) -> PathBuf {
+        let path = fill_template(&self.path_template, |placeholder| {
+            let value = match placeholder {
+                "feed" => feed_alias.to_string(),
+                "date" => entry.pub_date.format("%Y-%m-%d").to_string(),
+                "title" => entry.title.clone(),
+                "ext" => file_extension(entry),
+                _ => return None,
+            };
+            Some(sanitize_file_name(&value))
+        }).expect("download path templates are validated when the filter is added");
+
+        self.directory.join(path)
+    }
+}
+
+/// Replace each `{placeholder}` in the template with `value(placeholder)`. `{{` and `}}` are
+/// literal braces.
+fn fill_template(template: &str, value: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut filled = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                filled.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                filled.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    placeholder.push(c);
+                }
+                if !closed {
+                    return Err(anyhow!("Unmatched `{{` in the download path template `{}`. Use `{{{{` for a \
+                        literal brace.", template));
+                }
+                let value = TEMPLATE_PLACEHOLDERS.contains(&placeholder.as_str())
+                    .then(|| value(&placeholder))
+                    .flatten()
+                    .ok_or_else(|| anyhow!("Unknown placeholder `{{{}}}` in the download path template `{}`. \
+                        Use {{feed}}, {{date}}, {{title}} or {{ext}}.", placeholder, template))?;
+                filled.push_str(&value);
+            }
+            '}' => return Err(anyhow!("Unmatched `}}` in the download path template `{}`. Use `}}}}` for a \
+                literal brace.", template)),
+            c => filled.push(c),
+        }
+    }
+    Ok(filled)
+}
+
+/// The file extension from the end of the url's path if it has one, otherwise from the
+/// enclosure's type. Links without an extension are usually web pages.
+fn file_extension(entry: &FeedEntry) -> String {
+    let url = DownloadAction::url(entry);
+    let path = url::Url::parse(url).map_or_else(|_| url.to_string(), |url| url.path().to_string());
+    let from_path = Path::new(&path).extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .filter(|ext| ext.len() <= 5 && ext.chars().all(|c| c.is_ascii_alphanumeric()));
+    if let Some(ext) = from_path {
+        return ext;
+    }
+
+    let ext = match entry.enclosure.as_ref().map(|enclosure| enclosure.mime_type.as_str()) {
+        None => "html",
+        Some("audio/mpeg") => "mp3",
+        Some("audio/mp4" | "audio/x-m4a") => "m4a",
+        Some("audio/ogg") => "ogg",
+        Some("video/mp4") => "mp4",
+        Some("application/pdf") => "pdf",
+        Some("image/jpeg") => "jpg",
+        Some("image/png") => "png",
+        Some(_) => "bin",
+    };
+    ext.into()
+}
+
+/// Replace path separators and other characters that aren't allowed in file names on common
+/// filesystems, and keep the name short enough for them.
+fn sanitize_file_name(value: &str) -> String {
+    const MAX_BYTES: usize = 200;
+
+    let mut name: String = value.chars()
+        .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+        .collect();
+    name = name.trim().trim_matches('.').to_string();
+    if name.len() > MAX_BYTES {
+        let mut end = MAX_BYTES;
+        while !name.is_char_boundary(end) {
+            end -= 1;
+        }
+        name.truncate(end);
+    }
+    if name.is_empty() {
+        name = "untitled".into();
+    }
+    name
+}
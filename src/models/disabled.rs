@@ -0,0 +1,33 @@
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Why and until when a feed or filter is disabled. Disabled feeds aren't downloaded and disabled
+/// filters aren't run by `update`, but both keep their state in the database.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Disabled {
+    /// An optional note on why it was disabled, e.g. "site is down for maintenance".
+    pub reason: Option<String>,
+    /// The time at which it's enabled again. If None, it stays disabled until it's enabled.
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl Disabled {
+    /// Whether this still applies at the time `now`, i.e. `until` hasn't passed yet.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.until.is_none_or(|until| now < until)
+    }
+}
+
+impl std::fmt::Display for Disabled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "disabled")?;
+        if let Some(until) = self.until {
+            let local_until: DateTime<Local> = until.into();
+            write!(f, " until {}", local_until)?;
+        }
+        if let Some(reason) = &self.reason {
+            write!(f, ": {}", reason)?;
+        }
+        Ok(())
+    }
+}
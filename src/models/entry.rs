@@ -0,0 +1,111 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+
+/// A wrapper class containing a validated RSS Feed entry with all relevant necessary data.
+///
+/// Entries of feeds that aren't RSS, e.g. scraped web pages, are converted into `FeedEntry`s as
+/// well so that they are processed the same way.
+#[derive(Debug, Clone)]
+pub struct FeedEntry {
+    pub title: String,
+    // We don't need to parse it into an actual URL since we don't ever fetch the resource.
+    pub link: String,
+    pub pub_date: DateTime<Utc>,
+    /// The entry's GUID, if the feed gives it one.
+    pub guid: Option<String>,
+    /// A file attached to the entry, e.g. a podcast episode.
+    pub enclosure: Option<Enclosure>,
+}
+
+/// The stored result of replaying a filter on an entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayRecord {
+    /// The alias of the feed the entry is from.
+    pub feed: String,
+    pub title: String,
+    pub link: String,
+    pub pub_date: DateTime<Utc>,
+    pub replayed_at: DateTime<Utc>,
+    /// Why the script or action failed, or None if it succeeded.
+    pub error: Option<String>,
+}
+
+/// A file attached to a feed entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enclosure {
+    pub url: String,
+    pub mime_type: String,
+    /// The length of the file in bytes. None if the feed's length isn't a number.
+    pub length: Option<u64>,
+}
+
+impl FeedEntry {
+    /// The key that identifies the entry across feeds and updates: its GUID if it has one,
+    /// otherwise its normalized link, otherwise its normalized title. Entries with the same key are
+    /// treated as the same entry by filters in a dedup group.
+    ///
+    /// Only one key is used, since feeds often reuse titles, e.g. "Weekly digest", or give every
+    /// entry the same link, and entries that share those are still different entries.
+    pub fn identity_key(&self) -> Option<String> {
+        if let Some(guid) = self.guid.as_deref().map(str::trim).filter(|guid| !guid.is_empty()) {
+            return Some(format!("guid:{}", guid));
+        }
+        let link = normalize_link(&self.link);
+        if !link.is_empty() {
+            return Some(format!("link:{}", link));
+        }
+        let title = self.title.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if !title.is_empty() {
+            return Some(format!("title:{}", title));
+        }
+        None
+    }
+
+    pub fn new(entry: &rss::Item) -> Result<FeedEntry> {
+        if entry.title.is_none() {
+            return Err(anyhow!("Entry title is missing."));
+        }
+        if entry.link.is_none() {
+            return Err(anyhow!("Entry link is missing."));
+        }
+        if entry.pub_date.is_none() {
+            return Err(anyhow!("Entry pub date is missing."));
+        }
+
+        let pub_date = entry.pub_date.as_ref().unwrap();
+        let pub_date_res = DateTime::parse_from_rfc2822(pub_date);
+        if let Err(err) = pub_date_res {
+            return Err(err)
+                .context("Entry pub date did not parse correctly.");
+        }
+        let pub_date = pub_date_res.unwrap().into();
+
+        Ok(FeedEntry {
+            title: entry.title.as_ref().unwrap().clone(),
+            link: entry.link.as_ref().unwrap().clone(),
+            pub_date,
+            guid: entry.guid.as_ref().map(|guid| guid.value.clone()),
+            enclosure: entry.enclosure.as_ref().map(|enclosure| Enclosure {
+                url: enclosure.url.clone(),
+                mime_type: enclosure.mime_type.clone(),
+                length: enclosure.length.trim().parse().ok(),
+            }),
+        })
+    }
+}
+
+/// Normalize a link so that the same page linked slightly differently compares equal: the scheme,
+/// a leading `www.`, the fragment, and a trailing slash are dropped, and the host is lowercased.
+fn normalize_link(link: &str) -> String {
+    let link = link.trim();
+    let link = link.split('#').next().unwrap_or_default();
+    let link = link.strip_prefix("https://")
+        .or_else(|| link.strip_prefix("http://"))
+        .unwrap_or(link);
+    let (host, path) = link.split_once('/').unwrap_or((link, ""));
+    let host = host.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let path = path.trim_end_matches('/');
+
+    if path.is_empty() { host.to_string() } else { format!("{}/{}", host, path) }
+}
@@ -1,12 +1,23 @@
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use url::Url;
 
+use crate::Disabled;
+
+/// The url schemes that feeds can be fetched from. `file://` urls are read from disk, and `exec:`
+/// urls run a program and read the feed from its stdout.
+const SUPPORTED_SCHEMES: [&str; 4] = ["http", "https", "file", "exec"];
+
 #[derive(Debug, Clone)]
 pub struct Feed {
-    /// The feed's URL.
+    /// The feed's URL. Either an http(s) url, a `file://` url, or an `exec:` url.
     pub url: Url,
     /// The user-chosen alias for the feed. Must not be empty.
     pub alias: String,
+    /// Set if the feed is disabled, in which case it isn't downloaded by `update`.
+    pub disabled: Option<Disabled>,
+    /// Tags used to group feeds, so that a filter on a tag applies to every feed with it.
+    pub tags: Vec<String>,
 }
 
 impl Feed {
@@ -14,9 +25,35 @@ impl Feed {
         if alias.is_empty() {
             return Err(anyhow!("A feed's alias must not be empty. {}", url));
         }
+        if !SUPPORTED_SCHEMES.contains(&url.scheme()) {
+            return Err(anyhow!("Unsupported feed url scheme `{}`. Feed urls must be one of {}: {}",
+                    url.scheme(), SUPPORTED_SCHEMES.join(", "), url));
+        }
+        if url.scheme() == "exec" && url.path().is_empty() {
+            return Err(anyhow!("An exec feed url must contain the program to run, e.g. exec:/usr/local/bin/make-feed"));
+        }
         Ok(Feed {
             url,
             alias: alias.into(),
+            disabled: None,
+            tags: Vec::new(),
         })
     }
+
+    /// Whether the feed is disabled at the time `now`.
+    pub fn is_disabled(&self, now: DateTime<Utc>) -> bool {
+        self.disabled.as_ref().is_some_and(|disabled| disabled.is_active(now))
+    }
+}
+
+/// Check that a feed tag is a single nonempty word, so that tags can be listed and given on the
+/// command line without quoting.
+pub fn validate_tag(tag: &str) -> Result<()> {
+    if tag.is_empty() {
+        return Err(anyhow!("A tag must not be empty."));
+    }
+    if tag.chars().any(|c| c.is_whitespace() || c == ',') {
+        return Err(anyhow!("A tag must not contain whitespace or commas: {}", tag));
+    }
+    Ok(())
 }
@@ -1,61 +1,261 @@
 use std::os::unix::fs::MetadataExt;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Utc};
 
+use crate::{BuiltinAction, Disabled, FeedEntry};
+
+/// The database id of a filter. It's shown by `list filters` and doesn't change for the lifetime
+/// of the filter, so it can be used to refer to a filter exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FilterId(pub usize);
+
+impl std::fmt::Display for FilterId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The ways a single filter can be referred to by commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterSelector {
+    Id(FilterId),
+    Name(String),
+    /// Feed alias, and keywords of which the filter must have all. Enough keywords to identify a
+    /// single filter on the feed are required.
+    Keywords(String, Vec<String>),
+}
+
+impl std::fmt::Display for FilterSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterSelector::Id(id) => write!(f, "filter {}", id),
+            FilterSelector::Name(name) => write!(f, "filter named `{}`", name),
+            FilterSelector::Keywords(alias, keywords) =>
+                write!(f, "filter matching `{}` on the feed `{}`", keywords.join(","), alias),
+        }
+    }
+}
+
+/// Where a new filter starts processing its feed's entries, instead of running its script on
+/// every matching entry already in the feed on its first update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterStart {
+    /// Only entries published after the newest entry currently in the feed.
+    Now,
+    /// Only entries published after this time.
+    Date(DateTime<Utc>),
+    /// Only the newest n matching entries currently in the feed, and any published after them.
+    Backlog(usize),
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Filter {
-    /// The feed alias this filter is associated with.
+    /// The filter's database id. None if the filter has not been stored yet.
+    pub id: Option<FilterId>,
+    /// An optional unique name used to refer to the filter.
+    pub name: Option<String>,
+    /// The feed alias this filter is associated with. Empty for a filter on a tag, except while
+    /// it's being run on one of the tag's feeds.
     pub alias: String,
+    /// Set if the filter is on every feed with this tag rather than on a single feed. Its last
+    /// updated time is kept separately for each feed.
+    pub tag: Option<String>,
     /// Keywords used to filter the titles of the feed entries.
     pub keywords: Vec<String>,
-    /// The path to the script to execute on matching feed entries.
+    /// The path to the script to execute on matching feed entries. Empty if the filter runs a
+    /// built-in action instead.
     pub script_path: PathBuf,
+    /// Set if the filter runs this built-in action on matching entries instead of a script.
+    pub action: Option<BuiltinAction>,
     /// The last time the filter was updated. If it has never been updated, it will be None.
     pub last_updated: Option<DateTime<Utc>>,
+    /// Set if the filter is disabled, in which case its script isn't run by `update`.
+    pub disabled: Option<Disabled>,
+    /// The most times the script is run in one update. Matching entries past the limit are
+    /// processed by later updates.
+    pub max_actions_per_run: Option<usize>,
+    /// Filters in the same dedup group run their scripts at most once per entry, so e.g. the same
+    /// item on two mirrored feeds is only handled once.
+    pub dedup_group: Option<String>,
+    /// Filters with a higher priority are run first. Filters with the same priority are run in
+    /// the order they were added.
+    pub priority: i64,
+    /// Whether lower-priority filters on the same feed skip entries this filter matches.
+    pub stop: bool,
 }
 
 impl Filter {
     pub fn new(alias: &str, keywords: Vec<String>, script_path: PathBuf) -> Result<Filter> {
-        if !script_path.is_file() {
-            return Err(anyhow!("The filter's script path is not a file: {}", script_path.to_string_lossy()));
-        }
+        validate_script_path(&script_path)?;
 
-        let script_metadata = std::fs::metadata(&script_path)
-            .with_context(|| format!("Failed to read file metadata: {:?}", script_path))?;
+        Ok(Filter::new_unchecked(alias, keywords, script_path))
+    }
 
-        // Test if the executable bit is set on user, group, or other permissions for the file.
-        if (script_metadata.mode() & 0o111) == 0 {
-            return Err(anyhow!("The filter's script path is not executable: {}", script_path.to_string_lossy()));
+    fn new_unchecked(alias: &str, keywords: Vec<String>, script_path: PathBuf) -> Filter {
+        Filter {
+            id: None,
+            name: None,
+            alias: alias.into(),
+            tag: None,
+            keywords,
+            script_path,
+            action: None,
+            last_updated: None,
+            disabled: None,
+            max_actions_per_run: None,
+            dedup_group: None,
+            priority: 0,
+            stop: false,
         }
+    }
 
-        // decided not to implement this because it's not really worth the effort but leaving it
-        // commented for future reference.
-        //
-        // If the script is executable by the owner or (TODO) the group but we're not that user or
-        // in that group (again, group TODO), report an error since we won't be able to execute the
-        // script.
-        // https://stackoverflow.com/questions/57951893/how-to-determine-the-effective-user-id-of-a-process-in-rust
-        // use the `uid` of the current process to determine the current user
-        // let current_process_metadata = std::fs::metadata("/proc/self")
-        //     .with_context(|| format!("Failed to read file metadata: {:?}", script_path))?;
+    /// A filter that runs a built-in action instead of a script.
+    pub fn with_action(alias: &str, keywords: Vec<String>, action: BuiltinAction) -> Result<Filter> {
+        action.validate()?;
 
         Ok(Filter {
-            alias: alias.into(),
-            keywords,
-            script_path,
-            last_updated: None
+            action: Some(action),
+            ..Filter::new_unchecked(alias, keywords, PathBuf::new())
         })
     }
 
+    /// A filter on every feed with the tag.
+    pub fn on_tag(tag: &str, keywords: Vec<String>, script_path: PathBuf) -> Result<Filter> {
+        let mut filter = Filter::new("", keywords, script_path)?;
+        filter.tag = Some(tag.into());
+        Ok(filter)
+    }
+
+    /// What the filter is on, e.g. `feed nyt` or `tag security`.
+    pub fn target(&self) -> String {
+        match &self.tag {
+            Some(tag) => format!("tag {}", tag),
+            None => format!("feed {}", self.alias),
+        }
+    }
+
+    /// Whether the filter is disabled at the time `now`.
+    pub fn is_disabled(&self, now: DateTime<Utc>) -> bool {
+        self.disabled.as_ref().is_some_and(|disabled| disabled.is_active(now))
+    }
+
+    /// The last updated time a new filter should start with so that only the entries chosen by
+    /// `start` are processed on its first update, given the entries currently in the feed.
+    pub fn start_time(&self, start: FilterStart, now: DateTime<Utc>, entries: &[FeedEntry]) -> Option<DateTime<Utc>> {
+        match start {
+            FilterStart::Date(date) => Some(date),
+            // With no entries in the feed yet, anything published from now on is new.
+            FilterStart::Now => Some(entries.iter().map(|entry| entry.pub_date).max().unwrap_or(now)),
+            FilterStart::Backlog(n) => {
+                let mut dates: Vec<DateTime<Utc>> = entries.iter()
+                    .filter(|entry| self.matches_keywords(&entry.title))
+                    .map(|entry| entry.pub_date)
+                    .collect();
+                dates.sort();
+                // Entries published at the same time as the newest entry skipped are skipped as
+                // well, so fewer than n entries may be processed.
+                dates.len().checked_sub(n + 1).map(|i| dates[i])
+            }
+        }
+    }
+
+    /// Orders filters by priority, highest first, then by id so that older filters go first.
+    pub fn run_order(&self) -> (std::cmp::Reverse<i64>, Option<FilterId>) {
+        (std::cmp::Reverse(self.priority), self.id)
+    }
+
     pub fn update_time(&mut self, update_time: DateTime<Utc>) {
         self.last_updated = Some(update_time);
     }
 
     pub fn matches_keywords(&self, s: &str) -> bool {
+        self.missing_keywords(s).is_empty()
+    }
+
+    /// The filter's keywords that aren't in `s`, ignoring case.
+    pub fn missing_keywords(&self, s: &str) -> Vec<String> {
         let lower_s = s.to_ascii_lowercase();
-        self.keywords.iter().all(|w| lower_s.contains(&w.to_ascii_lowercase()))
+        self.keywords.iter()
+            .filter(|w| !lower_s.contains(&w.to_ascii_lowercase()))
+            .cloned()
+            .collect()
+    }
+
+    /// Check the settings a new filter is added with, other than its script path. Filters are
+    /// checked the same way whether they're added by `add filter` or imported.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(name) = &self.name {
+            validate_filter_name(name)?;
+        }
+        if self.max_actions_per_run == Some(0) {
+            return Err(anyhow!("The limit on script runs per update must be at least 1."));
+        }
+        if let Some(dedup_group) = &self.dedup_group {
+            validate_dedup_group(dedup_group)?;
+        }
+        if let Some(tag) = &self.tag {
+            crate::validate_tag(tag)?;
+        }
+        if let Some(action) = &self.action {
+            action.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Whether an entry published at `pub_date` is newer than the last entry the filter ran its
+    /// script on.
+    pub fn is_new_entry(&self, pub_date: DateTime<Utc>) -> bool {
+        self.last_updated.is_none_or(|last_updated| last_updated < pub_date)
     }
 }
+
+/// Check that a filter name can be used. Names that are numbers aren't allowed so that they
+/// can't be confused with filter ids.
+pub fn validate_filter_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(anyhow!("A filter's name must not be empty."));
+    }
+    if name.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!("A filter's name must not be a number, so that it isn't confused with a filter id: {}", name));
+    }
+    Ok(())
+}
+
+/// Check that a dedup group's name isn't blank.
+pub fn validate_dedup_group(dedup_group: &str) -> Result<()> {
+    if dedup_group.trim().is_empty() {
+        return Err(anyhow!("A dedup group's name must not be empty."));
+    }
+    Ok(())
+}
+
+/// Check that a filter's script exists and is executable.
+pub fn validate_script_path(script_path: &Path) -> Result<()> {
+    if !script_path.is_file() {
+        return Err(anyhow!("The filter's script path is not a file: {}", script_path.to_string_lossy()));
+    }
+
+    let script_metadata = std::fs::metadata(script_path)
+        .with_context(|| format!("Failed to read file metadata: {:?}", script_path))?;
+
+    // Test if the executable bit is set on user, group, or other permissions for the file.
+    if (script_metadata.mode() & 0o111) == 0 {
+        return Err(anyhow!("The filter's script path is not executable: {}", script_path.to_string_lossy()));
+    }
+
+    // decided not to implement this because it's not really worth the effort but leaving it
+    // commented for future reference.
+    //
+    // If the script is executable by the owner or (TODO) the group but we're not that user or
+    // in that group (again, group TODO), report an error since we won't be able to execute the
+    // script.
+    // https://stackoverflow.com/questions/57951893/how-to-determine-the-effective-user-id-of-a-process-in-rust
+    // use the `uid` of the current process to determine the current user
+    // let current_process_metadata = std::fs::metadata("/proc/self")
+    //     .with_context(|| format!("Failed to read file metadata: {:?}", script_path))?;
+
+    Ok(())
+}
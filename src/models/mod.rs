@@ -1,8 +1,14 @@
+mod action;
+mod disabled;
+mod entry;
 mod feed;
 mod filter;
 
-pub use feed::Feed;
-pub use filter::Filter;
+pub use action::{BuiltinAction, DownloadAction, BUILTIN_ACTION_PREFIX, DEFAULT_DOWNLOAD_TEMPLATE};
+pub use disabled::Disabled;
+pub use entry::{Enclosure, FeedEntry, ReplayRecord};
+pub use feed::{validate_tag, Feed};
+pub use filter::{validate_dedup_group, validate_filter_name, validate_script_path, Filter, FilterId, FilterSelector, FilterStart};
 
 #[cfg(test)]
 mod test;
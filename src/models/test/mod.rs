@@ -1,7 +1,9 @@
-use crate::models::{Feed, Filter};
+use crate::models::{validate_tag, Disabled, DownloadAction, Enclosure, Feed, FeedEntry, Filter, FilterStart};
 
 use std::path::PathBuf;
 
+use chrono::{TimeZone, Utc};
+
 #[test]
 fn feed_alias_must_be_nonempty() {
     let res = Feed::new(url::Url::parse("https://example.org").unwrap(), "");
@@ -11,6 +13,31 @@ fn feed_alias_must_be_nonempty() {
     assert!(err.to_string().contains("A feed's alias must not be empty."));
 }
 
+#[test]
+fn feed_tags_are_single_words() {
+    assert!(validate_tag("security").is_ok());
+    for tag in ["", "two words", "a,b", "tab\t"] {
+        assert!(validate_tag(tag).is_err(), "{:?} was accepted as a tag", tag);
+    }
+}
+
+#[test]
+fn feed_url_scheme_must_be_supported() {
+    for url in ["https://example.org/feed.rss", "http://example.org", "file:///var/lib/feeds/feed.rss",
+                "exec:/usr/local/bin/make-feed"] {
+        let res = Feed::new(url::Url::parse(url).unwrap(), "example");
+        assert!(res.is_ok(), "Feed with url {} failed: {:?}", url, res.unwrap_err());
+    }
+
+    let res = Feed::new(url::Url::parse("ftp://example.org/feed.rss").unwrap(), "example");
+    assert!(res.is_err(), "Feed with ftp url did not error");
+    assert!(res.unwrap_err().to_string().contains("Unsupported feed url scheme `ftp`"));
+
+    let res = Feed::new(url::Url::parse("exec:").unwrap(), "example");
+    assert!(res.is_err(), "Feed with empty exec url did not error");
+    assert!(res.unwrap_err().to_string().contains("An exec feed url must contain the program to run"));
+}
+
 #[test]
 fn filter_new_time_is_none_and_update_is_some() {
     let res = Filter::new("example_feed", Vec::new(), PathBuf::from("/bin/false"));
@@ -45,3 +72,89 @@ fn filter_new_succeeds_on_executable_file() {
     let res = Filter::new("example_feed", Vec::new(), PathBuf::from("/bin/false"));
     assert!(res.is_ok(), "Creating new filter failed: {:?}", res.unwrap_err());
 }
+
+#[test]
+fn disabled_until_time_expires() {
+    let now = chrono::Utc::now();
+    let mut filter = Filter::new("example_feed", Vec::new(), PathBuf::from("/bin/false")).unwrap();
+    assert!(!filter.is_disabled(now));
+
+    filter.disabled = Some(Disabled { reason: None, until: None });
+    assert!(filter.is_disabled(now));
+
+    filter.disabled = Some(Disabled { reason: None, until: Some(now + chrono::Duration::hours(1)) });
+    assert!(filter.is_disabled(now));
+    assert!(!filter.is_disabled(now + chrono::Duration::hours(1)));
+}
+
+#[test]
+fn filter_start_time_from_backlog() {
+    let filter = Filter::new("example_feed", vec!["match".into()], PathBuf::from("/bin/true")).unwrap();
+    let date = |hour| Utc.with_ymd_and_hms(2020, 1, 1, hour, 0, 0).unwrap();
+    let entry = |title: &str, hour| FeedEntry {
+        title: title.into(),
+        link: "https://example.org".into(),
+        pub_date: date(hour),
+        guid: None,
+        enclosure: None,
+    };
+    let entries = vec![entry("match 1", 3), entry("other", 4), entry("match 2", 1), entry("match 3", 2)];
+    let now = date(12);
+
+    assert_eq!(filter.start_time(FilterStart::Now, now, &entries), Some(date(4)));
+    assert_eq!(filter.start_time(FilterStart::Now, now, &[]), Some(now));
+    assert_eq!(filter.start_time(FilterStart::Date(date(2)), now, &entries), Some(date(2)));
+
+    // The newest matching entries are processed, so the start is the newest one left out
+    assert_eq!(filter.start_time(FilterStart::Backlog(0), now, &entries), Some(date(3)));
+    assert_eq!(filter.start_time(FilterStart::Backlog(1), now, &entries), Some(date(2)));
+    assert_eq!(filter.start_time(FilterStart::Backlog(2), now, &entries), Some(date(1)));
+    assert_eq!(filter.start_time(FilterStart::Backlog(3), now, &entries), None);
+    assert_eq!(filter.start_time(FilterStart::Backlog(10), now, &entries), None);
+}
+
+#[test]
+fn entry_identity_key_is_normalized() {
+    let entry = |title: &str, link: &str, guid: Option<&str>| FeedEntry {
+        title: title.into(),
+        link: link.into(),
+        pub_date: Utc::now(),
+        guid: guid.map(String::from),
+        enclosure: None,
+    };
+
+    let key = entry("Release 1.0", "https://example.org/releases/1.0", Some(" abc ")).identity_key();
+    assert_eq!(key.as_deref(), Some("guid:abc"));
+
+    let key = entry("release 1.0", "https://WWW.Example.org/releases/1.0/#notes", None).identity_key();
+    assert_eq!(key.as_deref(), Some("link:example.org/releases/1.0"));
+
+    let key = entry("  Release   1.0 ", "", Some("")).identity_key();
+    assert_eq!(key.as_deref(), Some("title:release 1.0"));
+
+    assert_eq!(entry("", "", None).identity_key(), None);
+}
+
+#[test]
+fn download_file_paths_are_sanitized() {
+    let download = DownloadAction::new(PathBuf::from("/downloads"), None, None).unwrap();
+    let mut entry = FeedEntry {
+        title: "../../etc/passwd: a \"story\"?".into(),
+        link: "https://example.org/posts/1".into(),
+        pub_date: Utc.with_ymd_and_hms(2020, 1, 2, 3, 0, 0).unwrap(),
+        guid: None,
+        enclosure: None,
+    };
+    assert_eq!(download.file_path("news", &entry),
+        PathBuf::from("/downloads/news/2020-01-02-_.._etc_passwd_ a _story__.html"));
+
+    entry.title = "   ".into();
+    entry.enclosure = Some(Enclosure { url: "https://cdn.example.org/episode?id=1".into(), mime_type: "audio/mpeg".into(), length: None });
+    assert_eq!(download.file_path("news", &entry), PathBuf::from("/downloads/news/2020-01-02-untitled.mp3"));
+
+    let download = DownloadAction::new(PathBuf::from("/downloads"), Some("{{{title}}}.{ext}".into()), None).unwrap();
+    entry.enclosure = Some(Enclosure { url: "https://cdn.example.org/Episode.MP3".into(), mime_type: "".into(), length: None });
+    entry.title = "x".repeat(300);
+    let name = download.file_path("news", &entry).file_name().unwrap().to_string_lossy().into_owned();
+    assert_eq!(name, format!("{{{}}}.mp3", "x".repeat(200)));
+}
@@ -0,0 +1,45 @@
+use std::collections::HashSet;
+
+use crate::{FeedEntry, Filter};
+
+/// The entries already processed by filters in each dedup group, so that filters in the same
+/// group run their scripts at most once per entry, both within an update and across updates.
+#[derive(Debug, Default)]
+pub struct DedupState {
+    /// Pairs of dedup group and entry identity key.
+    seen: HashSet<(String, String)>,
+    /// Keys recorded during this update that haven't been saved to the database yet.
+    pending: Vec<(String, String)>,
+}
+
+impl DedupState {
+    pub fn new(keys: Vec<(String, String)>) -> DedupState {
+        DedupState { seen: keys.into_iter().collect(), pending: Vec::new() }
+    }
+
+    /// Whether a filter in the same dedup group as `filter` already processed the entry.
+    pub fn is_duplicate(&self, filter: &Filter, entry: &FeedEntry) -> bool {
+        let Some(group) = &filter.dedup_group else {
+            return false;
+        };
+        entry.identity_key()
+            .is_some_and(|key| self.seen.contains(&(group.clone(), key)))
+    }
+
+    /// Record that `filter` processed the entry.
+    pub fn record(&mut self, filter: &Filter, entry: &FeedEntry) {
+        let Some(group) = &filter.dedup_group else {
+            return;
+        };
+        if let Some(key) = entry.identity_key() {
+            if self.seen.insert((group.clone(), key.clone())) {
+                self.pending.push((group.clone(), key));
+            }
+        }
+    }
+
+    /// The keys recorded since the last call, to be saved to the database.
+    pub fn take_pending(&mut self) -> Vec<(String, String)> {
+        std::mem::take(&mut self.pending)
+    }
+}
@@ -0,0 +1,88 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+
+/// An exclusive advisory lock on `<db_path>.lock`, held while an update runs so that two updates
+/// can't run the same scripts on the same entries before either has committed. Replaying,
+/// importing and migrating the database hold it too, since they'd otherwise change the filters an
+/// update is saving.
+///
+/// The lock is released by the OS when the file is closed, including when the process crashes or
+/// is killed, so a lock file left behind by a dead update never blocks later ones. The file only
+/// records which process has the lock, for the error message.
+#[derive(Debug)]
+pub struct UpdateLock {
+    _file: File,
+}
+
+/// The error returned when another update holds the update lock.
+#[derive(Debug)]
+pub struct UpdateRunning {
+    pub lock_path: PathBuf,
+    /// The pid and start time of the running update, if they could be read from the lock file.
+    pub pid: Option<u32>,
+    pub started: Option<DateTime<Utc>>,
+}
+
+impl UpdateLock {
+    pub fn acquire(db_path: &Path) -> Result<UpdateLock> {
+        let mut lock_path = db_path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        let lock_path = PathBuf::from(lock_path);
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open the update lock file {}", lock_path.display()))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut contents = String::new();
+                // If the other update hasn't written its pid yet there's just less to report.
+                let _ = file.read_to_string(&mut contents);
+                let mut lines = contents.lines();
+                return Err(UpdateRunning {
+                    lock_path,
+                    pid: lines.next().and_then(|pid| pid.parse().ok()),
+                    started: lines.next().and_then(|started| DateTime::parse_from_rfc3339(started).ok())
+                        .map(|started| started.into()),
+                }.into());
+            }
+            Err(TryLockError::Error(err)) => {
+                return Err(err)
+                    .with_context(|| format!("Failed to lock the update lock file {}", lock_path.display()));
+            }
+        }
+
+        file.set_len(0)
+            .and_then(|_| file.rewind())
+            .and_then(|_| writeln!(file, "{}\n{}", std::process::id(), Utc::now().to_rfc3339()))
+            .with_context(|| format!("Failed to write to the update lock file {}", lock_path.display()))?;
+
+        Ok(UpdateLock { _file: file })
+    }
+}
+
+impl std::fmt::Display for UpdateRunning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Another update is already running")?;
+        match (self.pid, self.started) {
+            (Some(pid), Some(started)) => {
+                let local_started: DateTime<Local> = started.into();
+                write!(f, " (pid {}, started {})", pid, local_started)?;
+            }
+            (Some(pid), None) => write!(f, " (pid {})", pid)?,
+            _ => {}
+        }
+        write!(f, ". It holds the lock file {}.", self.lock_path.display())
+    }
+}
+
+impl std::error::Error for UpdateRunning {}
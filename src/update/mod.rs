@@ -1,88 +1,158 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::process::ExitStatus;
 
 use anyhow::{anyhow, Result, Context};
-use chrono::prelude::*;
-use reqwest::blocking::Client;
-use rss::Channel;
+use chrono::{DateTime, Utc};
 
-use crate::{Feed, Filter};
-use crate::db::RSSActionsTx;
+use crate::{BuiltinAction, Config, Feed, FeedEntry, Filter, FilterId};
+use crate::fetch::{DownloadedFiles, FetchedFeed};
+use crate::db::RSSActionsDb;
 use crate::UpdateOutput;
 
+mod lock;
+pub use lock::{UpdateLock, UpdateRunning};
+
+mod replay;
+pub use replay::{replay, ReplayWindow};
+
+mod dedup;
+use dedup::DedupState;
+
 /// Stdout, Stderr, ExitStatus
 pub type ProcessOutput = (String, String, ExitStatus);
 
-static RSSACTIONS_USER_AGENT: &str = concat!(
-    env!("CARGO_PKG_NAME"),
-    "/",
-    env!("CARGO_PKG_VERSION"),
-);
-
-/// A wrapper class containing a validated RSS Feed entry with all relevant necessary data.
+/// What running a filter's script or built-in action on an entry produced.
 #[derive(Debug)]
-struct FeedEntry {
-    title: String,
-    // We don't need to parse it into an actual URL since we don't ever fetch the resource.
-    link: String,
-    pub_date: DateTime<Utc>,
+pub enum ActionOutput {
+    Script(ProcessOutput),
+    /// The path the built-in downloader saved the entry's file to.
+    Downloaded(PathBuf),
 }
 
-impl FeedEntry {
-    pub fn new(entry: &rss::Item) -> Result<FeedEntry> {
-        if entry.title.is_none() {
-            return Err(anyhow!("Entry title is missing."));
-        }
-        if entry.link.is_none() {
-            return Err(anyhow!("Entry link is missing."));
+/// The feeds and filters an update is restricted to. Each list that isn't empty restricts the
+/// update further, so e.g. selecting a feed and a filter only runs the filter if it's on that feed.
+/// The default selection updates everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdateSelection {
+    /// Only download these feeds and run their filters.
+    pub feeds: Vec<String>,
+    /// Only run these filters, and only download their feeds.
+    pub filters: Vec<FilterId>,
+    /// Only download feeds with any of these tags and run their filters.
+    pub tags: Vec<String>,
+}
+
+impl UpdateSelection {
+    pub fn is_all(&self) -> bool {
+        self.feeds.is_empty() && self.filters.is_empty() && self.tags.is_empty()
+    }
+
+    fn includes_feed(&self, feed: &Feed) -> bool {
+        (self.feeds.is_empty() || self.feeds.contains(&feed.alias))
+            && (self.tags.is_empty() || feed.tags.iter().any(|tag| self.tags.contains(tag)))
+    }
+
+    /// Whether a feed is downloaded even if none of its filters are selected.
+    fn includes_feed_without_filters(&self, feed: &Feed) -> bool {
+        self.filters.is_empty() && self.includes_feed(feed)
+    }
+
+    /// Whether the filter is run on `feed`, which it's on either directly or through a tag.
+    fn includes(&self, filter: &Filter, feed: &Feed) -> bool {
+        self.includes_feed(feed)
+            && (self.filters.is_empty() || filter.id.is_some_and(|id| self.filters.contains(&id)))
+    }
+
+    /// Check that the selected feeds and filters exist, so that a typo doesn't silently update
+    /// nothing.
+    fn validate(&self, feeds: &[Feed], filters: &[Filter]) -> Result<()> {
+        for alias in &self.feeds {
+            if !feeds.iter().any(|feed| &feed.alias == alias) {
+                return Err(anyhow!("No feed was found to update that matches name `{}`", alias));
+            }
         }
-        if entry.pub_date.is_none() {
-            return Err(anyhow!("Entry pub date is missing."));
+        for tag in &self.tags {
+            if !feeds.iter().any(|feed| feed.tags.contains(tag)) {
+                return Err(anyhow!("No feed with the tag `{}` was found to update.", tag));
+            }
         }
-
-        let pub_date = entry.pub_date.as_ref().unwrap();
-        let pub_date_res = DateTime::parse_from_rfc2822(pub_date);
-        if let Err(err) = pub_date_res {
-            return Err(err)
-                .context("Entry pub date did not parse correctly.");
+        for id in &self.filters {
+            if !filters.iter().any(|filter| filter.id == Some(*id)) {
+                return Err(anyhow!("No filter with id {} was found in the database.", id));
+            }
         }
-        let pub_date = pub_date_res.unwrap().into();
-
-        Ok(FeedEntry {
-            title: entry.title.as_ref().unwrap().clone(),
-            link: entry.link.as_ref().unwrap().clone(),
-            pub_date,
-        })
+        Ok(())
     }
 }
 
-pub fn update(tx: &mut RSSActionsTx) -> Result<UpdateOutput> {
+/// Download the feeds and run the filters' scripts on new matching entries, restricted to the
+/// selected feeds and filters.
+///
+/// Downloads and scripts run outside of any transaction, and each filter's new `last_updated` time
+/// is committed as soon as its scripts have run, so that if the update is killed partway through
+/// the scripts that already ran aren't run again by the next update.
+pub fn update(cfg: &Config, db: &mut RSSActionsDb, selection: &UpdateSelection) -> Result<UpdateOutput> {
     // TODO instead of fetching all feeds and then all filters, could do join in db. maybe faster
     // maybe not, doesn't really matter to be honest.
-    let feeds = tx.fetch_feeds()?;
-    let filters = tx.fetch_filters()?;
+    let (feeds, mut filters) = {
+        let tx = db.transaction()?;
+        let feeds = tx.fetch_feeds()?;
+        selection.validate(&feeds, &tx.fetch_filters()?)?;
+        // Filters on a tag are run once for each feed with the tag, with separate last updated
+        // times, as if they were a filter on each feed.
+        (feeds, tx.fetch_feed_filters()?)
+    };
+    let now = Utc::now();
+    // Stop filters make lower-priority filters skip entries whether or not they're selected, so
+    // that updating only some filters doesn't change which entries each filter handles.
+    let stop_filters: Vec<Filter> = filters.iter()
+        .filter(|filter| filter.stop && !filter.is_disabled(now))
+        .cloned()
+        .collect();
+    filters.retain(|filter| feeds.iter()
+        .find(|feed| feed.alias == filter.alias)
+        .is_some_and(|feed| selection.includes(filter, feed)));
     if filters.is_empty() {
         return Ok(UpdateOutput {
             executed_feeds: Vec::new(),
             executed_filters: Vec::new(),
+            skipped_filters: Vec::new(),
+            deferred: Vec::new(),
+            deduplicated: Vec::new(),
+            downloads: Vec::new(),
             successes: 0,
             failures: 0,
             updates: 0,
         });
     }
-    let mut filters_map = join_feeds_and_filters(&feeds, filters);
+    let (mut filters_map, skipped_filters) = join_feeds_and_filters(&feeds, filters, now);
+    // Feeds that aren't in the feeds-filters map aren't downloaded.
+    filters_map.retain(|alias, filters| !filters.is_empty() || feeds.iter()
+        .find(|feed| &feed.alias == alias)
+        .is_some_and(|feed| selection.includes_feed_without_filters(feed)));
 
     let mut output = UpdateOutput {
         executed_feeds: Vec::new(),
         executed_filters: Vec::new(),
+        skipped_filters,
+        deferred: Vec::new(),
+        deduplicated: Vec::new(),
+        downloads: Vec::new(),
         successes: 0,
         failures: 0,
         updates: 0,
     };
 
-    // Download feeds
+    // Download feeds, skipping disabled ones
 
-    let download_results = download_feeds(feeds);
+    let feeds: Vec<Feed> = feeds.into_iter()
+        .filter(|feed| filters_map.contains_key(&feed.alias))
+        .collect();
+    if feeds.is_empty() {
+        return Ok(output);
+    }
+    let download_results = download_feeds(cfg, feeds);
     // If all downloads resulted in an error, network is probably down.
     if download_results.iter().all(|(_, res)| res.is_err()) {
         // We know all results are errors so take the first one
@@ -96,19 +166,22 @@ pub fn update(tx: &mut RSSActionsTx) -> Result<UpdateOutput> {
     // Otherwise, report errors individually for each download and immediately fail all relevant
     // filters.
     for (feed, res) in download_results {
-        if let Ok(channel) = res {
-            let parsed_items_res: Vec<_> = channel.items().iter().map(FeedEntry::new).collect();
+        if let Ok(fetched) = res {
+            let parsed_items_res = fetched.entries;
 
             // If any entries are missing data, fail the whole feed
             // TODO: is this really the best idea? maybe just ignore the ones with missing data
             if parsed_items_res.iter().any(|res| res.is_err()) {
                 if let Some(filters) = filters_map.remove(&feed.alias) {
+                    let error_count = parsed_items_res.iter().filter(|res| res.is_err()).count();
+                    // Keep the first error so that e.g. a wrong date format can be debugged.
+                    let first_err = parsed_items_res.into_iter().find_map(Result::err).unwrap();
+
                     output.failures += filters.len();
                     output.executed_feeds.push(
                         (feed.clone(),
-                        Err(anyhow!("{} entries in feed {} had data errors",
-                            parsed_items_res.iter().filter(|res| res.is_err()).count(),
-                            &feed.alias))));
+                        Err(first_err.context(format!("{} entries in feed {} had data errors",
+                            error_count, &feed.alias)))));
                 }
                 continue;
             }
@@ -133,34 +206,68 @@ pub fn update(tx: &mut RSSActionsTx) -> Result<UpdateOutput> {
         }
     }
 
-    // For each feed, for each filter, process the feed's entries with the filter
+    // For each filter, process its feed's entries with the filter. Filters are run by priority and
+    // then in the order they were added, across all feeds, so that the first filter in a dedup
+    // group wins.
 
-    for (feed, entries) in feed_data {
-        let filters = match filters_map.get(&feed.alias) {
-            Some(filters) => filters,
-            None => {
-                // If the feed failed to download or there are no filters for the feed, just
-                // continue.
-                continue;
-            }
+    let mut runs: Vec<(&Feed, &Filter, &[FeedEntry])> = feed_data.iter()
+        .filter_map(|(feed, entries)| {
+            // If the feed failed to download there are no filters for it in the map.
+            filters_map.get(&feed.alias).map(|filters| (feed, filters, entries))
+        })
+        .flat_map(|(feed, filters, entries)| filters.iter().map(move |filter| (feed, filter, entries.as_slice())))
+        .collect();
+    runs.sort_by_key(|(_, filter, _)| filter.run_order());
+
+    let mut state = {
+        let tx = db.transaction()?;
+        tx.prune_dedup_keys(now - cfg.dedup_retention())?;
+        let state = RunState {
+            budget: cfg.max_actions_per_run,
+            dedup: DedupState::new(tx.fetch_dedup_keys()?),
+            downloads: DownloadedFiles::new(tx.fetch_downloads()?),
         };
-
-        let results = process_filters(filters, &entries);
-        for (filter, res) in results {
-            if let Ok((updated_filter, was_updated, script_outputs)) = res {
-
+        tx.commit().context("Failed to delete old dedup keys")?;
+        state
+    };
+    for (feed, filter, entries) in runs {
+        let stoppers: Vec<&Filter> = stop_filters.iter()
+            .filter(|stopper| stopper.alias == filter.alias && stopper.priority > filter.priority)
+            .collect();
+        let res = process_single_filter(cfg, feed, filter, entries, &stoppers, &mut state);
+
+        // Entries whose scripts ran are recorded for the dedup group even if a later script
+        // failed, since they won't be run again.
+        let tx = db.transaction()?;
+        tx.store_dedup_keys(&state.dedup.take_pending(), Utc::now())?;
+        tx.store_downloads(&state.downloads.take_pending(), Utc::now())?;
+        tx.commit()
+            .with_context(|| format!("Failed to save the entries processed by filter {:?}", filter))?;
+
+        match res {
+            Ok(run) => {
                 // Originally I was going to only update filters if the script executed
                 // successfully on all entries matched by the filter, but that would mean that if a
                 // scripted succeeded on one entry and failed on the next, every time update was
                 // run the script would be re-run on that entry, forcing scripts to be idempotent
                 // to work in all cases.
-                tx.update_filter(&updated_filter)?;
+                //
+                // If the filter can't be saved, e.g. because it was deleted while its scripts ran,
+                // only that filter fails and the other filters are still run.
+                if let Err(err) = save_filter(db, &run.updated_filter) {
+                    output.executed_filters.push((filter.clone(), Err(err)));
+                    output.failures += 1;
+                    continue;
+                }
 
-                if was_updated { output.updates += 1; }
-                output.executed_filters.push((filter.clone(), Ok(script_outputs)));
+                if run.was_updated { output.updates += 1; }
+                if run.deferred > 0 { output.deferred.push((filter.clone(), run.deferred)); }
+                output.deduplicated.extend(run.deduplicated.into_iter().map(|entry| (filter.clone(), entry)));
+                output.downloads.extend(run.downloads.into_iter().map(|path| (filter.clone(), path)));
+                output.executed_filters.push((filter.clone(), Ok(run.script_outputs)));
                 output.successes += 1;
             }
-            else if let Err(err) = res {
+            Err(err) => {
                 output.executed_filters.push((filter.clone(), Err(err)));
                 output.failures += 1;
             }
@@ -170,41 +277,96 @@ pub fn update(tx: &mut RSSActionsTx) -> Result<UpdateOutput> {
     Ok(output)
 }
 
-/// Run filters' scripts on each entry that matches, and return filters with an updated
-/// `last_updated` time if the filters' script successfully finishes, whether the filter was
-/// actually updated, and the script's stdout and stderr.
-#[allow(clippy::type_complexity)]
-fn process_filters(filters: &[Filter], entries: &[FeedEntry])
-        -> Vec<(Filter, Result<(Filter, bool, Vec<ProcessOutput>)>)> {
+/// Commit the filter's new last updated time.
+fn save_filter(db: &mut RSSActionsDb, filter: &Filter) -> Result<()> {
+    let mut tx = db.transaction()?;
+    tx.update_filter(filter)?;
+    tx.commit()
+        .with_context(|| format!("Failed to save the last updated time of filter {:?}", filter))
+}
 
-    filters.iter().map(|filter| {
-        (filter.clone(), process_single_filter(filter, entries))
-    })
-    .collect()
+/// What the filters run by one update share.
+struct RunState {
+    /// The number of scripts that can still be run by all filters together, if there's a limit.
+    budget: Option<usize>,
+    dedup: DedupState,
+    downloads: DownloadedFiles,
 }
 
-/// Returns the filter with a possibly updated time, a bool indicating whether the filter was
-/// updated, and the output of scripts run.
+/// The result of running a filter's script on its feed's new matching entries.
+struct FilterRun {
+    /// The filter with its last updated time moved to the newest entry processed.
+    updated_filter: Filter,
+    /// Whether the last updated time changed.
+    was_updated: bool,
+    script_outputs: Vec<ProcessOutput>,
+    /// The files saved by the built-in downloader.
+    downloads: Vec<PathBuf>,
+    /// The number of matching entries left for later updates because the filter's
+    /// `max_actions_per_run` or the global budget was reached.
+    deferred: usize,
+    /// Entries skipped because a filter in the same dedup group already processed them.
+    deduplicated: Vec<FeedEntry>,
+}
+
+/// Run the filter's script or built-in action on each new entry that matches, skipping entries that another filter in
+/// its dedup group already processed or that a higher-priority stop filter in `stoppers` matches.
+///
+/// The state's `budget` is the number of scripts that can still be run by all filters together,
+/// and is reduced by each script run.
 ///
 /// Currently the entire filter fails if the script fails on a single entry. This is because it's
 /// easier but also because if we updated the filter's last_updated field there would be no way to
 /// retry failed entries.
-fn process_single_filter(filter: &Filter, entries: &[FeedEntry]) -> Result<(Filter, bool, Vec<ProcessOutput>)> {
+fn process_single_filter(cfg: &Config, feed: &Feed, filter: &Filter, entries: &[FeedEntry], stoppers: &[&Filter],
+        state: &mut RunState) -> Result<FilterRun> {
     // The entries must be sorted by pub date for the most_recent_updated to be computed properly.
     assert!(entries.windows(2).all(|s| s[0].pub_date <= s[1].pub_date));
 
+    let new_entries: Vec<&FeedEntry> = entries.iter()
+        .filter(|entry| filter.matches_keywords(&entry.title) && filter.is_new_entry(entry.pub_date))
+        .collect();
+
+    let limit = match (filter.max_actions_per_run, state.budget) {
+        (Some(max_actions), Some(budget)) => Some(max_actions.min(budget)),
+        (max_actions, budget) => max_actions.or(budget),
+    };
+
     let mut most_recent_updated = filter.last_updated;
     let mut script_outputs = Vec::new();
-    for entry in entries {
-        if filter.matches_keywords(&entry.title) &&
-                (filter.last_updated.is_none() || filter.last_updated.unwrap() < entry.pub_date) {
-            most_recent_updated = Some(entry.pub_date);
-
-            let script_output = run_script(filter, entry)
-                .with_context(|| format!("Script failed for filter on feed {}, keywords {}, script {}",
-                        filter.alias, filter.keywords.join(", "), filter.script_path.to_string_lossy()))?;
-            script_outputs.push(script_output);
+    let mut downloads = Vec::new();
+    let mut deduplicated = Vec::new();
+    let mut actions_run = 0;
+    let mut processed = 0;
+    for entry in &new_entries {
+        // Deferred entries are processed by later updates since they're newer than the last entry
+        // processed. Entries published at the same time can't be told apart by the last updated
+        // time, so they're never split, even if that goes over the limit.
+        let at_limit = limit.is_some_and(|limit| actions_run >= limit);
+        if at_limit && most_recent_updated != Some(entry.pub_date) {
+            break;
+        }
+        processed += 1;
+        most_recent_updated = Some(entry.pub_date);
+
+        if stoppers.iter().any(|stopper| stopper.matches_keywords(&entry.title)) {
+            continue;
+        }
+        if state.dedup.is_duplicate(filter, entry) {
+            deduplicated.push((*entry).clone());
+            continue;
+        }
+
+        match run_action(cfg, feed, filter, entry, &mut state.downloads)? {
+            ActionOutput::Script(script_output) => script_outputs.push(script_output),
+            ActionOutput::Downloaded(path) => downloads.push(path),
         }
+        // Only actions that succeed count towards the limits.
+        actions_run += 1;
+        if let Some(budget) = &mut state.budget {
+            *budget = budget.saturating_sub(1);
+        }
+        state.dedup.record(filter, entry);
     }
 
     let was_updated = most_recent_updated != filter.last_updated;
@@ -217,7 +379,29 @@ fn process_single_filter(filter: &Filter, entries: &[FeedEntry]) -> Result<(Filt
         }
         updated_filter
     };
-    Ok((updated_filter, was_updated, script_outputs))
+    Ok(FilterRun {
+        updated_filter,
+        was_updated,
+        script_outputs,
+        downloads,
+        deferred: new_entries.len() - processed,
+        deduplicated,
+    })
+}
+
+/// Run the filter's built-in action on the entry if it has one, otherwise its script.
+fn run_action(cfg: &Config, feed: &Feed, filter: &Filter, entry: &FeedEntry, downloads: &mut DownloadedFiles)
+        -> Result<ActionOutput> {
+    match &filter.action {
+        Some(BuiltinAction::Download(download)) => crate::fetch::download_entry(cfg, feed, download, entry, downloads)
+            .map(ActionOutput::Downloaded)
+            .with_context(|| format!("Download failed for filter on feed {}, keywords {}",
+                    filter.alias, filter.keywords.join(", "))),
+        None => run_script(filter, entry)
+            .map(ActionOutput::Script)
+            .with_context(|| format!("Script failed for filter on feed {}, keywords {}, script {}",
+                    filter.alias, filter.keywords.join(", "), filter.script_path.to_string_lossy())),
+    }
 }
 
 /// Returns a pair of Strings (stdout, stderr) with the script's output if it succeeded, or an error message with the
@@ -242,48 +426,42 @@ fn run_script(filter: &Filter, entry: &FeedEntry) -> Result<(String, String, Exi
     }
 }
 
-fn download_feeds(feeds: Vec<Feed>) -> Vec<(Feed, Result<Channel>)> {
+fn download_feeds(cfg: &Config, feeds: Vec<Feed>) -> Vec<(Feed, Result<FetchedFeed>)> {
     return feeds.into_iter()
         .map(|feed| {
-            let res = download_single_feed(&feed);
+            let res = crate::fetch::fetch_feed(cfg, &feed);
             (feed, res)
         })
         .collect();
 }
 
-fn download_single_feed(feed: &Feed) -> Result<Channel> {
-    let client = Client::builder()
-        .user_agent(RSSACTIONS_USER_AGENT)
-        .timeout(std::time::Duration::from_secs(30))
-        .build().unwrap();
-
-    let response = client.get(feed.url.clone()).send()
-        .with_context(|| format!("Failed to download {} rss feed from url {}", feed.alias, feed.url))?
-        .bytes()
-        .with_context(|| format!("Failed to download {} rss feed from url {}", feed.alias, feed.url))?;
-
-    return Channel::read_from(&*response)
-        .with_context(|| format!("Could not parse {} rss feed from url {}", feed.alias, feed.url));
-}
-
 /// This is just a join on feeds and filters where feed.alias = filter.alias.
 /// We could do this at the database layer if we really wanted.
 /// The output hashmap's key is the feed alias.
-fn join_feeds_and_filters(feeds: &[Feed], filters: Vec<Filter>) -> HashMap<String, Vec<Filter>> {
+///
+/// Feeds and filters that are disabled at the time `now` are left out of the map. The filters left
+/// out, either because they're disabled or their feed is, are returned separately.
+fn join_feeds_and_filters(feeds: &[Feed], filters: Vec<Filter>, now: DateTime<Utc>)
+        -> (HashMap<String, Vec<Filter>>, Vec<Filter>) {
     let mut filters_map: HashMap<String, Vec<Filter>> = HashMap::new();
+    let mut skipped_filters = Vec::new();
     for feed in feeds {
-        filters_map.insert(feed.alias.clone(), Vec::new());
+        if !feed.is_disabled(now) {
+            filters_map.insert(feed.alias.clone(), Vec::new());
+        }
     }
     for filter in filters.into_iter() {
         let alias = filter.alias.clone();
         match filters_map.get_mut(&alias) {
+            Some(_) if filter.is_disabled(now) => skipped_filters.push(filter),
             Some(feed_filters) => feed_filters.push(filter),
+            None if feeds.iter().any(|feed| feed.alias == alias) => skipped_filters.push(filter),
             // This should never happen because the database would error first due to constraint
             // violations.
             None => unreachable!("Missing feed {} for filter {:?}", alias, filter),
         }
     }
-    return filters_map;
+    return (filters_map, skipped_filters);
 }
 
 // async fn download_feeds(feeds: &[Feed]) -> Vec<Result<Channel>> {
@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+
+use crate::{Config, Feed, FeedEntry, Filter, FilterSelector, ReplayOutput};
+use crate::db::RSSActionsDb;
+use crate::fetch::DownloadedFiles;
+
+use super::dedup::DedupState;
+use super::run_action;
+
+/// Which of the entries a filter matches are replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayWindow {
+    /// Entries published at or after this time.
+    Since(DateTime<Utc>),
+    /// The newest n entries.
+    Last(usize),
+}
+
+/// Run a filter's script or built-in action again on the entries in the window that match its keywords, whether or
+/// not they're newer than the filter's last update. A filter on a tag is replayed on each feed with
+/// the tag, with the window applied to each feed separately.
+///
+/// Entries are read from the feed as it is now, since entries aren't stored. The filter's last
+/// updated time isn't changed, so the next update runs the script on the same entries it would
+/// have otherwise, including any replayed entries that are still newer than the last update.
+///
+/// The result for each entry is stored, and entries the script succeeded on are recorded for the
+/// filter's dedup group so that other filters in the group don't run on them again.
+pub fn replay(cfg: &Config, db: &mut RSSActionsDb, selector: &FilterSelector, window: ReplayWindow)
+        -> Result<ReplayOutput> {
+    let (filter, runs, mut dedup, mut downloads) = {
+        let tx = db.transaction()?;
+        let filter = tx.find_filter(selector)?;
+        let feeds = tx.fetch_feeds()?;
+        // Like update, a filter on a tag runs on each of the tag's feeds with that feed's state.
+        let runs: Vec<(Feed, Filter)> = tx.fetch_feed_filters()?.into_iter()
+            .filter(|instance| instance.id == filter.id)
+            .filter_map(|instance| feeds.iter()
+                .find(|feed| feed.alias == instance.alias)
+                .map(|feed| (feed.clone(), instance)))
+            .collect();
+        if runs.is_empty() {
+            return Err(match &filter.tag {
+                Some(tag) => anyhow!("No feed has the tag `{}`, so there's nothing to replay.", tag),
+                None => anyhow!("Couldn't find a feed with alias {}.", filter.alias),
+            });
+        }
+        (filter, runs, DedupState::new(tx.fetch_dedup_keys()?), DownloadedFiles::new(tx.fetch_downloads()?))
+    };
+
+    // Every feed is read before anything is run, so a feed that fails doesn't leave the replay
+    // half done.
+    let mut feed_entries = Vec::new();
+    for (feed, instance) in &runs {
+        feed_entries.push(replayed_entries(cfg, feed, instance, window)?);
+    }
+
+    let mut results = Vec::new();
+    let mut still_new = 0;
+    for ((feed, instance), entries) in runs.iter().zip(feed_entries) {
+        for entry in entries {
+            let res = run_action(cfg, feed, instance, &entry, &mut downloads);
+            if res.is_ok() {
+                dedup.record(instance, &entry);
+            }
+            if instance.is_new_entry(entry.pub_date) {
+                still_new += 1;
+            }
+            results.push((feed.alias.clone(), entry, res));
+        }
+    }
+
+    let now = Utc::now();
+    let records: Vec<(String, FeedEntry, Option<String>)> = results.iter()
+        .map(|(alias, entry, res)| (alias.clone(), entry.clone(), res.as_ref().err().map(|err| format!("{:#}", err))))
+        .collect();
+    let tx = db.transaction()?;
+    tx.store_replay_results(filter.id.expect("filters from the db have ids"), &records, now)?;
+    tx.store_dedup_keys(&dedup.take_pending(), now)?;
+    tx.store_downloads(&downloads.take_pending(), now)?;
+    tx.commit().context("Failed to save the results of the replay")?;
+
+    Ok(ReplayOutput { filter, window, results, still_new })
+}
+
+/// The feed's entries in the window that match the filter's keywords, sorted by date.
+fn replayed_entries(cfg: &Config, feed: &Feed, filter: &Filter, window: ReplayWindow) -> Result<Vec<FeedEntry>> {
+    let fetched = crate::fetch::fetch_feed(cfg, feed)?;
+    // Like update, don't run anything on a feed with missing data, since its entries may not be
+    // the ones update would see.
+    let error_count = fetched.entries.iter().filter(|res| res.is_err()).count();
+    let mut entries = Vec::new();
+    for res in fetched.entries {
+        match res {
+            Ok(entry) => entries.push(entry),
+            Err(err) => return Err(err.context(format!("{} entries in feed {} had data errors",
+                error_count, feed.alias))),
+        }
+    }
+
+    let mut entries: Vec<FeedEntry> = entries.into_iter()
+        .filter(|entry| filter.matches_keywords(&entry.title))
+        .collect();
+    entries.sort_by_key(|entry| entry.pub_date);
+    match window {
+        ReplayWindow::Since(since) => entries.retain(|entry| entry.pub_date >= since),
+        ReplayWindow::Last(n) => {
+            let skip = entries.len().saturating_sub(n);
+            entries.drain(..skip);
+        }
+    }
+    Ok(entries)
+}
@@ -0,0 +1,135 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{EditFilterCmd, FilterId, FilterSelector, ListFiltersCmd, UpdateCmd};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+fn script_runs(log_path: &std::path::Path) -> usize {
+    let runs = std::fs::read_to_string(log_path).map_or(0, |log| log.matches("rss action script end").count());
+    let _ = std::fs::remove_file(log_path);
+    runs
+}
+
+#[test]
+/// A filter's limit defers the newer matching entries to the next update.
+fn filter_limit_defers_entries() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    let mut cmd = example_add_filter_local1(vec!["example"], script_path);
+    cmd.0.max_actions_per_run = Some(1);
+    let lines = cmd.execute(&cfg).unwrap().output();
+    assert!(lines.contains(&"Max script runs per update: 1".to_string()));
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.deferred.len(), 1);
+    assert_eq!(output.deferred[0].1, 1);
+    let lines = output.output();
+    assert!(lines.contains(&"1 entries deferred to later updates because filters reached the limit on script runs:".to_string()),
+        "{:?}", lines);
+    assert!(lines.contains(&"  filter 1 on feed local1: 1 entries".to_string()));
+    let log = std::fs::read_to_string(&log_path).unwrap();
+    assert!(log.contains("title: Pizza Example marshmallow"));
+    assert_eq!(script_runs(&log_path), 1);
+
+    // The deferred entry is processed by the next update
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert!(output.deferred.is_empty());
+    let log = std::fs::read_to_string(&log_path).unwrap();
+    assert!(log.contains("title: Example entry NYC"));
+    assert_eq!(script_runs(&log_path), 1);
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.updates, 0);
+    assert_eq!(script_runs(&log_path), 0);
+}
+
+#[test]
+/// Entries published at the same time are processed together even if that goes over the limit,
+/// since the next update couldn't tell which of them had been processed.
+fn filter_limit_does_not_split_same_pub_date() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries_same_pubdate.rss").unwrap()).execute(&cfg).unwrap();
+    let mut cmd = example_add_filter_local1(vec!["example"], script_path);
+    cmd.0.max_actions_per_run = Some(1);
+    cmd.execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert!(output.deferred.is_empty());
+    assert_eq!(script_runs(&log_path), 2);
+}
+
+#[test]
+/// The global limit is shared by all filters, and filters past it defer all their entries.
+fn global_limit_is_shared_by_filters() {
+    let (dir, mut cfg) = temp_config();
+    cfg.max_actions_per_run = Some(2);
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["example"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], script_path).execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(script_runs(&log_path), 2);
+    assert_eq!(output.deferred.len(), 1);
+    assert_eq!(output.deferred[0].1, 2);
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(script_runs(&log_path), 2);
+    assert!(output.deferred.is_empty());
+}
+
+#[test]
+/// Scripts that fail don't use up the global limit.
+fn global_limit_ignores_failed_scripts() {
+    let (dir, mut cfg) = temp_config();
+    cfg.max_actions_per_run = Some(2);
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], "/bin/false".into()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["example"], script_path).execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.failures, 1);
+    assert_eq!(script_runs(&log_path), 2);
+    assert!(output.deferred.is_empty());
+}
+
+#[test]
+/// A filter's limit can be changed and removed.
+fn edit_filter_limit() {
+    let (dir, cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["example"], script_path).execute(&cfg).unwrap();
+
+    let edit = |new_max_actions| EditFilterCmd {
+        filter: FilterSelector::Id(FilterId(1)),
+        new_keywords: None,
+        new_script_path: None,
+        new_action: None,
+        new_name: None,
+        new_max_actions,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    };
+    edit(Some(Some(5))).execute(&cfg).unwrap();
+    assert_eq!(ListFiltersCmd.execute(&cfg).unwrap().filters[0].max_actions_per_run, Some(5));
+    edit(Some(None)).execute(&cfg).unwrap();
+    assert_eq!(ListFiltersCmd.execute(&cfg).unwrap().filters[0].max_actions_per_run, None);
+
+    let err = edit(Some(Some(0))).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "The limit on script runs per update must be at least 1.");
+}
@@ -0,0 +1,141 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{ExportCmd, ImportCmd, ListFiltersCmd, UpdateCmd};
+use rss_actions::ImportMode;
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+fn script_runs(log_path: &std::path::Path) -> usize {
+    let runs = std::fs::read_to_string(log_path).map_or(0, |log| log.matches("rss action script end").count());
+    let _ = std::fs::remove_file(log_path);
+    runs
+}
+
+#[test]
+/// Filters in the same dedup group on mirrored feeds only run their scripts once per entry, and
+/// the oldest filter wins.
+fn dedup_group_runs_script_once_per_entry() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    for mut cmd in [example_add_filter_local1(vec!["example"], script_path.clone()),
+                    example_add_filter_local2(vec!["example"], script_path.clone())] {
+        cmd.0.dedup_group = Some("mirrors".into());
+        cmd.execute(&cfg).unwrap();
+    }
+    // Not in the group, so it runs on every entry
+    example_add_filter_local2(vec![], script_path).execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(script_runs(&log_path), 4);
+    assert_eq!(output.successes, 3);
+    assert_eq!(output.deduplicated.len(), 2);
+    assert!(output.deduplicated.iter().all(|(filter, _)| filter.alias == "local2" && !filter.keywords.is_empty()));
+
+    let lines = output.output();
+    assert!(lines.contains(&"2 entries skipped because another filter in their dedup group already processed them:".to_string()),
+        "{:?}", lines);
+
+    // The deduplicated filter still moves forward
+    let filters = ListFiltersCmd.execute(&cfg).unwrap().filters;
+    assert!(filters.iter().all(|filter| filter.last_updated.is_some()));
+}
+
+#[test]
+/// Entries processed by a filter in a dedup group are remembered across updates, and entries with
+/// a GUID are matched by it rather than by their link or title.
+fn dedup_group_is_remembered_across_updates() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    // Its only entry has the same GUID as the newer entry of two_entries.rss, but a different link
+    // and title.
+    example_add_feed_local2(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+
+    let mut cmd = example_add_filter_local1(vec!["nyc"], script_path.clone());
+    cmd.0.dedup_group = Some("group".into());
+    cmd.execute(&cfg).unwrap();
+    UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(script_runs(&log_path), 1);
+
+    let mut cmd = example_add_filter_local2(vec![], script_path);
+    cmd.0.dedup_group = Some("group".into());
+    let lines = cmd.execute(&cfg).unwrap().output();
+    assert!(lines.contains(&"Dedup group: group".to_string()));
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(script_runs(&log_path), 0);
+    assert_eq!(output.deduplicated.len(), 1);
+    assert_eq!(output.deduplicated[0].1.title, "Example entry");
+}
+
+#[test]
+/// Entries that share a title and link but have different GUIDs, like a weekly digest, are all
+/// processed.
+fn dedup_group_keeps_entries_with_different_guids() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let item = |guid: &str, date: &str| format!("<item><title>Weekly digest</title>\
+        <link>http://www.example.com/digest</link><guid isPermaLink=\"false\">{}</guid>\
+        <pubDate>{}</pubDate></item>", guid, date);
+    let feed = format!("<?xml version=\"1.0\" encoding=\"UTF-8\" ?><rss version=\"2.0\"><channel>\
+        <title>Digest</title><link>http://www.example.com/</link><description>d</description>{}{}\
+        </channel></rss>",
+        item("digest-2", "Sun, 13 Sep 2009 16:20:00 +0000"), item("digest-1", "Sun, 06 Sep 2009 16:20:00 +0000"));
+    let feed_path = dir.path().join("digest.rss");
+    std::fs::write(&feed_path, feed).unwrap();
+
+    example_add_feed_local1(url::Url::from_file_path(&feed_path).unwrap()).execute(&cfg).unwrap();
+    let mut cmd = example_add_filter_local1(vec![], script_path);
+    cmd.0.dedup_group = Some("group".into());
+    cmd.execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(script_runs(&log_path), 2);
+    assert!(output.deduplicated.is_empty());
+}
+
+#[test]
+/// Entries processed by a dedup group are forgotten after the retention window, and when the
+/// database is replaced by an import.
+fn dedup_entries_are_pruned() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    let mut cmd = example_add_filter_local1(vec![], script_path.clone());
+    cmd.0.dedup_group = Some("mirrors".into());
+    cmd.execute(&cfg).unwrap();
+    UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(script_runs(&log_path), 2);
+
+    // Replacing the database with filters that haven't run yet and no dedup entries doesn't keep
+    // the old dedup entries
+    let mut document = ExportCmd(None).execute(&cfg).unwrap().document;
+    document.filters[0].last_updated = None;
+    assert_eq!(document.dedup_entries.len(), 2);
+    document.dedup_entries.clear();
+    let export_path = dir.path().join("export.json");
+    std::fs::write(&export_path, serde_json::to_string(&document).unwrap()).unwrap();
+    ImportCmd(export_path, ImportMode::Replace).execute(&cfg).unwrap();
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(script_runs(&log_path), 2);
+    assert!(output.deduplicated.is_empty());
+
+    // With no retention, the mirror's filter runs on entries the first filter already processed
+    cfg.dedup_retention_days = Some(0);
+    let mut cmd = example_add_filter_local2(vec![], script_path);
+    cmd.0.dedup_group = Some("mirrors".into());
+    cmd.execute(&cfg).unwrap();
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(script_runs(&log_path), 2);
+    assert!(output.deduplicated.is_empty());
+}
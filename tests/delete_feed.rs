@@ -1,7 +1,7 @@
 mod test_utils;
 use test_utils::*;
 
-use rss_actions::{RSSActionCmd, DeleteFeedCmd, ListFeedsCmd};
+use rss_actions::{RSSActionCmd, DeleteFeedCmd, DeleteFeedCascadeCmd, ListFeedsCmd, ListFiltersCmd};
 use rss_actions::ConsoleOutput;
 
 
@@ -9,11 +9,13 @@ use rss_actions::ConsoleOutput;
 // - delete with feed and no filters
 // - delete with feed and filter on other feed
 // - add filter, fail to delete feed, delete filter, delete feed
+// - delete with --cascade, deleting the feed's filters
 //
 // failure
 // - delete with nonexistant feed
 // - delete with filter on feed
 //   - delete with multiple filters on feed
+//   - error lists the filters
 
 #[test]
 /// Delete a non-existant feed.
@@ -147,3 +149,61 @@ fn delete_feed_suceeds_after_deleting_filter() {
 
 }
 
+
+#[test]
+/// The error from deleting a feed with filters names the filters and mentions --cascade.
+fn delete_feed_with_filters_err_lists_filters() {
+    let (_dir, cfg) = temp_config();
+
+    example_add_feed1().execute(&cfg).unwrap();
+    example_add_feed2().execute(&cfg).unwrap();
+    example_add_filter1().execute(&cfg).unwrap();
+    example_add_filter5().execute(&cfg).unwrap();
+    example_add_filter4().execute(&cfg).unwrap();
+
+    let res = DeleteFeedCmd("example_1".into()).execute(&cfg);
+    let err = res.unwrap_err();
+    let message = err.to_string();
+    assert!(message.starts_with("The feed `example_1` still has 2 filters."), "Incorrect error: {}", message);
+    assert!(message.contains("`delete feed --cascade`"), "Incorrect error: {}", message);
+    assert!(message.contains("keywords: test\tscript: "), "Incorrect error: {}", message);
+    assert!(message.contains("keywords: keyword, uwu\tscript: /bin/false"), "Incorrect error: {}", message);
+    assert!(!message.contains("other_keyword"), "Incorrect error: {}", message);
+}
+
+#[test]
+/// Deleting a feed with --cascade deletes its filters too, and only its filters.
+fn delete_feed_cascade() {
+    let (_dir, cfg) = temp_config();
+
+    example_add_feed1().execute(&cfg).unwrap();
+    example_add_feed2().execute(&cfg).unwrap();
+    example_add_filter1().execute(&cfg).unwrap();
+    example_add_filter5().execute(&cfg).unwrap();
+    example_add_filter4().execute(&cfg).unwrap();
+
+    let res = DeleteFeedCascadeCmd("example_1".into()).execute(&cfg);
+    assert!(res.is_ok(), "deleting feed failed: {:?}", res.unwrap_err());
+    let output = res.unwrap();
+    assert_eq!(output.0, "example_1");
+    assert_eq!(output.1.len(), 2);
+
+    let console_output = output.output();
+    assert_eq!(console_output[0], "Successfully deleted feed example_1 and 2 filters");
+    assert_eq!(console_output.len(), 3);
+
+    let output = ListFeedsCmd.execute(&cfg).unwrap();
+    assert_eq!(output.feeds.len(), 1);
+    assert_eq!(output.feeds[0].alias, "example_2_org");
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    assert_eq!(output.filters.len(), 1);
+    assert_eq!(output.filters[0].alias, "example_2_org");
+
+    // Feeds without filters can be deleted with --cascade as well, and missing feeds fail
+    let output = DeleteFeedCascadeCmd("example_2_org".into()).execute(&cfg).unwrap();
+    assert_eq!(output.1.len(), 1);
+
+    let res = DeleteFeedCascadeCmd("example_1".into()).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(), "No feed was found to delete that matches name `example_1`");
+}
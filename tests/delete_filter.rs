@@ -1,3 +1,6 @@
+// Some test docs continue a list with the following sentence.
+#![allow(clippy::doc_lazy_continuation)]
+
 mod test_utils;
 use test_utils::*;
 
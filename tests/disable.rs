@@ -0,0 +1,106 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{DisableFeedCmd, DisableFilterCmd, EnableFeedCmd, EnableFilterCmd, ListFeedsCmd,
+    ListFiltersCmd, UpdateCmd};
+use rss_actions::{Disabled, FilterId, FilterSelector};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+use chrono::{Duration, Utc};
+
+#[test]
+/// A disabled filter's script isn't run and its last updated time is kept until it's enabled.
+fn disabled_filter_is_skipped_until_enabled() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], script_path).execute(&cfg).unwrap();
+
+    let disabled = Disabled { reason: Some("broken script".into()), until: None };
+    let output = DisableFilterCmd(FilterSelector::Id(FilterId(1)), disabled).execute(&cfg).unwrap();
+    assert_eq!(output.output(), ["Successfully disabled filter 1 on feed local1", "Keywords: ",
+        "Reason: broken script"]);
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    assert!(output.output()[2].ends_with("Never updated\tdisabled: broken script"), "{:?}", output.output());
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 0);
+    assert_eq!(output.failures, 0);
+    assert!(output.executed_filters.is_empty());
+    assert_eq!(output.skipped_filters.len(), 1);
+    assert_eq!(output.output()[3], "1 filters skipped because they or their feeds are disabled.");
+    assert!(!log_path.exists(), "script ran for a disabled filter");
+
+    let output = EnableFilterCmd(FilterSelector::Id(FilterId(1))).execute(&cfg).unwrap();
+    assert!(output.0.disabled.is_none());
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.updates, 1);
+    assert!(output.skipped_filters.is_empty());
+    assert!(log_path.exists(), "script did not run for an enabled filter");
+}
+
+#[test]
+/// A disabled feed isn't downloaded and its filters are skipped.
+fn disabled_feed_is_not_downloaded() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    // The feed doesn't exist, so the update would fail if it was downloaded.
+    example_add_feed_local1(base_url.join("does_not_exist.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], script_path.clone()).execute(&cfg).unwrap();
+
+    let disabled = Disabled { reason: None, until: Some(Utc::now() + Duration::days(1)) };
+    let output = DisableFeedCmd("local1".into(), disabled).execute(&cfg).unwrap();
+    assert!(output.output()[1].starts_with("Until: "));
+
+    let output = ListFeedsCmd.execute(&cfg).unwrap();
+    assert!(output.output()[2].contains("\tdisabled until "), "{:?}", output.output());
+
+    // Only disabled feeds
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert!(output.executed_feeds.is_empty());
+    assert_eq!(output.skipped_filters.len(), 1);
+
+    example_add_feed_local2(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec![], script_path).execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.executed_feeds.len(), 1);
+    assert_eq!(output.executed_feeds[0].0.alias, "local2");
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.failures, 0);
+    assert_eq!(output.skipped_filters.len(), 1);
+    assert_eq!(output.skipped_filters[0].alias, "local1");
+    assert!(log_path.exists());
+
+    EnableFeedCmd("local1".into()).execute(&cfg).unwrap();
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.executed_feeds.len(), 2);
+    assert_eq!(output.failures, 1);
+    assert!(output.skipped_filters.is_empty());
+}
+
+#[test]
+fn disable_errors() {
+    let (_dir, cfg) = temp_config();
+
+    example_add_feed1().execute(&cfg).unwrap();
+    example_add_filter1().execute(&cfg).unwrap();
+
+    let disabled = Disabled { reason: None, until: Some(Utc::now() - Duration::hours(1)) };
+    let res = DisableFeedCmd("example_1".into(), disabled.clone()).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(), "The time to disable until has already passed.");
+    let res = DisableFilterCmd(FilterSelector::Id(FilterId(1)), disabled).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(), "The time to disable until has already passed.");
+
+    let res = DisableFeedCmd("missing".into(), Disabled::default()).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(), "No feed was found that matches name `missing`");
+    let res = EnableFilterCmd(FilterSelector::Id(FilterId(2))).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(), "No filter with id 2 was found in the database.");
+}
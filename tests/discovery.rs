@@ -0,0 +1,105 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{AddFeedWithDiscoveryCmd, Feed, ListFeedsCmd};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+use url::Url;
+
+fn add_feed_cmd(url: Url, validate: bool) -> AddFeedWithDiscoveryCmd {
+    AddFeedWithDiscoveryCmd {
+        feed: Feed::new(url, "local1").unwrap(),
+        discover: true,
+        validate,
+    }
+}
+
+/// Adding a web page adds the first RSS feed linked from it, and lists the others.
+#[test]
+fn discover_feed_from_page() {
+    let (_dir, cfg) = temp_config();
+    let server_url = run_rss_files_server();
+    let page_url = server_url.join("blog_home.html").unwrap();
+
+    let res = add_feed_cmd(page_url.clone(), false).execute(&cfg);
+    assert!(res.is_ok(), "Error adding feed from web page: {:?}", res.unwrap_err());
+
+    let output = res.unwrap();
+    let feed_url = server_url.join("two_entries.rss").unwrap();
+    assert_eq!(output.feed.url, feed_url);
+    assert_eq!(output.discovered_from, Some(page_url.clone()));
+    assert_eq!(output.other_feeds.len(), 1);
+    assert_eq!(output.other_feeds[0].url, server_url.join("simple_feed.rss").unwrap());
+    assert!(output.validation.is_none());
+
+    let console_output = output.output().join("\n");
+    assert!(console_output.contains(&format!("Found feed {} on page {}", feed_url, page_url)),
+        "Incorrect output: {}", console_output);
+    assert!(console_output.contains("Comments"), "Incorrect output: {}", console_output);
+
+    let output = ListFeedsCmd.execute(&cfg).unwrap();
+    assert_eq!(output.feeds.len(), 1);
+    assert_eq!(output.feeds[0].url, feed_url);
+}
+
+/// A feed url is added as is, and validating it shows its title and number of entries.
+#[test]
+fn validate_feed() {
+    let (_dir, cfg) = temp_config();
+    let feed_url = run_rss_files_server().join("two_entries.rss").unwrap();
+
+    let output = add_feed_cmd(feed_url.clone(), true).execute(&cfg).unwrap();
+    assert_eq!(output.feed.url, feed_url);
+    assert!(output.discovered_from.is_none());
+
+    let validation = output.validation.as_ref().unwrap();
+    assert_eq!(validation.title.as_deref(), Some("Two entries RSS Title"));
+    assert_eq!(validation.entries, 2);
+
+    let console_output = output.output().join("\n");
+    assert!(console_output.contains("Feed title: Two entries RSS Title"), "Incorrect output: {}", console_output);
+    assert!(console_output.contains("Feed entries: 2"), "Incorrect output: {}", console_output);
+}
+
+/// Pages without RSS feed links and feeds with bad entries are not added.
+#[test]
+fn discovery_and_validation_failures() {
+    let (_dir, cfg) = temp_config();
+    let server_url = run_rss_files_server();
+
+    let res = add_feed_cmd(server_url.join("news_page.html").unwrap(), false).execute(&cfg);
+    let err = format!("{:#}", res.unwrap_err());
+    assert!(err.contains("is not an RSS feed and does not link to any feeds"), "Incorrect error: {}", err);
+
+    let res = add_feed_cmd(server_url.join("atom_only.html").unwrap(), false).execute(&cfg);
+    let err = format!("{:#}", res.unwrap_err());
+    assert!(err.contains("only links to feeds in unsupported formats: \
+        https://example.com/atom.xml (Atom), https://example.com/feed.json (JSON Feed)"),
+        "Incorrect error: {}", err);
+
+    let res = add_feed_cmd(server_url.join("missing_data.rss").unwrap(), true).execute(&cfg);
+    let err = format!("{:#}", res.unwrap_err());
+    assert!(err.contains("1 entries in feed local1 had data errors"), "Incorrect error: {}", err);
+
+    let output = ListFeedsCmd.execute(&cfg).unwrap();
+    assert!(output.feeds.is_empty());
+}
+
+/// Unreachable urls fail unless discovery is turned off.
+#[test]
+fn no_discover_skips_download() {
+    let (dir, cfg) = temp_config();
+    let missing_url = Url::from_file_path(dir.path().join("not_yet.rss")).unwrap();
+
+    let res = add_feed_cmd(missing_url.clone(), false).execute(&cfg);
+    let err = format!("{:#}", res.unwrap_err());
+    assert!(err.contains("Could not check the url"), "Incorrect error: {}", err);
+
+    let mut cmd = add_feed_cmd(missing_url.clone(), false);
+    cmd.discover = false;
+    let output = cmd.execute(&cfg).unwrap();
+    assert_eq!(output.feed.url, missing_url);
+
+    let output = ListFeedsCmd.execute(&cfg).unwrap();
+    assert_eq!(output.feeds.len(), 1);
+}
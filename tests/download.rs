@@ -0,0 +1,220 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{AddFilterCmd, EditFilterCmd, ExportCmd, ListFiltersCmd, ReplayCmd, UpdateCmd};
+use rss_actions::{ActionOutput, BuiltinAction, DownloadAction, Filter, FilterId, FilterSelector, ReplayWindow};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+use url::Url;
+
+use std::path::{Path, PathBuf};
+
+fn test_feed_file(name: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests/test_rss_feed_files");
+    path.push(name);
+    path
+}
+
+/// Write a podcast feed to `dir` whose enclosures are files served by the test server, and
+/// return its `file://` url.
+fn write_podcast_feed(dir: &Path, base_url: &Url) -> Url {
+    let feed = std::fs::read_to_string(test_feed_file("podcast.rss")).unwrap()
+        .replace("http://www.example.com/podcast/episode2.mp3", base_url.join("two_entries.rss").unwrap().as_str())
+        .replace("http://www.example.com/podcast/episode1.mp3", base_url.join("simple_feed.rss").unwrap().as_str());
+    let path = dir.join("podcast.rss");
+    std::fs::write(&path, feed).unwrap();
+    Url::from_file_path(path).unwrap()
+}
+
+fn download_filter(dir: &Path, path_template: Option<&str>, max_bytes: Option<u64>) -> Filter {
+    let download = DownloadAction::new(dir.join("downloads"), path_template.map(String::from), max_bytes).unwrap();
+    Filter::with_action("local1", vec![], BuiltinAction::Download(download)).unwrap()
+}
+
+#[test]
+/// Matching entries' enclosures are saved under the download directory, named by the template,
+/// and aren't downloaded again.
+fn download_saves_enclosures() {
+    let (dir, cfg) = temp_config();
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(write_podcast_feed(dir.path(), &base_url)).execute(&cfg).unwrap();
+
+    let lines = AddFilterCmd(download_filter(dir.path(), None, None)).execute(&cfg).unwrap().output();
+    let expected_action = format!("Action: builtin:download to {}",
+        dir.path().join("downloads/{feed}/{date}-{title}.{ext}").display());
+    assert!(lines.contains(&expected_action), "Incorrect add filter output: {:?}", lines);
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.failures, 0, "Update failed: {:?}", output.executed_filters);
+    let feed_dir = dir.path().join("downloads/local1");
+    let episode2 = feed_dir.join("2009-09-06-Episode 2_ Pizza.rss");
+    let episode1 = feed_dir.join("2009-09-06-Episode 1_ Marshmallows.rss");
+    let downloaded: Vec<&PathBuf> = output.downloads.iter().map(|(_, path)| path).collect();
+    assert_eq!(downloaded, [&episode1, &episode2]);
+    assert_eq!(std::fs::read(&episode2).unwrap(), std::fs::read(test_feed_file("two_entries.rss")).unwrap());
+    assert_eq!(std::fs::read(&episode1).unwrap(), std::fs::read(test_feed_file("simple_feed.rss")).unwrap());
+    assert!(output.output().contains(&"2 files downloaded:".to_string()));
+
+    // The action is kept in the database and the export
+    let filters = ListFiltersCmd.execute(&cfg).unwrap().filters;
+    assert!(matches!(filters[0].action, Some(BuiltinAction::Download(_))));
+    assert!(filters[0].script_path.as_os_str().is_empty());
+    assert!(ExportCmd(None).execute(&cfg).unwrap().document.filters[0].action.is_some());
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert!(output.downloads.is_empty());
+}
+
+#[test]
+/// Entries whose files would have the same path are saved under numbered names instead of one
+/// being reported as downloaded without a file, and downloading them again doesn't add more.
+fn download_same_path_gets_numbered_name() {
+    let (dir, cfg) = temp_config();
+    let base_url = run_rss_files_server();
+    let feed_url = write_podcast_feed(dir.path(), &base_url);
+    let feed_path = feed_url.to_file_path().unwrap();
+    let feed = std::fs::read_to_string(&feed_path).unwrap()
+        .replace("Episode 2: Pizza", "Episode").replace("Episode 1: Marshmallows", "Episode");
+    std::fs::write(&feed_path, feed).unwrap();
+    example_add_feed_local1(feed_url).execute(&cfg).unwrap();
+    AddFilterCmd(download_filter(dir.path(), Some("{title}.{ext}"), None)).execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.failures, 0, "Update failed: {:?}", output.executed_filters);
+    let downloads_dir = dir.path().join("downloads");
+    let first = downloads_dir.join("Episode.rss");
+    let second = downloads_dir.join("Episode (2).rss");
+    let downloaded: Vec<&PathBuf> = output.downloads.iter().map(|(_, path)| path).collect();
+    assert_eq!(downloaded, [&first, &second]);
+    assert_eq!(std::fs::read(&first).unwrap(), std::fs::read(test_feed_file("simple_feed.rss")).unwrap());
+    assert_eq!(std::fs::read(&second).unwrap(), std::fs::read(test_feed_file("two_entries.rss")).unwrap());
+
+    let output = ReplayCmd(FilterSelector::Id(FilterId(1)), ReplayWindow::Last(2)).execute(&cfg).unwrap();
+    let replayed: Vec<PathBuf> = output.results.into_iter()
+        .map(|(_, _, res)| match res.unwrap() {
+            ActionOutput::Downloaded(path) => path,
+            ActionOutput::Script(_) => panic!("the download filter ran a script"),
+        })
+        .collect();
+    assert_eq!(replayed, [first, second]);
+    assert_eq!(std::fs::read_dir(&downloads_dir).unwrap().count(), 2);
+}
+
+#[test]
+/// A download that fails doesn't take its path, so another entry's file with the same name can be
+/// saved there.
+fn failed_download_does_not_take_path() {
+    let (dir, cfg) = temp_config();
+    let base_url = run_rss_files_server();
+    let feed_url = write_podcast_feed(dir.path(), &base_url);
+    let feed_path = feed_url.to_file_path().unwrap();
+    let feed = std::fs::read_to_string(&feed_path).unwrap()
+        .replace("Episode 2: Pizza", "Episode").replace("Episode 1: Marshmallows", "Episode")
+        .replace("simple_feed.rss", "missing.rss");
+    std::fs::write(&feed_path, feed).unwrap();
+    example_add_feed_local1(feed_url).execute(&cfg).unwrap();
+    AddFilterCmd(download_filter(dir.path(), Some("{title}.{ext}"), None)).execute(&cfg).unwrap();
+
+    let output = ReplayCmd(FilterSelector::Id(FilterId(1)), ReplayWindow::Last(2)).execute(&cfg).unwrap();
+    assert!(output.results[0].2.is_err());
+    let path = dir.path().join("downloads/Episode.rss");
+    assert!(matches!(&output.results[1].2, Ok(ActionOutput::Downloaded(downloaded)) if downloaded == &path),
+        "Incorrect result: {:?}", output.results[1].2);
+    assert_eq!(std::fs::read(&path).unwrap(), std::fs::read(test_feed_file("two_entries.rss")).unwrap());
+}
+
+#[test]
+/// A download that was interrupted is finished from its partial file.
+fn download_resumes_partial_file() {
+    let (dir, cfg) = temp_config();
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(write_podcast_feed(dir.path(), &base_url)).execute(&cfg).unwrap();
+    AddFilterCmd(download_filter(dir.path(), Some("{title}.{ext}"), None)).execute(&cfg).unwrap();
+
+    let expected = std::fs::read(test_feed_file("two_entries.rss")).unwrap();
+    let downloads_dir = dir.path().join("downloads");
+    std::fs::create_dir_all(&downloads_dir).unwrap();
+    // The partial file's contents are kept, so only the rest of the file is requested.
+    std::fs::write(downloads_dir.join("Episode 2_ Pizza.rss.part"), [b'x'; 100]).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.failures, 0, "Update failed: {:?}", output.executed_filters);
+    let downloaded = std::fs::read(downloads_dir.join("Episode 2_ Pizza.rss")).unwrap();
+    assert_eq!(downloaded[..100], [b'x'; 100]);
+    assert_eq!(downloaded[100..], expected[100..]);
+    assert!(!downloads_dir.join("Episode 2_ Pizza.rss.part").exists());
+}
+
+#[test]
+/// Downloads over the size limit fail the filter and don't leave a file behind.
+fn download_size_limit() {
+    let (dir, cfg) = temp_config();
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(write_podcast_feed(dir.path(), &base_url)).execute(&cfg).unwrap();
+    AddFilterCmd(download_filter(dir.path(), Some("{title}.{ext}"), Some(10))).execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.failures, 1);
+    let err = output.executed_filters[0].1.as_ref().unwrap_err();
+    assert!(format!("{:#}", err).contains("is larger than the limit of 10 bytes"), "Incorrect error: {:#}", err);
+    assert!(output.downloads.is_empty());
+    let files: Vec<_> = std::fs::read_dir(dir.path().join("downloads")).unwrap().collect();
+    assert!(files.is_empty(), "Files left after failed downloads: {:?}", files);
+}
+
+#[test]
+/// Templates with unknown placeholders or paths outside the download directory are rejected, as
+/// are relative download directories.
+fn download_template_validation() {
+    let dir = std::env::temp_dir();
+    let err = DownloadAction::new(dir.clone(), Some("{feed}/{author}.{ext}".into()), None).unwrap_err();
+    assert_eq!(err.to_string(), "Unknown placeholder `{author}` in the download path template \
+        `{feed}/{author}.{ext}`. Use {feed}, {date}, {title} or {ext}.");
+
+    let err = DownloadAction::new(dir.clone(), Some("{title".into()), None).unwrap_err();
+    assert!(err.to_string().starts_with("Unmatched `{`"), "Incorrect error: {}", err);
+
+    for template in ["../{title}.{ext}", "/tmp/{title}.{ext}", ""] {
+        let err = DownloadAction::new(dir.clone(), Some(template.into()), None).unwrap_err();
+        assert!(err.to_string().starts_with("The download path template must be a relative path"),
+            "Incorrect error for {:?}: {}", template, err);
+    }
+
+    let err = DownloadAction::new("downloads".into(), None, None).unwrap_err();
+    assert_eq!(err.to_string(), "The download directory must be an absolute path: downloads");
+}
+
+#[test]
+/// A filter can switch between a script and a built-in action, but can't have both.
+fn edit_filter_action() {
+    let (dir, cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(write_podcast_feed(dir.path(), &base_url)).execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], script_path.clone()).execute(&cfg).unwrap();
+
+    let action = download_filter(dir.path(), None, None).action;
+    let mut edit = EditFilterCmd {
+        filter: FilterSelector::Id(FilterId(1)),
+        new_keywords: None,
+        new_script_path: Some(script_path),
+        new_action: action.clone(),
+        new_name: None,
+        new_max_actions: None,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    };
+    let err = edit.execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "A filter can run either a script or a built-in action, not both.");
+
+    edit.new_script_path = None;
+    edit.execute(&cfg).unwrap();
+    let filter = &ListFiltersCmd.execute(&cfg).unwrap().filters[0];
+    assert_eq!(filter.action, action);
+    assert!(filter.script_path.as_os_str().is_empty());
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.downloads.len(), 2);
+}
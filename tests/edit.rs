@@ -0,0 +1,206 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{EditFeedCmd, EditFilterCmd, ListFeedsCmd, ListFiltersCmd, RenameFeedCmd, UpdateCmd};
+use rss_actions::{RSSActionCmd, ConsoleOutput, FeedConfig, FilterSelector};
+
+use chrono::prelude::*;
+use url::Url;
+
+use std::path::PathBuf;
+
+fn test_feed_url(server_url: &Url, name: &str) -> Url {
+    server_url.join(name).unwrap()
+}
+
+#[test]
+/// Renaming a feed keeps its filters and their last updated times, so old entries don't match
+/// again.
+fn rename_feed_keeps_filters() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+    let server_url = run_rss_files_server();
+
+    example_add_feed_local1(test_feed_url(&server_url, "two_entries.rss")).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["entry"], script_path).execute(&cfg).unwrap();
+    UpdateCmd.execute(&cfg).unwrap();
+    std::fs::remove_file(&log_path).unwrap();
+
+    cfg.feeds.insert("local1".into(), FeedConfig::default());
+    let output = RenameFeedCmd("local1".into(), "renamed".into()).execute(&cfg).unwrap();
+    assert!(output.has_feed_config);
+    let console_output = output.output().join("\n");
+    assert!(console_output.contains("Successfully renamed feed local1 to renamed"), "Incorrect output: {}", console_output);
+    assert!(console_output.contains("[feeds.local1] section"), "Incorrect output: {}", console_output);
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    assert_eq!(output.filters.len(), 1);
+    assert_eq!(output.filters[0].alias, "renamed");
+    let timestamp = Utc.with_ymd_and_hms(2009, 9, 6, 16, 20, 0).unwrap();
+    assert_eq!(output.filters[0].last_updated.unwrap(), timestamp);
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.updates, 0);
+    assert!(!log_path.exists(), "Script was run again after renaming the feed");
+}
+
+#[test]
+/// Renaming a missing feed, or to an alias that is already used, fails.
+fn rename_feed_err() {
+    let (_dir, cfg) = temp_config();
+
+    let res = RenameFeedCmd("example_1".into(), "other".into()).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(), "No feed was found to rename that matches name `example_1`");
+
+    example_add_feed1().execute(&cfg).unwrap();
+    example_add_feed2().execute(&cfg).unwrap();
+
+    let res = RenameFeedCmd("example_1".into(), "example_2_org".into()).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(), "A feed with the alias `example_2_org` already exists.");
+
+    let res = RenameFeedCmd("example_1".into(), "".into()).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(), "A feed's alias must not be empty.");
+
+    let output = ListFeedsCmd.execute(&cfg).unwrap();
+    let aliases: Vec<_> = output.feeds.iter().map(|feed| feed.alias.as_str()).collect();
+    assert_eq!(aliases, ["example_1", "example_2_org"]);
+}
+
+#[test]
+/// Changing a feed's url keeps its filters.
+fn edit_feed_url() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+    let server_url = run_rss_files_server();
+
+    example_add_feed_local1(test_feed_url(&server_url, "missing.rss")).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["entry"], script_path).execute(&cfg).unwrap();
+
+    let new_url = test_feed_url(&server_url, "two_entries.rss");
+    let output = EditFeedCmd("local1".into(), new_url.clone()).execute(&cfg).unwrap();
+    assert_eq!(output.0.url, new_url);
+
+    let output = ListFeedsCmd.execute(&cfg).unwrap();
+    assert_eq!(output.feeds[0].url, new_url);
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.updates, 1);
+    assert!(log_path.exists());
+
+    let res = EditFeedCmd("local1".into(), Url::parse("ftp://example.com/feed.rss").unwrap()).execute(&cfg);
+    assert!(res.unwrap_err().to_string().contains("Unsupported feed url scheme `ftp`"));
+
+    let res = EditFeedCmd("local2".into(), new_url).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(), "No feed was found to edit that matches name `local2`");
+}
+
+#[test]
+/// Editing a filter's keywords and script keeps its last updated time.
+fn edit_filter_keeps_last_updated() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+    let server_url = run_rss_files_server();
+
+    example_add_feed_local1(test_feed_url(&server_url, "two_entries.rss")).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path.clone()).execute(&cfg).unwrap();
+    UpdateCmd.execute(&cfg).unwrap();
+    std::fs::remove_file(&log_path).unwrap();
+
+    let cmd = EditFilterCmd {
+        filter: FilterSelector::Keywords("local1".into(), vec!["Example".into()]),
+        new_keywords: Some(vec!["entry".into()]),
+        new_script_path: Some(example_script_path1()),
+        new_action: None,
+        new_name: None,
+        new_max_actions: None,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    };
+    let output = cmd.execute(&cfg).unwrap();
+    assert_eq!(output.0.keywords, ["entry"]);
+    assert_eq!(output.0.script_path, example_script_path1());
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    assert_eq!(output.filters.len(), 1);
+    assert_eq!(output.filters[0].keywords, ["entry"]);
+    assert_eq!(output.filters[0].script_path, example_script_path1());
+    let timestamp = Utc.with_ymd_and_hms(2009, 9, 6, 16, 20, 0).unwrap();
+    assert_eq!(output.filters[0].last_updated.unwrap(), timestamp);
+
+    // Only the script is changed
+    let cmd = EditFilterCmd {
+        filter: FilterSelector::Keywords("local1".into(), vec!["entry".into()]),
+        new_keywords: None,
+        new_script_path: Some(script_path),
+        new_action: None,
+        new_name: None,
+        new_max_actions: None,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    };
+    cmd.execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.updates, 0);
+    assert!(!log_path.exists(), "Script was run again after editing the filter");
+}
+
+#[test]
+/// Edits that would duplicate another filter, use a bad script, or change nothing fail.
+fn edit_filter_err() {
+    let (_dir, cfg) = temp_config();
+
+    example_add_feed1().execute(&cfg).unwrap();
+    example_add_filter1().execute(&cfg).unwrap();
+    example_add_filter5().execute(&cfg).unwrap();
+    let filters_before = ListFiltersCmd.execute(&cfg).unwrap().filters;
+
+    let cmd = EditFilterCmd {
+        filter: FilterSelector::Keywords("example_1".into(), vec!["uwu".into()]),
+        new_keywords: Some(vec!["test".into()]),
+        new_script_path: Some(example_script_path1()),
+        new_action: None,
+        new_name: None,
+        new_max_actions: None,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    };
+    let res = cmd.execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(),
+        "There is already a filter with the same feed alias, keywords, and script path.");
+
+    let cmd = EditFilterCmd {
+        filter: FilterSelector::Keywords("example_1".into(), vec!["uwu".into()]),
+        new_keywords: None,
+        new_script_path: Some(PathBuf::from("/nonexistent/script")),
+        new_action: None,
+        new_name: None,
+        new_max_actions: None,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    };
+    let res = cmd.execute(&cfg);
+    assert!(res.unwrap_err().to_string().contains("The filter's script path is not a file"));
+
+    let cmd = EditFilterCmd {
+        filter: FilterSelector::Keywords("example_1".into(), vec!["uwu".into()]),
+        new_keywords: None,
+        new_script_path: None,
+        new_action: None,
+        new_name: None,
+        new_max_actions: None,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    };
+    let res = cmd.execute(&cfg);
+    assert!(res.unwrap_err().to_string().starts_with("Nothing to edit."));
+
+    let filters_after = ListFiltersCmd.execute(&cfg).unwrap().filters;
+    assert_eq!(filters_before, filters_after);
+}
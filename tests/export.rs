@@ -0,0 +1,277 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{DisableFilterCmd, ExportCmd, ImportCmd, ListFeedsCmd, ListFiltersCmd};
+use rss_actions::{Disabled, FilterId, FilterSelector, ImportMode};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+use chrono::{TimeZone, Utc};
+
+#[test]
+/// Exporting and importing into an empty database gives the same feeds and filters.
+fn export_import_round_trip() {
+    let (dir, cfg) = temp_config();
+
+    example_add_feed1().execute(&cfg).unwrap();
+    example_add_feed2().execute(&cfg).unwrap();
+    let mut filter = example_add_filter1().0;
+    filter.name = Some("tests".into());
+    rss_actions::AddFilterCmd(filter).execute(&cfg).unwrap();
+    example_add_filter4().execute(&cfg).unwrap();
+    let disabled = Disabled { reason: Some("broken".into()), until: None };
+    DisableFilterCmd(FilterSelector::Id(FilterId(2)), disabled).execute(&cfg).unwrap();
+
+    let export_path = dir.path().join("export.json");
+    let output = ExportCmd(Some(export_path.clone())).execute(&cfg).unwrap();
+    assert_eq!(output.output(), [format!("Exported 2 feeds and 2 filters to {}", export_path.display())]);
+
+    let (_dir2, cfg2) = temp_config();
+    let output = ImportCmd(export_path, ImportMode::Merge).execute(&cfg2).unwrap();
+    assert_eq!(output.added_feeds, 2);
+    assert_eq!(output.added_filters, 2);
+    assert!(output.conflicts.is_empty());
+    assert_eq!(output.output(), ["Imported 2 feeds and 2 filters", "Updated the last updated time of 0 filters"]);
+
+    assert_eq!(ListFeedsCmd.execute(&cfg).unwrap().output(), ListFeedsCmd.execute(&cfg2).unwrap().output());
+    assert_eq!(ListFiltersCmd.execute(&cfg).unwrap().output(), ListFiltersCmd.execute(&cfg2).unwrap().output());
+
+    // Exporting to stdout gives the same document
+    let exported1 = ExportCmd(None).execute(&cfg).unwrap().document;
+    let exported2 = ExportCmd(None).execute(&cfg2).unwrap().document;
+    assert_eq!(serde_json::to_value(&exported1.feeds).unwrap(), serde_json::to_value(&exported2.feeds).unwrap());
+    assert_eq!(serde_json::to_value(&exported1.filters).unwrap(), serde_json::to_value(&exported2.filters).unwrap());
+}
+
+fn write_export(dir: &std::path::Path, document: serde_json::Value) -> std::path::PathBuf {
+    let path = dir.join("import.json");
+    std::fs::write(&path, document.to_string()).unwrap();
+    path
+}
+
+#[test]
+/// Merging adds new feeds and filters, moves last updated times forward and reports conflicts.
+fn import_merge_conflicts() {
+    let (dir, cfg) = temp_config();
+
+    example_add_feed1().execute(&cfg).unwrap();
+    example_add_feed2().execute(&cfg).unwrap();
+    example_add_filter1().execute(&cfg).unwrap();
+    let mut filter = example_add_filter4().0;
+    filter.name = Some("taken".into());
+    rss_actions::AddFilterCmd(filter).execute(&cfg).unwrap();
+
+    let path = write_export(dir.path(), serde_json::json!({
+        "version": 1,
+        "exported_at": "2024-01-01T00:00:00Z",
+        "feeds": [
+            {"alias": "example_1", "url": "https://example.com/feed.rss"},
+            {"alias": "example_2_org", "url": "https://example.org/moved.rss"},
+            {"alias": "new_feed", "url": "https://example.net/feed.rss",
+                "disabled": {"reason": "not yet", "until": null}},
+        ],
+        "filters": [
+            // existing filter, newer time
+            {"feed": "example_1", "keywords": ["test"], "script_path": example_script_path1(),
+                "last_updated": "2024-01-01T00:00:00Z"},
+            // on a conflicting feed
+            {"feed": "example_2_org", "keywords": ["a"], "script_path": "/bin/true", "last_updated": null},
+            // name conflict
+            {"feed": "new_feed", "name": "taken", "keywords": ["b"], "script_path": "/bin/true",
+                "last_updated": null},
+            {"feed": "new_feed", "name": "new", "keywords": ["c"], "script_path": "/bin/true",
+                "last_updated": "2023-01-01T00:00:00Z"},
+        ],
+    }));
+
+    let output = ImportCmd(path.clone(), ImportMode::Merge).execute(&cfg).unwrap();
+    assert_eq!(output.added_feeds, 1);
+    assert_eq!(output.added_filters, 1);
+    assert_eq!(output.updated_filters, 1);
+    assert_eq!(output.conflicts, [
+        "Feed example_2_org: the imported url https://example.org/moved.rss is different from the existing url https://example.org/feed2.rss",
+        "Filter on feed example_2_org with keywords `a` and script /bin/true: skipped because its feed conflicts",
+        "Filter on feed new_feed with keywords `b` and script /bin/true: a filter named `taken` already exists",
+    ]);
+    assert_eq!(output.output()[3], "Skipped 3 conflicts:");
+
+    let feeds = ListFeedsCmd.execute(&cfg).unwrap().feeds;
+    let new_feed = feeds.iter().find(|feed| feed.alias == "new_feed").unwrap();
+    assert_eq!(new_feed.disabled.as_ref().unwrap().reason.as_deref(), Some("not yet"));
+
+    let filters = ListFiltersCmd.execute(&cfg).unwrap().filters;
+    assert_eq!(filters.len(), 3);
+    let updated = filters.iter().find(|filter| filter.alias == "example_1").unwrap();
+    assert_eq!(updated.last_updated, Some(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()));
+    let added = filters.iter().find(|filter| filter.alias == "new_feed").unwrap();
+    assert_eq!(added.name.as_deref(), Some("new"));
+    assert_eq!(added.last_updated, Some(Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap()));
+
+    // Importing again changes nothing, and older times don't move filters backwards
+    let output = ImportCmd(path, ImportMode::Merge).execute(&cfg).unwrap();
+    assert_eq!(output.added_feeds, 0);
+    assert_eq!(output.added_filters, 0);
+    assert_eq!(output.updated_filters, 0);
+}
+
+#[test]
+/// Settings of an imported filter that differ from the filter it matches are reported, and the
+/// existing settings are kept.
+fn import_merge_differing_settings() {
+    let (dir, cfg) = temp_config();
+
+    example_add_feed1().execute(&cfg).unwrap();
+    example_add_filter1().execute(&cfg).unwrap();
+
+    let path = write_export(dir.path(), serde_json::json!({
+        "version": 1,
+        "exported_at": "2024-01-01T00:00:00Z",
+        "feeds": [{"alias": "example_1", "url": "https://example.com/feed.rss"}],
+        "filters": [
+            {"feed": "example_1", "name": "renamed", "keywords": ["test"], "script_path": example_script_path1(),
+                "last_updated": null, "disabled": {"reason": "broken", "until": null},
+                "max_actions_per_run": 3, "dedup_group": "news", "priority": 5, "stop": true},
+        ],
+    }));
+
+    let output = ImportCmd(path, ImportMode::Merge).execute(&cfg).unwrap();
+    assert_eq!(output.added_filters, 0);
+    let description = format!("Filter on feed example_1 with keywords `test` and script {}",
+        example_script_path1().display());
+    let expected: Vec<String> = [
+        "the imported name `renamed` is different from the existing name none",
+        "the imported state disabled (broken) is different from the existing state enabled",
+        "the imported max actions per run `3` is different from the existing max actions per run none",
+        "the imported dedup group `news` is different from the existing dedup group none",
+        "the imported priority 5 is different from the existing priority 0",
+        "the imported stop flag true is different from the existing stop flag false",
+    ].iter().map(|conflict| format!("{}: {}", description, conflict)).collect();
+    assert_eq!(output.conflicts, expected);
+
+    let filter = &ListFiltersCmd.execute(&cfg).unwrap().filters[0];
+    assert_eq!(filter.name, None);
+    assert_eq!(filter.disabled, None);
+    assert_eq!(filter.priority, 0);
+    assert!(!filter.stop);
+}
+
+#[test]
+/// The entries each dedup group has processed are exported and imported with their times, and
+/// entries that are already recorded keep theirs.
+fn export_import_dedup_entries() {
+    let (dir, cfg) = temp_config();
+
+    let path = write_export(dir.path(), serde_json::json!({
+        "version": 1,
+        "exported_at": "2024-01-01T00:00:00Z",
+        "feeds": [],
+        "filters": [],
+        "dedup_entries": [
+            {"group": "news", "key": "guid:1", "processed_at": "2024-01-01T00:00:00Z"},
+            {"group": "news", "key": "guid:2", "processed_at": "2024-01-02T00:00:00Z"},
+        ],
+    }));
+    ImportCmd(path.clone(), ImportMode::Merge).execute(&cfg).unwrap();
+
+    let exported = serde_json::to_value(ExportCmd(None).execute(&cfg).unwrap().document.dedup_entries).unwrap();
+    let expected = serde_json::json!([
+        {"group": "news", "key": "guid:1", "processed_at": "2024-01-01T00:00:00Z"},
+        {"group": "news", "key": "guid:2", "processed_at": "2024-01-02T00:00:00Z"},
+    ]);
+    assert_eq!(exported, expected);
+
+    let path = write_export(dir.path(), serde_json::json!({
+        "version": 1,
+        "exported_at": "2024-02-01T00:00:00Z",
+        "feeds": [],
+        "filters": [],
+        "dedup_entries": [{"group": "news", "key": "guid:1", "processed_at": "2024-02-01T00:00:00Z"}],
+    }));
+    ImportCmd(path, ImportMode::Merge).execute(&cfg).unwrap();
+    let exported = serde_json::to_value(ExportCmd(None).execute(&cfg).unwrap().document.dedup_entries).unwrap();
+    assert_eq!(exported, expected);
+
+    // A database without dedup entries exports without the field
+    let (_dir2, cfg2) = temp_config();
+    let document = serde_json::to_value(ExportCmd(None).execute(&cfg2).unwrap().document).unwrap();
+    assert!(document.get("dedup_entries").is_none());
+}
+
+#[test]
+/// Replacing deletes everything in the database first.
+fn import_replace() {
+    let (dir, cfg) = temp_config();
+
+    example_add_feed1().execute(&cfg).unwrap();
+    example_add_feed2().execute(&cfg).unwrap();
+    example_add_filter1().execute(&cfg).unwrap();
+
+    let path = write_export(dir.path(), serde_json::json!({
+        "version": 1,
+        "exported_at": "2024-01-01T00:00:00Z",
+        "feeds": [{"alias": "example_2_org", "url": "https://example.org/moved.rss"}],
+        "filters": [{"feed": "example_2_org", "keywords": [], "script_path": "/bin/true", "last_updated": null}],
+    }));
+
+    let output = ImportCmd(path, ImportMode::Replace).execute(&cfg).unwrap();
+    assert_eq!(output.output(), ["Deleted 2 feeds and 1 filters", "Imported 1 feeds and 1 filters"]);
+
+    let feeds = ListFeedsCmd.execute(&cfg).unwrap().feeds;
+    assert_eq!(feeds.len(), 1);
+    assert_eq!(feeds[0].url.as_str(), "https://example.org/moved.rss");
+    let filters = ListFiltersCmd.execute(&cfg).unwrap().filters;
+    assert_eq!(filters.len(), 1);
+    assert_eq!(filters[0].alias, "example_2_org");
+}
+
+#[test]
+fn import_errors() {
+    let (dir, cfg) = temp_config();
+    example_add_feed1().execute(&cfg).unwrap();
+
+    let path = write_export(dir.path(), serde_json::json!({
+        "version": 2,
+        "exported_at": "2024-01-01T00:00:00Z",
+        "feeds": [],
+        "filters": [],
+    }));
+    let err = ImportCmd(path, ImportMode::Replace).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "The export has format version 2, but this version of rss-actions only supports up to version 1.");
+
+    // A filter on a feed that doesn't exist fails the whole import
+    let path = write_export(dir.path(), serde_json::json!({
+        "version": 1,
+        "exported_at": "2024-01-01T00:00:00Z",
+        "feeds": [{"alias": "new_feed", "url": "https://example.net/feed.rss"}],
+        "filters": [{"feed": "missing", "keywords": [], "script_path": "/bin/true", "last_updated": null}],
+    }));
+    let err = ImportCmd(path, ImportMode::Merge).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "Filter on feed missing with keywords `` and script /bin/true: could not be imported");
+    assert_eq!(ListFeedsCmd.execute(&cfg).unwrap().feeds.len(), 1);
+
+    // Filters are checked the same way as when they're added
+    let invalid_filters = [
+        (serde_json::json!({"max_actions_per_run": 0}), "The limit on script runs per update must be at least 1."),
+        (serde_json::json!({"dedup_group": " "}), "A dedup group's name must not be empty."),
+        (serde_json::json!({"feed": "", "tag": "two words"}), "A tag must not contain whitespace or commas: two words"),
+        (serde_json::json!({"name": "12"}), "A filter's name must not be a number, so that it isn't confused with a filter id: 12"),
+    ];
+    for (settings, expected) in invalid_filters {
+        let mut filter = serde_json::json!({"feed": "example_1", "keywords": [], "script_path": "/bin/true",
+            "last_updated": null});
+        filter.as_object_mut().unwrap().extend(settings.as_object().unwrap().clone());
+        let path = write_export(dir.path(), serde_json::json!({
+            "version": 1,
+            "exported_at": "2024-01-01T00:00:00Z",
+            "feeds": [],
+            "filters": [filter],
+        }));
+        let err = ImportCmd(path, ImportMode::Merge).execute(&cfg).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), expected);
+    }
+    assert!(ListFiltersCmd.execute(&cfg).unwrap().filters.is_empty());
+
+    let path = dir.path().join("not_json.json");
+    std::fs::write(&path, "feeds: []").unwrap();
+    let err = ImportCmd(path.clone(), ImportMode::Merge).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), format!("{} is not an rss-actions export", path.display()));
+}
@@ -0,0 +1,175 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{AddFilterCmd, DeleteSelectedFilterCmd, EditFilterCmd, Filter, FilterId, FilterSelector, ListFiltersCmd};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+#[test]
+/// Filters get ids when added, and the ids and names are shown by list filters.
+fn list_filters_shows_ids_and_names() {
+    let (_dir, cfg) = temp_config();
+
+    example_add_feed1().execute(&cfg).unwrap();
+    let output = example_add_filter1().execute(&cfg).unwrap();
+    assert_eq!(output.0.id, Some(FilterId(1)));
+
+    let mut filter = Filter::new("example_1", vec!["uwu".into()], example_script_path2()).unwrap();
+    filter.name = Some("uwu-watcher".into());
+    let output = AddFilterCmd(filter).execute(&cfg).unwrap();
+    assert_eq!(output.0.id, Some(FilterId(2)));
+    assert_eq!(output.output(), ["Successfully added filter on feed example_1", "Keywords: uwu", "Id: 2",
+        "Name: uwu-watcher"]);
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    let lines = output.output();
+    assert_eq!(lines[2], "1\t-\texample_1\ttest\tprint_data\tNever updated");
+    assert_eq!(lines[3], "2\tuwu-watcher\texample_1\tuwu\tfalse\tNever updated");
+}
+
+#[test]
+/// Filters that can't be told apart by keywords can be deleted by id or name.
+fn delete_filter_by_id_and_name() {
+    let (_dir, cfg) = temp_config();
+
+    example_add_feed1().execute(&cfg).unwrap();
+    example_add_filter1().execute(&cfg).unwrap();
+    example_add_filter3().execute(&cfg).unwrap();
+    let mut filter = Filter::new("example_1", vec![], example_script_path2()).unwrap();
+    filter.name = Some("everything".into());
+    AddFilterCmd(filter).execute(&cfg).unwrap();
+
+    let res = DeleteSelectedFilterCmd(FilterSelector::Keywords("example_1".into(), vec![])).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(),
+        "Multiple filters matching `` on the feed `example_1` were found in the database.");
+
+    let output = DeleteSelectedFilterCmd(FilterSelector::Id(FilterId(2))).execute(&cfg).unwrap();
+    assert_eq!(output.0.script_path, example_script_path2());
+    assert_eq!(output.0.keywords, ["test"]);
+    assert_eq!(output.output(), ["Successfully deleted filter 2 on feed example_1", "Keywords: test"]);
+
+    let output = DeleteSelectedFilterCmd(FilterSelector::Name("everything".into())).execute(&cfg).unwrap();
+    assert!(output.0.keywords.is_empty());
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    assert_eq!(output.filters.len(), 1);
+    assert_eq!(output.filters[0].id, Some(FilterId(1)));
+
+    let res = DeleteSelectedFilterCmd(FilterSelector::Id(FilterId(2))).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(), "No filter with id 2 was found in the database.");
+    let res = DeleteSelectedFilterCmd(FilterSelector::Name("everything".into())).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(), "No filter named `everything` was found in the database.");
+}
+
+#[test]
+/// Names can be set by editing a filter, must be unique, and can't be numbers.
+fn filter_names() {
+    let (_dir, cfg) = temp_config();
+
+    example_add_feed1().execute(&cfg).unwrap();
+    example_add_filter1().execute(&cfg).unwrap();
+    example_add_filter5().execute(&cfg).unwrap();
+
+    let cmd = EditFilterCmd {
+        filter: FilterSelector::Id(FilterId(1)),
+        new_keywords: None,
+        new_script_path: None,
+        new_action: None,
+        new_name: Some("tests".into()),
+        new_max_actions: None,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    };
+    let output = cmd.execute(&cfg).unwrap();
+    assert_eq!(output.0.name.as_deref(), Some("tests"));
+    assert_eq!(output.0.keywords, ["test"]);
+
+    let cmd = EditFilterCmd {
+        filter: FilterSelector::Id(FilterId(2)),
+        new_keywords: None,
+        new_script_path: None,
+        new_action: None,
+        new_name: Some("tests".into()),
+        new_max_actions: None,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    };
+    assert_eq!(cmd.execute(&cfg).unwrap_err().to_string(), "A filter named `tests` already exists.");
+
+    let mut filter = Filter::new("example_1", vec!["other".into()], example_script_path2()).unwrap();
+    filter.name = Some("tests".into());
+    let res = AddFilterCmd(filter.clone()).execute(&cfg);
+    assert_eq!(res.unwrap_err().to_string(), "A filter named `tests` already exists.");
+
+    filter.name = Some("123".into());
+    let res = AddFilterCmd(filter).execute(&cfg);
+    assert!(res.unwrap_err().to_string().starts_with("A filter's name must not be a number"));
+
+    // Edit by name
+    let cmd = EditFilterCmd {
+        filter: FilterSelector::Name("tests".into()),
+        new_keywords: Some(vec!["new".into()]),
+        new_script_path: None,
+        new_action: None,
+        new_name: None,
+        new_max_actions: None,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    };
+    cmd.execute(&cfg).unwrap();
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    let filter = output.filters.iter().find(|filter| filter.id == Some(FilterId(1))).unwrap();
+    assert_eq!(filter.name.as_deref(), Some("tests"));
+    assert_eq!(filter.keywords, ["new"]);
+}
+
+#[test]
+/// Databases created before filters had names get the name column added when opened.
+fn old_database_gets_name_column() {
+    let (_dir, cfg) = temp_config();
+
+    {
+        let conn = rusqlite::Connection::open(&cfg.db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE feeds (
+                id INTEGER PRIMARY KEY,
+                url TEXT NOT NULL,
+                alias TEXT NOT NULL UNIQUE
+            );
+            CREATE TABLE filters (
+                id INTEGER PRIMARY KEY,
+                feed_id INTEGER NOT NULL,
+                keywords TEXT NOT NULL,
+                script_path TEXT NOT NULL,
+                last_updated TEXT,
+                FOREIGN KEY (feed_id) REFERENCES feeds(id),
+                UNIQUE(feed_id,keywords,script_path)
+            );
+            INSERT INTO feeds (url, alias) VALUES ('https://example.com/feed.rss', 'example_1');
+            INSERT INTO filters (feed_id, keywords, script_path) VALUES (1, 'test', '/bin/false');").unwrap();
+    }
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    assert_eq!(output.filters.len(), 1);
+    assert_eq!(output.filters[0].id, Some(FilterId(1)));
+    assert_eq!(output.filters[0].name, None);
+
+    let cmd = EditFilterCmd {
+        filter: FilterSelector::Keywords("example_1".into(), vec!["test".into()]),
+        new_keywords: None,
+        new_script_path: None,
+        new_action: None,
+        new_name: Some("old".into()),
+        new_max_actions: None,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    };
+    cmd.execute(&cfg).unwrap();
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    assert_eq!(output.filters[0].name.as_deref(), Some("old"));
+}
@@ -0,0 +1,92 @@
+mod test_utils;
+use test_utils::*;
+
+use chrono::{TimeZone, Utc};
+
+use rss_actions::{AddFilterWithStartCmd, Filter, FilterStart, ListFiltersCmd, UpdateCmd};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+fn add_filter_with_start(keywords: Vec<&str>, script_path: std::path::PathBuf, start: FilterStart) -> AddFilterWithStartCmd {
+    let keywords = keywords.into_iter().map(String::from).collect();
+    AddFilterWithStartCmd { filter: Filter::new("local1", keywords, script_path).unwrap(), start }
+}
+
+#[test]
+/// A filter starting now doesn't run its script on the entries already in the feed.
+fn add_filter_from_now_skips_backlog() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    let output = add_filter_with_start(vec!["example"], script_path, FilterStart::Now).execute(&cfg).unwrap();
+    let newest = Utc.with_ymd_and_hms(2009, 9, 6, 16, 20, 0).unwrap();
+    assert_eq!(output.0.last_updated, Some(newest));
+    assert!(output.output().last().unwrap().starts_with("Only entries published after "));
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!((output.successes, output.updates), (1, 0));
+    assert!(!log_path.exists(), "Script was run on the backlog: {}", std::fs::read_to_string(&log_path).unwrap());
+}
+
+#[test]
+/// A filter starting at a date only runs its script on entries published after it.
+fn add_filter_from_date() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    let start = Utc.with_ymd_and_hms(2009, 9, 6, 12, 0, 0).unwrap();
+    add_filter_with_start(vec!["example"], script_path, FilterStart::Date(start)).execute(&cfg).unwrap();
+    assert_eq!(ListFiltersCmd.execute(&cfg).unwrap().filters[0].last_updated, Some(start));
+
+    UpdateCmd.execute(&cfg).unwrap();
+    let script_output = std::fs::read_to_string(&log_path).unwrap();
+    assert!(script_output.contains("title: Example entry NYC"));
+    assert!(!script_output.contains("title: Pizza"));
+}
+
+#[test]
+/// A filter with a backlog only runs its script on the newest matching entries.
+fn add_filter_with_backlog() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    add_filter_with_start(vec!["example"], script_path.clone(), FilterStart::Backlog(1)).execute(&cfg).unwrap();
+    UpdateCmd.execute(&cfg).unwrap();
+    let script_output = std::fs::read_to_string(&log_path).unwrap();
+    assert!(script_output.contains("title: Example entry NYC"));
+    assert!(!script_output.contains("title: Pizza"));
+
+    // A backlog as large as the matching entries processes all of them
+    let output = add_filter_with_start(vec!["pizza"], script_path, FilterStart::Backlog(1)).execute(&cfg).unwrap();
+    assert!(output.0.last_updated.is_none());
+}
+
+#[test]
+/// The database isn't locked while the feed is downloaded to find where the filter starts.
+fn add_filter_does_not_lock_db_while_fetching() {
+    let (dir, cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+    let feed_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/test_rss_feed_files/two_entries.rss");
+    let (program, started_path, release_path) = temp_blocking_script(dir.path(), Some(&feed_path));
+    example_add_feed_local1(url::Url::parse(&format!("exec:{}", program.to_string_lossy())).unwrap())
+        .execute(&cfg).unwrap();
+
+    let add_cfg = cfg.clone();
+    let add_thread = std::thread::spawn(move || {
+        add_filter_with_start(vec!["example"], script_path, FilterStart::Now).execute(&add_cfg)
+    });
+
+    wait_for_file(&started_path);
+    example_add_feed_local2(url::Url::parse("https://example.com/feed.rss").unwrap()).execute(&cfg).unwrap();
+
+    std::fs::write(&release_path, "").unwrap();
+    let output = add_thread.join().unwrap().unwrap();
+    assert_eq!(output.0.last_updated, Some(Utc.with_ymd_and_hms(2009, 9, 6, 16, 20, 0).unwrap()));
+    assert_eq!(ListFiltersCmd.execute(&cfg).unwrap().filters.len(), 1);
+}
@@ -0,0 +1,182 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{ExportCmd, FeedConfig, HtmlSource, ImportCmd, ImportMode, ListFeedsCmd, ListFiltersCmd, RenameFeedCmd};
+use rss_actions::UpdateCmd;
+use rss_actions::RSSActionCmd;
+
+use chrono::prelude::*;
+
+fn news_page_source(date: &str, date_format: Option<&str>) -> HtmlSource {
+    HtmlSource {
+        item: "li.post".into(),
+        title: "a.post-link".into(),
+        link: "a.post-link".into(),
+        date: date.into(),
+        date_format: date_format.map(String::from),
+    }
+}
+
+/// Entries scraped from a page are matched and passed to scripts like RSS entries, with relative
+/// links resolved against the page's url.
+#[test]
+fn html_feed_updates_filter() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let url = run_rss_files_server().join("news_page.html").unwrap();
+    example_add_feed_local1(url.clone()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["entry"], script_path).execute(&cfg).unwrap();
+
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        html: Some(news_page_source("span.posted", Some("%B %d, %Y"))),
+        ..Default::default()
+    });
+
+    let res = UpdateCmd.execute(&cfg);
+    assert!(res.is_ok(), "Error running update with html feed: {:?}", res.unwrap_err());
+
+    let output = res.unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.updates, 1);
+    assert_eq!(output.failures, 0);
+
+    let script_output = std::fs::read_to_string(log_path).unwrap();
+    let host = url.origin().ascii_serialization();
+    let expected = [
+        "title: Example entry".to_string(),
+        format!("url: {}/blog/post/1", host),
+        "title: Asthmatic entry".to_string(),
+        format!("url: {}/feeds/post/2", host),
+    ];
+    for line in expected {
+        assert!(script_output.contains(&line), "Missing `{}` in script output: {}", line, script_output);
+    }
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    let timestamp = Utc.with_ymd_and_hms(2009, 9, 6, 0, 0, 0).unwrap();
+    assert_eq!(output.filters[0].last_updated.unwrap(), timestamp);
+}
+
+/// A `datetime` attribute is preferred over the element's text, and is parsed as RFC 3339 when no
+/// date format is given.
+#[test]
+fn html_feed_datetime_attribute() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let url = run_rss_files_server().join("news_page.html").unwrap();
+    example_add_feed_local1(url).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Asthmatic"], script_path).execute(&cfg).unwrap();
+
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        html: Some(news_page_source("time", None)),
+        ..Default::default()
+    });
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.updates, 1);
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    let timestamp = Utc.with_ymd_and_hms(2009, 9, 6, 16, 20, 0).unwrap();
+    assert_eq!(output.filters[0].last_updated.unwrap(), timestamp);
+}
+
+/// Entries whose date doesn't parse or is missing fail the feed, and the error says why.
+#[test]
+fn html_feed_bad_entries_fail() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let url = run_rss_files_server().join("news_page.html").unwrap();
+    example_add_feed_local1(url.clone()).execute(&cfg).unwrap();
+    example_add_feed_local2(url).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["entry"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec!["entry"], script_path).execute(&cfg).unwrap();
+
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        html: Some(news_page_source("span.posted", Some("%Y-%m-%d"))),
+        ..Default::default()
+    });
+    cfg.feeds.insert("local2".into(), FeedConfig {
+        html: Some(news_page_source("span.missing", None)),
+        ..Default::default()
+    });
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 0);
+    assert_eq!(output.failures, 2);
+
+    let err = format!("{:#}", output.executed_feeds[0].1.as_ref().unwrap_err());
+    assert!(err.contains("2 entries in feed local1 had data errors"), "Incorrect error: {}", err);
+    assert!(err.contains("does not match the format `%Y-%m-%d`"), "Incorrect error: {}", err);
+
+    let err = format!("{:#}", output.executed_feeds[1].1.as_ref().unwrap_err());
+    assert!(err.contains("Entry date is missing."), "Incorrect error: {}", err);
+}
+
+/// An invalid selector fails the feed without running any scripts.
+#[test]
+fn html_feed_invalid_selector() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let server_url = run_rss_files_server();
+    example_add_feed_local1(server_url.join("news_page.html").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(server_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["entry"], script_path).execute(&cfg).unwrap();
+
+    let mut source = news_page_source("time", None);
+    source.item = "li..post".into();
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        html: Some(source),
+        ..Default::default()
+    });
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.failures, 1);
+
+    let err = format!("{:#}", output.executed_feeds[0].1.as_ref().unwrap_err());
+    assert!(err.contains("Could not scrape local1 html feed"), "Incorrect error: {}", err);
+    assert!(err.contains("Invalid item selector `li..post`"), "Incorrect error: {}", err);
+    assert!(!log_path.exists());
+}
+
+/// Sources only live in the config file, so a feed with one can't be renamed until its config
+/// section is, and exports carry the source so that importing checks the config file has it.
+#[test]
+fn html_feed_rename_and_export() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let url = run_rss_files_server().join("news_page.html").unwrap();
+    example_add_feed_local1(url).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["entry"], script_path).execute(&cfg).unwrap();
+    let feed_cfg = FeedConfig {
+        html: Some(news_page_source("span.posted", Some("%B %d, %Y"))),
+        ..Default::default()
+    };
+    cfg.feeds.insert("local1".into(), feed_cfg.clone());
+
+    let err = RenameFeedCmd("local1".into(), "news".into()).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "Feed local1 is read with the html source in the [feeds.local1] section of the \
+        config file. Rename the section to [feeds.news] first, so that the renamed feed is still read with it.");
+    assert_eq!(ListFeedsCmd.execute(&cfg).unwrap().feeds[0].alias, "local1");
+
+    let export_path = dir.path().join("export.json");
+    let document = ExportCmd(Some(export_path.clone())).execute(&cfg).unwrap().document;
+    assert_eq!(document.feeds[0].html, feed_cfg.html);
+
+    // Importing without the source in the config file fails, and works once it's there
+    let (_dir2, mut cfg2) = temp_config();
+    let err = ImportCmd(export_path.clone(), ImportMode::Merge).execute(&cfg2).unwrap_err();
+    assert!(err.to_string().starts_with("The imported feed `local1` is read with the html source in the export, which doesn't"),
+        "Incorrect error: {}", err);
+    assert!(ListFeedsCmd.execute(&cfg2).unwrap().feeds.is_empty());
+
+    cfg2.feeds.insert("local1".into(), feed_cfg);
+    ImportCmd(export_path, ImportMode::Merge).execute(&cfg2).unwrap();
+    let output = UpdateCmd.execute(&cfg2).unwrap();
+    assert_eq!(output.failures, 0);
+    assert_eq!(output.updates, 1);
+}
@@ -0,0 +1,317 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{Config, FeedConfig, HttpConfig, UpdateCmd};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+use std::sync::mpsc::Sender;
+
+use url::Url;
+use warp::Filter;
+
+/// An address nothing is listening on, so connections to it are refused immediately.
+static DEAD_PROXY: &str = "http://127.0.0.1:1";
+
+static SIMPLE_FEED: &str = include_str!("test_rss_feed_files/simple_feed.rss");
+
+/// Start a local server with routes for testing the HTTP settings:
+///
+/// - `/feeds/user_agent.rss` only serves the feed to the user agent `custom-agent/1.0`
+/// - `/feeds/redirect/<n>` redirects n times before serving the feed
+/// - `/feeds/slow.rss` waits 5 seconds before serving the feed
+fn run_http_settings_server() -> Url {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let tokio_runtime = tokio::runtime::Runtime::new().unwrap();
+        tokio_runtime.block_on(_run_http_settings_server(tx));
+    });
+    rx.recv().unwrap()
+}
+
+async fn _run_http_settings_server(tx: Sender<Url>) {
+    let user_agent = warp::path!("feeds" / "user_agent.rss")
+        .and(warp::header::exact("user-agent", "custom-agent/1.0"))
+        .map(|| SIMPLE_FEED);
+
+    let redirect = warp::path!("feeds" / "redirect" / u32)
+        .map(|n: u32| -> Box<dyn warp::Reply> {
+            if n == 0 {
+                Box::new(SIMPLE_FEED)
+            }
+            else {
+                let next = format!("/feeds/redirect/{}", n - 1).parse::<warp::http::Uri>().unwrap();
+                Box::new(warp::redirect::temporary(next))
+            }
+        });
+
+    let slow = warp::path!("feeds" / "slow.rss")
+        .then(|| async {
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            SIMPLE_FEED
+        });
+
+    let route = user_agent.or(redirect).or(slow);
+
+    let any_port_addr: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let (addr, server) = warp::serve(route).bind_ephemeral(any_port_addr);
+
+    let url = Url::parse(&format!("http://127.0.0.1:{}/feeds/", addr.port())).unwrap();
+    tx.send(url).unwrap();
+    server.await;
+}
+
+/// Feeds are fetched through the global proxy. The feed's host doesn't exist, so it can only be
+/// reached via the proxy, which is the local test server.
+#[test]
+fn global_proxy_is_used() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let proxy_url = run_rss_files_server();
+    let feed_url = Url::parse("http://feeds.example.invalid/feeds/simple_feed.rss").unwrap();
+
+    example_add_feed_local1(feed_url).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path).execute(&cfg).unwrap();
+
+    cfg.http.proxy = Some(proxy_url.origin().ascii_serialization());
+
+    let res = UpdateCmd.execute(&cfg);
+    assert!(res.is_ok(), "Error running update through proxy: {:?}", res.unwrap_err());
+
+    let output = res.unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.updates, 1);
+    assert_eq!(output.failures, 0);
+    assert!(log_path.exists(), "Script was not run on feed fetched through proxy");
+}
+
+/// A feed with `proxy = "none"` connects directly even when the global proxy is set, and feeds
+/// without an override use the global proxy.
+#[test]
+fn per_feed_proxy_overrides_global() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec!["Example"], script_path).execute(&cfg).unwrap();
+
+    cfg.http.proxy = Some(DEAD_PROXY.into());
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        http: HttpConfig {
+            proxy: Some("none".into()),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    let res = UpdateCmd.execute(&cfg);
+    assert!(res.is_ok(), "Error running update: {:?}", res.unwrap_err());
+
+    let output = res.unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.failures, 1);
+
+    // local1 connected directly, local2 went through the dead proxy
+    let (feed, feed_res) = output.executed_feeds.iter().find(|(_, res)| res.is_err()).unwrap();
+    assert_eq!(feed.alias, "local2");
+    let err = format!("{:?}", feed_res.as_ref().unwrap_err());
+    assert!(err.contains("Failed to download local2 rss feed"), "Incorrect error message: {}", err);
+}
+
+/// Missing certificate files and incomplete client identities are reported as errors on the feed
+/// that uses them.
+#[test]
+fn bad_tls_settings_fail_feed() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local3(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec!["Example"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local3(vec!["Example"], script_path).execute(&cfg).unwrap();
+
+    cfg.feeds.insert("local2".into(), FeedConfig {
+        http: HttpConfig {
+            root_certificates: Some(vec![dir.path().join("missing-ca.pem")]),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    cfg.feeds.insert("local3".into(), FeedConfig {
+        http: HttpConfig {
+            client_certificate: Some(dir.path().join("client.pem")),
+            insecure: Some(true),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    let res = UpdateCmd.execute(&cfg);
+    assert!(res.is_ok(), "Error running update: {:?}", res.unwrap_err());
+
+    let output = res.unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.failures, 2);
+
+    let console_output = output.output().join("\n");
+    assert!(console_output.contains("Invalid HTTP settings for local2 rss feed"), "Incorrect output: {}", console_output);
+    assert!(console_output.contains("Failed to read root certificate file"), "Incorrect output: {}", console_output);
+    assert!(console_output.contains("Invalid HTTP settings for local3 rss feed"), "Incorrect output: {}", console_output);
+    assert!(console_output.contains("A client certificate was given without a client key."),
+        "Incorrect output: {}", console_output);
+}
+
+/// The `[http]` section and per-feed `[feeds.<alias>.http]` sections are read from the config file
+/// and merged per feed.
+#[test]
+fn http_settings_in_config_file() {
+    let (dir, _cfg) = temp_config();
+    let cfg_path = dir.path().join("config.toml");
+
+    let config_data = format!("db_path = {:?}
+
+[http]
+proxy = \"socks5://127.0.0.1:1080\"
+root_certificates = [\"/etc/ssl/internal-ca.pem\"]
+
+[feeds.internal.http]
+proxy = \"none\"
+client_certificate = \"/etc/rss-actions/client.pem\"
+client_key = \"/etc/rss-actions/client.key\"
+", dir.path().join("rss-actions.db"));
+    std::fs::write(&cfg_path, config_data).unwrap();
+
+    let cfg = Config::open(Some(&cfg_path)).unwrap();
+
+    let internal = cfg.feed_http_config("internal");
+    assert_eq!(internal.proxy.as_deref(), Some("none"));
+    assert_eq!(internal.root_certificates, Some(vec!["/etc/ssl/internal-ca.pem".into()]));
+    assert_eq!(internal.client_certificate, Some("/etc/rss-actions/client.pem".into()));
+    assert_eq!(internal.insecure, None);
+
+    let other = cfg.feed_http_config("other");
+    assert_eq!(other, cfg.http);
+    assert_eq!(other.proxy.as_deref(), Some("socks5://127.0.0.1:1080"));
+
+    // round trip
+    cfg.write_out(&cfg_path).unwrap();
+    let reread = Config::open(Some(&cfg_path)).unwrap();
+    assert_eq!(reread.feed_http_config("internal"), internal);
+}
+
+/// The configured user agent is sent, and can be overridden per feed.
+#[test]
+fn custom_user_agent_is_sent() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_http_settings_server();
+    example_add_feed_local1(base_url.join("user_agent.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("user_agent.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec!["Example"], script_path).execute(&cfg).unwrap();
+
+    cfg.http.user_agent = Some("custom-agent/1.0".into());
+    cfg.feeds.insert("local2".into(), FeedConfig {
+        http: HttpConfig {
+            user_agent: Some("other-agent/2.0".into()),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.failures, 1);
+
+    let (feed, _) = output.executed_feeds.iter().find(|(_, res)| res.is_ok()).unwrap();
+    assert_eq!(feed.alias, "local1");
+}
+
+/// A response larger than max_body_bytes fails with an error saying so.
+#[test]
+fn response_over_body_limit_fails() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec!["Example"], script_path).execute(&cfg).unwrap();
+
+    // simple_feed.rss is less than 1kb, so it's under the global limit but over local2's
+    cfg.http.max_body_bytes = Some(1024);
+    cfg.feeds.insert("local2".into(), FeedConfig {
+        http: HttpConfig {
+            max_body_bytes: Some(100),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.failures, 1);
+
+    let console_output = output.output().join("\n");
+    assert!(console_output.contains("Failed to download local2 rss feed"), "Incorrect output: {}", console_output);
+    assert!(console_output.contains("The response is larger than the limit of 100 bytes."),
+        "Incorrect output: {}", console_output);
+
+    // script only ran for local1
+    let script_output = std::fs::read_to_string(log_path).unwrap();
+    assert_eq!(script_output.matches("rss action script start").count(), 1);
+}
+
+/// Redirects are followed up to max_redirects.
+#[test]
+fn redirects_are_limited() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_http_settings_server();
+    example_add_feed_local1(base_url.join("redirect/2").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("redirect/3").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec!["Example"], script_path).execute(&cfg).unwrap();
+
+    cfg.http.max_redirects = Some(2);
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.failures, 1);
+
+    let (feed, res) = output.executed_feeds.iter().find(|(_, res)| res.is_err()).unwrap();
+    assert_eq!(feed.alias, "local2");
+    let err = format!("{:?}", res.as_ref().unwrap_err());
+    assert!(err.contains("too many redirects"), "Incorrect error message: {}", err);
+}
+
+/// Requests taking longer than the read timeout fail.
+#[test]
+fn slow_response_times_out() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_http_settings_server();
+    example_add_feed_local1(base_url.join("slow.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path).execute(&cfg).unwrap();
+
+    cfg.http.read_timeout_secs = Some(1);
+    cfg.http.connect_timeout_secs = Some(1);
+
+    let start = std::time::Instant::now();
+    let res = UpdateCmd.execute(&cfg);
+    assert!(start.elapsed() < std::time::Duration::from_secs(4), "Request did not time out early");
+
+    assert!(res.is_err(), "Update with timed out feed succeeded");
+    let err = format!("{:?}", res.unwrap_err());
+    assert!(err.contains("All RSS feed downloads failed"), "Incorrect error message: {}", err);
+}
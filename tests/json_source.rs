@@ -0,0 +1,173 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{ExportCmd, FeedConfig, ImportCmd, ImportMode, JsonSource, ListFeedsCmd, ListFiltersCmd};
+use rss_actions::{RenameFeedCmd, UpdateCmd};
+use rss_actions::{AddFeedCmd, Feed, RSSActionCmd};
+
+use chrono::prelude::*;
+
+fn releases_source(items: &str, date: &str, date_format: Option<&str>) -> JsonSource {
+    JsonSource {
+        items: items.into(),
+        title: "name".into(),
+        link: "$.html_url".into(),
+        date: date.into(),
+        date_format: date_format.map(String::from),
+    }
+}
+
+/// Items selected from a JSON document are matched and passed to scripts like RSS entries, with
+/// relative links resolved against the document's url.
+#[test]
+fn json_feed_updates_filter() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let url = run_rss_files_server().join("releases.json").unwrap();
+    example_add_feed_local1(url.clone()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["release"], script_path).execute(&cfg).unwrap();
+
+    // Timestamps are used since the last item has no published_at
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        json: Some(releases_source("$.items[*]", "created", Some("%s"))),
+        ..Default::default()
+    });
+
+    let res = UpdateCmd.execute(&cfg);
+    assert!(res.is_ok(), "Error running update with json feed: {:?}", res.unwrap_err());
+
+    let output = res.unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.updates, 1);
+    assert_eq!(output.failures, 0);
+
+    let script_output = std::fs::read_to_string(log_path).unwrap();
+    let expected = [
+        "title: Example release 1.0".to_string(),
+        format!("url: {}/releases/v1.0", url.origin().ascii_serialization()),
+        "title: Asthmatic release 1.1".to_string(),
+        "url: https://example.com/releases/v1.1".to_string(),
+        "title: Draft release 1.2".to_string(),
+    ];
+    for line in expected {
+        assert!(script_output.contains(&line), "Missing `{}` in script output: {}", line, script_output);
+    }
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    let timestamp = Utc.timestamp_opt(1252340400, 0).unwrap();
+    assert_eq!(output.filters[0].last_updated.unwrap(), timestamp);
+}
+
+/// A path to an array selects its elements, and entries with a null date fail the feed.
+#[test]
+fn json_feed_missing_date_fails() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let server_url = run_rss_files_server();
+    example_add_feed_local1(server_url.join("releases.json").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(server_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["release"], script_path).execute(&cfg).unwrap();
+
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        json: Some(releases_source("$.items", "published_at", None)),
+        ..Default::default()
+    });
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.failures, 1);
+
+    let err = format!("{:#}", output.executed_feeds[0].1.as_ref().unwrap_err());
+    assert!(err.contains("1 entries in feed local1 had data errors"), "Incorrect error: {}", err);
+    assert!(err.contains("Entry date is missing."), "Incorrect error: {}", err);
+    assert!(!log_path.exists());
+}
+
+/// Invalid paths, documents that aren't JSON and feeds with both an html and a json source fail
+/// the feed.
+#[test]
+fn json_feed_invalid_config() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let server_url = run_rss_files_server();
+    example_add_feed_local1(server_url.join("releases.json").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(server_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local3(server_url.join("releases.json").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["release"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec!["release"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local3(vec!["release"], script_path).execute(&cfg).unwrap();
+    // One feed has to succeed, otherwise the whole update fails
+    let good_feed = Feed::new(server_url.join("simple_feed.rss").unwrap(), "local4").unwrap();
+    AddFeedCmd(good_feed).execute(&cfg).unwrap();
+
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        json: Some(releases_source("$.items[*", "published_at", None)),
+        ..Default::default()
+    });
+    cfg.feeds.insert("local2".into(), FeedConfig {
+        json: Some(releases_source("$.items[*]", "published_at", None)),
+        ..Default::default()
+    });
+    cfg.feeds.insert("local3".into(), FeedConfig {
+        json: Some(releases_source("$.items[*]", "created", Some("%s"))),
+        html: Some(rss_actions::HtmlSource {
+            item: "li".into(), title: "a".into(), link: "a".into(), date: "time".into(), date_format: None,
+        }),
+        ..Default::default()
+    });
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 0);
+    assert_eq!(output.failures, 3);
+
+    let errors: Vec<String> = output.executed_feeds.iter()
+        .filter(|(feed, _)| feed.alias != "local4")
+        .map(|(_, res)| format!("{:#}", res.as_ref().unwrap_err()))
+        .collect();
+    assert!(errors[0].contains("Invalid items path `$.items[*`: unclosed `[`"), "Incorrect error: {}", errors[0]);
+    assert!(errors[1].contains("The document is not valid JSON."), "Incorrect error: {}", errors[1]);
+    assert!(errors[2].contains("both an html and a json source"), "Incorrect error: {}", errors[2]);
+}
+
+/// Like html sources, a feed with a json source can't be renamed until its config section is, and
+/// its source is exported and checked on import.
+#[test]
+fn json_feed_rename_and_export() {
+    let (dir, mut cfg) = temp_config();
+
+    let url = run_rss_files_server().join("releases.json").unwrap();
+    example_add_feed_local1(url).execute(&cfg).unwrap();
+    let feed_cfg = FeedConfig {
+        json: Some(releases_source("$.items[*]", "created", Some("%s"))),
+        ..Default::default()
+    };
+    cfg.feeds.insert("local1".into(), feed_cfg.clone());
+
+    let err = RenameFeedCmd("local1".into(), "releases".into()).execute(&cfg).unwrap_err();
+    assert!(err.to_string().starts_with("Feed local1 is read with the json source in the [feeds.local1] section"),
+        "Incorrect error: {}", err);
+
+    // Once the section is renamed the feed can be renamed too
+    let section = cfg.feeds.remove("local1").unwrap();
+    cfg.feeds.insert("releases".into(), section);
+    RenameFeedCmd("local1".into(), "releases".into()).execute(&cfg).unwrap();
+
+    let export_path = dir.path().join("export.json");
+    let document = ExportCmd(Some(export_path.clone())).execute(&cfg).unwrap().document;
+    assert_eq!(document.feeds[0].json, feed_cfg.json);
+    assert!(document.feeds[0].html.is_none());
+
+    let (_dir2, mut cfg2) = temp_config();
+    cfg2.feeds.insert("releases".into(), FeedConfig {
+        json: Some(releases_source("$.items[*]", "published_at", None)),
+        ..Default::default()
+    });
+    let err = ImportCmd(export_path.clone(), ImportMode::Merge).execute(&cfg2).unwrap_err();
+    assert!(err.to_string().contains("doesn't match the [feeds.releases.json] section"), "Incorrect error: {}", err);
+
+    cfg2.feeds.insert("releases".into(), feed_cfg);
+    ImportCmd(export_path, ImportMode::Merge).execute(&cfg2).unwrap();
+    assert_eq!(ListFeedsCmd.execute(&cfg2).unwrap().feeds[0].alias, "releases");
+}
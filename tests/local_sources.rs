@@ -0,0 +1,163 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{FeedConfig, ListFiltersCmd, UpdateCmd};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+use chrono::prelude::*;
+use url::Url;
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+fn test_feed_file(name: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests/test_rss_feed_files");
+    path.push(name);
+    path
+}
+
+/// Write an executable shell script with the given body to `dir/name`.
+fn write_feed_program(dir: &Path, name: &str, body: &str) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::write(&path, format!("#!/bin/bash\n{}\n", body)).unwrap();
+
+    let mut perms = std::fs::metadata(&path).unwrap().permissions();
+    perms.set_mode(0o755);
+    std::fs::set_permissions(&path, perms).unwrap();
+
+    path
+}
+
+fn exec_url(program: &Path) -> Url {
+    Url::parse(&format!("exec:{}", program.to_string_lossy())).unwrap()
+}
+
+/// A program that writes more to stderr than is kept still runs to completion.
+#[test]
+fn exec_feed_large_stderr() {
+    let (dir, cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let program = write_feed_program(dir.path(), "noisy.sh",
+        &format!("head -c 1000000 /dev/zero >&2 || exit 1\ncat {}", test_feed_file("two_entries.rss").to_string_lossy()));
+    example_add_feed_local1(exec_url(&program)).execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], script_path).execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.failures, 0);
+}
+
+/// A `file://` feed is read from disk and processed like any other feed.
+#[test]
+fn file_feed_updates_filter() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let feed_url = Url::from_file_path(test_feed_file("two_entries.rss")).unwrap();
+    example_add_feed_local1(feed_url).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["asthmatic"], script_path).execute(&cfg).unwrap();
+
+    let res = UpdateCmd.execute(&cfg);
+    assert!(res.is_ok(), "Error running update with file feed: {:?}", res.unwrap_err());
+
+    let output = res.unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.updates, 1);
+    assert_eq!(output.failures, 0);
+
+    let script_output = std::fs::read_to_string(log_path).unwrap();
+    assert!(script_output.contains("url: http://www.example.com/blog/post/2"), "Incorrect script output: {}", script_output);
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    let timestamp = Utc.with_ymd_and_hms(2009, 9, 6, 16, 20, 0).unwrap();
+    assert_eq!(output.filters[0].last_updated.unwrap(), timestamp);
+}
+
+/// A missing file fails the feed but not the whole update.
+#[test]
+fn missing_file_feed_fails() {
+    let (dir, cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let good_url = Url::from_file_path(test_feed_file("simple_feed.rss")).unwrap();
+    let missing_url = Url::from_file_path(dir.path().join("missing.rss")).unwrap();
+    example_add_feed_local1(good_url).execute(&cfg).unwrap();
+    example_add_feed_local2(missing_url).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec!["Example"], script_path).execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.failures, 1);
+
+    let console_output = output.output().join("\n");
+    assert!(console_output.contains("Failed to read local2 rss feed"), "Incorrect output: {}", console_output);
+    assert!(console_output.contains("Failed to open feed file"), "Incorrect output: {}", console_output);
+}
+
+/// An `exec:` feed runs the program with the configured arguments and parses its stdout.
+#[test]
+fn exec_feed_updates_filter() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let program = write_feed_program(dir.path(), "make_feed.sh",
+        "[ \"$RSSACTIONS_FEED_ALIAS\" = local1 ] || exit 1\ncat \"$1\"");
+    example_add_feed_local1(exec_url(&program)).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path).execute(&cfg).unwrap();
+
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        exec_args: vec![test_feed_file("two_entries.rss").to_string_lossy().into()],
+        ..Default::default()
+    });
+
+    let res = UpdateCmd.execute(&cfg);
+    assert!(res.is_ok(), "Error running update with exec feed: {:?}", res.unwrap_err());
+
+    let output = res.unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.updates, 1);
+    assert_eq!(output.failures, 0);
+
+    let script_output = std::fs::read_to_string(log_path).unwrap();
+    assert_eq!(script_output.matches("rss action script start").count(), 2);
+}
+
+/// A program that exits with an error or runs past the timeout fails its feed, with stderr in the
+/// error output.
+#[test]
+fn exec_feed_failure_and_timeout() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let good = write_feed_program(dir.path(), "good.sh",
+        &format!("cat {}", test_feed_file("simple_feed.rss").to_string_lossy()));
+    let failing = write_feed_program(dir.path(), "failing.sh", "echo 'no feed today' >&2\nexit 3");
+    let slow = write_feed_program(dir.path(), "slow.sh", "sleep 10");
+
+    example_add_feed_local1(exec_url(&good)).execute(&cfg).unwrap();
+    example_add_feed_local2(exec_url(&failing)).execute(&cfg).unwrap();
+    example_add_feed_local3(exec_url(&slow)).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec!["Example"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local3(vec!["Example"], script_path).execute(&cfg).unwrap();
+
+    cfg.feeds.insert("local3".into(), FeedConfig {
+        exec_timeout_secs: Some(1),
+        ..Default::default()
+    });
+
+    let start = std::time::Instant::now();
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert!(start.elapsed() < std::time::Duration::from_secs(5), "Slow feed program was not killed");
+
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.failures, 2);
+
+    let console_output = output.output().join("\n");
+    assert!(console_output.contains("failed with exit status: 3"), "Incorrect output: {}", console_output);
+    assert!(console_output.contains("no feed today"), "Incorrect output: {}", console_output);
+    assert!(console_output.contains("did not finish within 1 seconds"), "Incorrect output: {}", console_output);
+}
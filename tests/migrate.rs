@@ -0,0 +1,121 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{DbMigrateCmd, ListFeedsCmd, ListFiltersCmd};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+/// The schema before it was versioned, with one feed and filter.
+const UNVERSIONED_SCHEMA: &str = "
+    CREATE TABLE feeds (
+        id INTEGER PRIMARY KEY,
+        url TEXT NOT NULL,
+        alias TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE filters (
+        id INTEGER PRIMARY KEY,
+        feed_id INTEGER NOT NULL,
+        keywords TEXT NOT NULL,
+        script_path TEXT NOT NULL,
+        last_updated TEXT,
+        FOREIGN KEY (feed_id) REFERENCES feeds(id),
+        UNIQUE(feed_id,keywords,script_path)
+    );
+    INSERT INTO feeds (url, alias) VALUES ('https://example.com/feed.rss', 'example_1');
+    INSERT INTO filters (feed_id, keywords, script_path) VALUES (1, 'test', '/bin/false');";
+
+fn user_version(conn: &rusqlite::Connection) -> usize {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap()
+}
+
+#[test]
+/// A new database is created at the current version and checking doesn't create it.
+fn new_database() {
+    let (_dir, cfg) = temp_config();
+
+    let output = DbMigrateCmd { check: true }.execute(&cfg).unwrap();
+    assert_eq!(output.from_version, 0);
+    assert_eq!(output.migrations.len(), output.to_version);
+    assert!(!cfg.db_path.exists(), "checking created the database");
+
+    ListFeedsCmd.execute(&cfg).unwrap();
+
+    let output = DbMigrateCmd { check: true }.execute(&cfg).unwrap();
+    assert_eq!(output.from_version, output.to_version);
+    assert!(output.migrations.is_empty());
+    assert_eq!(output.output(), [format!("The database is up to date at schema version {}.", output.to_version)]);
+
+    let conn = rusqlite::Connection::open(&cfg.db_path).unwrap();
+    assert_eq!(user_version(&conn), output.to_version);
+
+    let backups = std::fs::read_dir(cfg.db_path.parent().unwrap()).unwrap()
+        .filter(|entry| entry.as_ref().unwrap().path().to_string_lossy().ends_with(".bak"))
+        .count();
+    assert_eq!(backups, 0, "a new database was backed up");
+}
+
+#[test]
+/// Databases from before the schema was versioned are migrated, keeping their data, after being
+/// backed up.
+fn unversioned_database_is_backed_up_and_migrated() {
+    let (_dir, cfg) = temp_config();
+    rusqlite::Connection::open(&cfg.db_path).unwrap().execute_batch(UNVERSIONED_SCHEMA).unwrap();
+
+    let output = DbMigrateCmd { check: true }.execute(&cfg).unwrap();
+    assert_eq!(output.from_version, 1);
+    assert_eq!(output.migrations.len(), output.to_version - 1);
+    assert_eq!(output.output()[0], format!(
+        "The database is at schema version 1 and needs {} migrations to reach version {}:",
+        output.to_version - 1, output.to_version));
+
+    let output = DbMigrateCmd { check: false }.execute(&cfg).unwrap();
+    assert_eq!(output.from_version, 1);
+    assert_eq!(output.migrations.len(), output.to_version - 1);
+    let backup_path = output.backup_path.clone().unwrap();
+    assert_eq!(backup_path.file_name().unwrap(), "rss-actions-test.db.v1.bak");
+    assert_eq!(output.output().last().unwrap(),
+        &format!("The database was backed up to {}", backup_path.display()));
+
+    // The backup is the database as it was before migrating
+    let backup = rusqlite::Connection::open(&backup_path).unwrap();
+    assert_eq!(user_version(&backup), 0);
+    let err = backup.prepare("SELECT name FROM filters").unwrap_err();
+    assert!(err.to_string().contains("no such column"));
+
+    let output = ListFiltersCmd.execute(&cfg).unwrap();
+    assert_eq!(output.filters.len(), 1);
+    assert_eq!(output.filters[0].alias, "example_1");
+    assert_eq!(output.filters[0].keywords, ["test"]);
+
+    let output = DbMigrateCmd { check: false }.execute(&cfg).unwrap();
+    assert!(output.migrations.is_empty());
+    assert!(output.backup_path.is_none());
+}
+
+#[test]
+/// Databases are migrated automatically by other commands.
+fn commands_migrate_database() {
+    let (_dir, cfg) = temp_config();
+    rusqlite::Connection::open(&cfg.db_path).unwrap().execute_batch(UNVERSIONED_SCHEMA).unwrap();
+
+    let output = ListFeedsCmd.execute(&cfg).unwrap();
+    assert_eq!(output.feeds.len(), 1);
+
+    let output = DbMigrateCmd { check: true }.execute(&cfg).unwrap();
+    assert!(output.migrations.is_empty());
+    assert!(cfg.db_path.with_file_name("rss-actions-test.db.v1.bak").exists());
+}
+
+#[test]
+/// Databases from a newer version of rss-actions aren't touched.
+fn newer_database_errors() {
+    let (_dir, cfg) = temp_config();
+    ListFeedsCmd.execute(&cfg).unwrap();
+    rusqlite::Connection::open(&cfg.db_path).unwrap().pragma_update(None, "user_version", 99).unwrap();
+
+    let err = ListFeedsCmd.execute(&cfg).unwrap_err();
+    assert!(format!("{:?}", err).contains("The database has schema version 99, but this version of rss-actions only supports"),
+        "{:?}", err);
+
+    let conn = rusqlite::Connection::open(&cfg.db_path).unwrap();
+    assert_eq!(user_version(&conn), 99);
+}
@@ -0,0 +1,108 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{FilterId, ListFiltersCmd, UpdateCmd, UpdateSelectedCmd, UpdateSelection};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+fn logged_titles(log_path: &std::path::Path) -> Vec<String> {
+    let log = std::fs::read_to_string(log_path).unwrap_or_default();
+    let _ = std::fs::remove_file(log_path);
+    log.lines()
+        .filter_map(|line| line.strip_prefix("title: "))
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+/// A higher-priority stop filter runs first and lower-priority filters on the feed skip the
+/// entries it matches.
+fn stop_filter_routes_entries() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    // The digest filter is added first, but the urgent one has a higher priority
+    example_add_filter_local1(vec![], script_path.clone()).execute(&cfg).unwrap();
+    let mut cmd = example_add_filter_local1(vec!["nyc"], script_path);
+    cmd.0.priority = 10;
+    cmd.0.stop = true;
+    let lines = cmd.execute(&cfg).unwrap().output();
+    assert!(lines.contains(&"Priority: 10".to_string()));
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 2);
+    assert_eq!(logged_titles(&log_path), [
+        "Example entry NYC with random asthmatic words guestbook interspersed",
+        "Pizza Example marshmallow entry with random listener words interspersed",
+    ]);
+
+    // Both filters moved past the entries
+    let filters = ListFiltersCmd.execute(&cfg).unwrap().filters;
+    assert!(filters.iter().all(|filter| filter.last_updated.is_some()));
+}
+
+#[test]
+/// A stop filter doesn't make filters with the same priority skip entries, even if it was added
+/// first.
+fn stop_filter_same_priority() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    let mut cmd = example_add_filter_local1(vec!["nyc"], script_path.clone());
+    cmd.0.stop = true;
+    cmd.execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], script_path).execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 2);
+    assert_eq!(logged_titles(&log_path), [
+        "Example entry NYC with random asthmatic words guestbook interspersed",
+        "Pizza Example marshmallow entry with random listener words interspersed",
+        "Example entry NYC with random asthmatic words guestbook interspersed",
+    ]);
+}
+
+#[test]
+/// Stop filters apply even when only the lower-priority filter is updated.
+fn stop_filter_applies_to_selected_update() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], script_path.clone()).execute(&cfg).unwrap();
+    let mut cmd = example_add_filter_local1(vec!["nyc"], script_path);
+    cmd.0.priority = 1;
+    cmd.0.stop = true;
+    cmd.execute(&cfg).unwrap();
+
+    let selection = UpdateSelection { filters: vec![FilterId(1)], ..Default::default() };
+    UpdateSelectedCmd(selection).execute(&cfg).unwrap();
+    assert_eq!(logged_titles(&log_path), ["Pizza Example marshmallow entry with random listener words interspersed"]);
+}
+
+#[test]
+/// In a dedup group the filter with the highest priority runs the script.
+fn priority_decides_dedup_winner() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    let mut cmd = example_add_filter_local1(vec![], script_path.clone());
+    cmd.0.dedup_group = Some("group".into());
+    cmd.execute(&cfg).unwrap();
+    let mut cmd = example_add_filter_local2(vec![], script_path);
+    cmd.0.dedup_group = Some("group".into());
+    cmd.0.priority = 5;
+    cmd.execute(&cfg).unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(logged_titles(&log_path).len(), 1);
+    assert_eq!(output.deduplicated.len(), 1);
+    assert_eq!(output.deduplicated[0].0.id, Some(FilterId(1)));
+}
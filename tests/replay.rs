@@ -0,0 +1,156 @@
+mod test_utils;
+use test_utils::*;
+
+use chrono::{TimeZone, Utc};
+
+use rss_actions::{AddFilterCmd, FilterId, FilterSelector, ListFiltersCmd, ReplayCmd, ReplayHistoryCmd, ReplayWindow,
+    TagFeedCmd, UpdateCmd};
+use rss_actions::Filter;
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+#[test]
+/// Replaying the last entries runs the script on them again without changing the filter.
+fn replay_last_entries() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["example"], script_path).execute(&cfg).unwrap();
+    UpdateCmd.execute(&cfg).unwrap();
+    let filter_before = ListFiltersCmd.execute(&cfg).unwrap().filters.remove(0);
+    std::fs::remove_file(&log_path).unwrap();
+
+    let output = ReplayCmd(FilterSelector::Id(FilterId(1)), ReplayWindow::Last(1)).execute(&cfg).unwrap();
+    assert_eq!(output.results.len(), 1);
+    assert!(output.results[0].2.is_ok());
+
+    let script_output = std::fs::read_to_string(&log_path).unwrap();
+    assert!(script_output.contains("title: Example entry NYC"));
+    assert!(!script_output.contains("title: Pizza"));
+
+    let lines = output.output();
+    assert_eq!(lines[0], "Replayed entries the last 1 for the filter on feed local1 with keywords: example");
+    assert!(lines[2].starts_with("ok\t"));
+    assert_eq!(lines[4], "1 scripts succeeded, 0 failed.");
+    assert_eq!(lines.len(), 5);
+
+    let filter_after = ListFiltersCmd.execute(&cfg).unwrap().filters.remove(0);
+    assert_eq!(filter_before, filter_after);
+
+    // More entries than match just replays all of them
+    let output = ReplayCmd(FilterSelector::Id(FilterId(1)), ReplayWindow::Last(10)).execute(&cfg).unwrap();
+    assert_eq!(output.results.len(), 2);
+
+    let err = ReplayCmd(FilterSelector::Id(FilterId(1)), ReplayWindow::Last(0)).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "The number of entries to replay must be at least 1.");
+}
+
+#[test]
+/// Replaying since a time only runs the script on matching entries published from then on, even
+/// if the filter hasn't seen them yet.
+fn replay_since_date() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["example"], script_path).execute(&cfg).unwrap();
+
+    let since = Utc.with_ymd_and_hms(2009, 9, 6, 10, 0, 0).unwrap();
+    let selector = FilterSelector::Keywords("local1".into(), vec!["example".into()]);
+    let output = ReplayCmd(selector.clone(), ReplayWindow::Since(since)).execute(&cfg).unwrap();
+    assert_eq!(output.results.len(), 2);
+    assert_eq!(output.results[0].1.title, "Pizza Example marshmallow entry with random listener words interspersed");
+    let lines = output.output();
+    assert_eq!(lines[lines.len() - 1],
+        "2 of the entries are newer than the filter's last update, so update will run the script on them again.");
+
+    std::fs::remove_file(&log_path).unwrap();
+    let since = Utc.with_ymd_and_hms(2009, 9, 6, 12, 0, 0).unwrap();
+    let output = ReplayCmd(selector.clone(), ReplayWindow::Since(since)).execute(&cfg).unwrap();
+    assert_eq!(output.results.len(), 1);
+    let script_output = std::fs::read_to_string(&log_path).unwrap();
+    assert!(script_output.contains("title: Example entry NYC"));
+
+    // The filter still hasn't been updated
+    assert!(ListFiltersCmd.execute(&cfg).unwrap().filters[0].last_updated.is_none());
+
+    let since = Utc.with_ymd_and_hms(2010, 1, 1, 0, 0, 0).unwrap();
+    let output = ReplayCmd(selector, ReplayWindow::Since(since)).execute(&cfg).unwrap();
+    assert!(output.results.is_empty());
+    assert!(output.output()[0].starts_with("No entries since "));
+}
+
+fn script_runs(log_path: &std::path::Path) -> usize {
+    let runs = std::fs::read_to_string(log_path).map_or(0, |log| log.matches("rss action script end").count());
+    let _ = std::fs::remove_file(log_path);
+    runs
+}
+
+#[test]
+/// Replay results are stored, and replayed entries count as processed for the filter's dedup group.
+fn replay_is_recorded() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    for mut cmd in [example_add_filter_local1(vec![], script_path.clone()),
+                    example_add_filter_local2(vec![], script_path)] {
+        cmd.0.dedup_group = Some("mirrors".into());
+        cmd.execute(&cfg).unwrap();
+    }
+    example_add_filter_local1(vec!["pizza"], "/bin/false".into()).execute(&cfg).unwrap();
+
+    let selector = FilterSelector::Id(FilterId(1));
+    let lines = ReplayHistoryCmd(selector.clone()).execute(&cfg).unwrap().output();
+    assert_eq!(lines, ["The filter on feed local1 with keywords:  hasn't been replayed."]);
+
+    ReplayCmd(selector.clone(), ReplayWindow::Last(1)).execute(&cfg).unwrap();
+    assert_eq!(script_runs(&log_path), 1);
+    let records = ReplayHistoryCmd(selector).execute(&cfg).unwrap().records;
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].feed, "local1");
+    assert_eq!(records[0].title, "Example entry NYC with random asthmatic words guestbook interspersed");
+    assert!(records[0].error.is_none());
+
+    // The replayed entry isn't run again by either filter in the group, so only the other entry is
+    // run, once
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(script_runs(&log_path), 1);
+    assert_eq!(output.deduplicated.len(), 3);
+
+    let selector = FilterSelector::Id(FilterId(3));
+    ReplayCmd(selector.clone(), ReplayWindow::Last(1)).execute(&cfg).unwrap();
+    let history = ReplayHistoryCmd(selector).execute(&cfg).unwrap();
+    assert!(history.records[0].error.as_ref().unwrap().contains("/bin/false"));
+    assert!(history.output()[2].contains("\tfailed\tlocal1\t"));
+}
+
+#[test]
+/// A filter on a tag is replayed on each feed with the tag.
+fn replay_tag_filter() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    for alias in ["local1", "local2"] {
+        TagFeedCmd(alias.into(), vec!["news".into()]).execute(&cfg).unwrap();
+    }
+    AddFilterCmd(Filter::on_tag("news", vec![], script_path).unwrap()).execute(&cfg).unwrap();
+
+    let output = ReplayCmd(FilterSelector::Id(FilterId(1)), ReplayWindow::Last(1)).execute(&cfg).unwrap();
+    assert_eq!(script_runs(&log_path), 2);
+    let aliases: Vec<&str> = output.results.iter().map(|(alias, _, _)| alias.as_str()).collect();
+    assert_eq!(aliases, ["local1", "local2"]);
+
+    let lines = output.output();
+    assert_eq!(lines[0], "Replayed entries the last 1 for the filter on tag news with keywords: ");
+    assert!(lines[2].starts_with("ok\tlocal1\t"));
+    assert_eq!(lines[lines.len() - 1],
+        "2 of the entries are newer than the filter's last update, so update will run the script on them again.");
+}
@@ -0,0 +1,260 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{Config, FeedConfig, BasicAuth, SecretRef, UpdateCmd};
+use rss_actions::RSSActionCmd;
+
+use std::sync::mpsc::Sender;
+
+use url::Url;
+use warp::Filter;
+
+static TEST_TOKEN: &str = "hunter2-token";
+
+/// Start a local server that only serves the example rss files to requests that have the bearer
+/// token `TEST_TOKEN` or the basic auth user `user` with password `TEST_TOKEN`.
+fn run_private_rss_files_server() -> Url {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let tokio_runtime = tokio::runtime::Runtime::new().unwrap();
+        tokio_runtime.block_on(_run_private_rss_files_server(tx));
+    });
+    rx.recv().unwrap()
+}
+
+async fn _run_private_rss_files_server(tx: Sender<Url>) {
+    let mut test_rss_files_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_rss_files_dir.push("tests/test_rss_feed_files/");
+
+    // "user:hunter2-token" base64 encoded
+    let bearer = format!("Bearer {}", TEST_TOKEN);
+    let basic = "Basic dXNlcjpodW50ZXIyLXRva2Vu".to_string();
+    let authorized = warp::header::<String>("authorization")
+        .and_then(move |auth: String| {
+            let ok = auth == bearer || auth == basic;
+            async move {
+                if ok { Ok(()) } else { Err(warp::reject::not_found()) }
+            }
+        })
+        .untuple_one();
+
+    let route = warp::path("feeds").and(authorized).and(warp::fs::dir(test_rss_files_dir));
+
+    let any_port_addr: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let (addr, server) = warp::serve(route).bind_ephemeral(any_port_addr);
+
+    let url = Url::parse(&format!("http://127.0.0.1:{}/feeds/", addr.port())).unwrap();
+    tx.send(url).unwrap();
+    server.await;
+}
+
+/// Add a feed pointing at the private server and a filter that matches one entry.
+fn add_private_feed_and_filter(cfg: &Config, script_path: std::path::PathBuf) {
+    let base_url = run_private_rss_files_server();
+    let feed_url = base_url.join("simple_feed.rss").unwrap();
+
+    example_add_feed_local1(feed_url).execute(cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path).execute(cfg).unwrap();
+}
+
+/// Without credentials the private feed fails, so the secrets are what make the other tests pass.
+#[test]
+fn private_feed_without_secret_fails() {
+    let (dir, cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+    add_private_feed_and_filter(&cfg, script_path);
+
+    let res = UpdateCmd.execute(&cfg);
+    // the only feed failed, so the whole update fails
+    assert!(res.is_err(), "Update of private feed without credentials succeeded");
+}
+
+/// A bearer token read from an environment variable is sent with the request.
+#[test]
+fn bearer_token_from_env_secret() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+    add_private_feed_and_filter(&cfg, script_path);
+
+    std::env::set_var("RSSACTIONS_TEST_BEARER_TOKEN", TEST_TOKEN);
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        bearer_token: Some(SecretRef::Env("RSSACTIONS_TEST_BEARER_TOKEN".into())),
+        ..Default::default()
+    });
+
+    let res = UpdateCmd.execute(&cfg);
+    assert!(res.is_ok(), "Error running update with bearer token: {:?}", res.unwrap_err());
+
+    let output = res.unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.updates, 1);
+    assert_eq!(output.failures, 0);
+    assert!(log_path.exists(), "Script was not run on private feed");
+}
+
+/// Basic auth with the password read from a file, and a secret header from a command.
+#[test]
+fn basic_auth_from_file_secret() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+    add_private_feed_and_filter(&cfg, script_path);
+
+    let secret_file = dir.path().join("password");
+    std::fs::write(&secret_file, format!("{}\n", TEST_TOKEN)).unwrap();
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        basic_auth: Some(BasicAuth {
+            username: "user".into(),
+            password: SecretRef::File(secret_file),
+        }),
+        secret_headers: [("X-Api-Key".to_string(), SecretRef::Command("echo unused-key".into()))].into(),
+        ..Default::default()
+    });
+
+    let res = UpdateCmd.execute(&cfg);
+    assert!(res.is_ok(), "Error running update with basic auth: {:?}", res.unwrap_err());
+    assert_eq!(res.unwrap().updates, 1);
+    assert!(log_path.exists(), "Script was not run on private feed");
+}
+
+/// Errors resolving secrets name the reference, and the secret value never makes it into the error
+/// output or the database file.
+#[test]
+fn secret_errors_and_db_do_not_contain_secret() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+    add_private_feed_and_filter(&cfg, script_path.clone());
+
+    // second feed so the update as a whole doesn't fail, with a failing secret command
+    let base_url = run_private_rss_files_server();
+    example_add_feed_local2(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec!["Example"], script_path).execute(&cfg).unwrap();
+
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        bearer_token: Some(SecretRef::Command(format!("echo {}", TEST_TOKEN))),
+        ..Default::default()
+    });
+    cfg.feeds.insert("local2".into(), FeedConfig {
+        bearer_token: Some(SecretRef::Env("RSSACTIONS_TEST_UNSET_VARIABLE".into())),
+        ..Default::default()
+    });
+
+    let res = UpdateCmd.execute(&cfg);
+    assert!(res.is_ok(), "Error running update: {:?}", res.unwrap_err());
+
+    let output = res.unwrap();
+    assert_eq!(output.successes, 1);
+    assert_eq!(output.failures, 1);
+
+    let console_output = rss_actions::ConsoleOutput::output(&output).join("\n");
+    assert!(console_output.contains("RSSACTIONS_TEST_UNSET_VARIABLE is not set"),
+        "Error output did not name the missing secret: {}", console_output);
+    assert!(!console_output.contains(TEST_TOKEN), "Error output contained secret: {}", console_output);
+
+    let db_contents = std::fs::read(&cfg.db_path).unwrap();
+    let token_bytes = TEST_TOKEN.as_bytes();
+    assert!(!db_contents.windows(token_bytes.len()).any(|w| w == token_bytes),
+        "Database file contains the secret");
+}
+
+/// Secret references round-trip through the config file as references, and values without a known
+/// prefix are rejected without echoing them back.
+#[test]
+fn secret_references_in_config_file() {
+    let (dir, _cfg) = temp_config();
+    let cfg_path = dir.path().join("config.toml");
+
+    let config_data = format!("db_path = {:?}
+
+[feeds.private]
+bearer_token = \"env:FEED_TOKEN\"
+
+[feeds.private.basic_auth]
+username = \"me\"
+password = \"file:/run/secrets/token\"
+
+[feeds.private.secret_headers]
+X-Api-Key = \"cmd:pass show api-key\"
+", dir.path().join("rss-actions.db"));
+    std::fs::write(&cfg_path, config_data).unwrap();
+
+    let cfg = Config::open(Some(&cfg_path)).unwrap();
+    let feed_cfg = cfg.feed_config("private");
+    assert_eq!(feed_cfg.bearer_token, Some(SecretRef::Env("FEED_TOKEN".into())));
+    assert_eq!(feed_cfg.basic_auth.unwrap().password, SecretRef::File("/run/secrets/token".into()));
+    assert_eq!(feed_cfg.secret_headers["X-Api-Key"], SecretRef::Command("pass show api-key".into()));
+
+    // feeds without a section get the defaults
+    assert!(cfg.feed_config("other").bearer_token.is_none());
+
+    // written back out as references
+    cfg.write_out(&cfg_path).unwrap();
+    let written = std::fs::read_to_string(&cfg_path).unwrap();
+    assert!(written.contains("env:FEED_TOKEN"), "Config was not written with secret reference:\n{}", written);
+
+    // plaintext secrets are rejected, and not included in the error
+    let config_data = format!("db_path = {:?}
+[feeds.private]
+bearer_token = \"{}\"
+", dir.path().join("rss-actions.db"), TEST_TOKEN);
+    std::fs::write(&cfg_path, config_data).unwrap();
+
+    let res = Config::open(Some(&cfg_path));
+    assert!(res.is_err(), "Config with plaintext secret was accepted");
+    let err = format!("{:?}", res.unwrap_err());
+    assert!(err.contains("Secrets must be given as"), "Incorrect error message: {}", err);
+    assert!(!err.contains(TEST_TOKEN), "Error message contained the secret: {}", err);
+}
+
+/// Start a local server that redirects every request under `/feeds/` to the same file under
+/// `target`.
+fn run_redirect_server(target: Url) -> Url {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let tokio_runtime = tokio::runtime::Runtime::new().unwrap();
+        tokio_runtime.block_on(async move {
+            let route = warp::path!("feeds" / String).map(move |file: String| {
+                let next = target.join(&file).unwrap().as_str().parse::<warp::http::Uri>().unwrap();
+                warp::redirect::temporary(next)
+            });
+
+            let any_port_addr: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
+            let (addr, server) = warp::serve(route).bind_ephemeral(any_port_addr);
+            tx.send(Url::parse(&format!("http://127.0.0.1:{}/feeds/", addr.port())).unwrap()).unwrap();
+            server.await;
+        });
+    });
+    rx.recv().unwrap()
+}
+
+/// A feed with secret headers isn't followed to another server, since reqwest would send the
+/// headers along, but feeds without them still are.
+#[test]
+fn secret_headers_are_not_sent_to_another_origin() {
+    let (dir, mut cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    let redirect_url = run_redirect_server(base_url.clone());
+    example_add_feed_local1(redirect_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["Example"], script_path).execute(&cfg).unwrap();
+    // Keeps the update from failing as a whole
+    example_add_feed_local2(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+
+    cfg.feeds.insert("local1".into(), FeedConfig {
+        secret_headers: [("X-Api-Key".to_string(), SecretRef::Command(format!("echo {}", TEST_TOKEN)))].into(),
+        ..Default::default()
+    });
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.failures, 1);
+    let (feed, res) = output.executed_feeds.iter().find(|(_, res)| res.is_err()).unwrap();
+    assert_eq!(feed.alias, "local1");
+    let err = format!("{:#}", res.as_ref().unwrap_err());
+    assert!(err.contains("which is on a different server"), "Incorrect error message: {}", err);
+    assert!(!err.contains(TEST_TOKEN));
+    assert!(!log_path.exists());
+
+    cfg.feeds.clear();
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.updates, 1);
+    assert!(log_path.exists());
+}
@@ -0,0 +1,110 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{EntryMatch, FilterId, ShowFeedCmd, UpdateCmd};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+#[test]
+/// Entries are shown in feed order with the filters that match them.
+fn show_feed_with_matching_filters() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["marshmallow"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["example"], script_path).execute(&cfg).unwrap();
+
+    let output = ShowFeedCmd("local1".into()).execute(&cfg).unwrap();
+    assert_eq!(output.title.as_deref(), Some("Two entries RSS Title"));
+    assert_eq!(output.entries.len(), 2);
+
+    let first = output.entries[0].as_ref().unwrap();
+    assert_eq!(first.entry.title, "Example entry NYC with random asthmatic words guestbook interspersed");
+    assert_eq!(first.entry.guid.as_deref(), Some("7bd204c6-1655-4c27-aeee-53f933c5395f"));
+    assert_eq!(first.matching_filters.len(), 1);
+    assert_eq!(first.matching_filters[0].0.id, Some(FilterId(2)));
+    assert_eq!(first.matching_filters[0].1, EntryMatch::New);
+
+    let second = output.entries[1].as_ref().unwrap();
+    assert!(second.entry.guid.is_none());
+    assert_eq!(second.matching_filters.len(), 2);
+
+    let lines = output.output();
+    assert_eq!(lines[0], "Feed local1: Two entries RSS Title");
+    assert_eq!(lines[3], "1. Example entry NYC with random asthmatic words guestbook interspersed");
+    assert_eq!(lines[6], "   GUID: 7bd204c6-1655-4c27-aeee-53f933c5395f");
+    assert_eq!(lines[7], "   Matching filters: 2 (new)");
+    assert_eq!(lines[12], "   Matching filters: 1 (new), 2 (new)");
+    assert_eq!(lines.last().unwrap(), "2 entries, 0 rejected.");
+
+    assert!(!log_path.exists(), "a script was run");
+
+    // After an update the entries have been seen by the filters
+    UpdateCmd.execute(&cfg).unwrap();
+    let lines = ShowFeedCmd("local1".into()).execute(&cfg).unwrap().output();
+    assert_eq!(lines[12], "   Matching filters: 1 (seen), 2 (seen)");
+}
+
+#[test]
+/// Entries that update would reject are shown with the reason.
+fn show_feed_with_rejected_entries() {
+    let (_dir, cfg) = temp_config();
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("missing_data.rss").unwrap()).execute(&cfg).unwrap();
+
+    let output = ShowFeedCmd("local1".into()).execute(&cfg).unwrap();
+    assert_eq!(output.entries.len(), 2);
+    assert!(output.entries[0].is_err());
+    assert!(output.entries[1].is_ok());
+
+    let lines = output.output();
+    assert_eq!(lines[3], "1. Rejected: Entry link is missing.");
+    assert_eq!(lines[5], "2. Pizza Example marshmallow entry with random listener words interspersed");
+    assert_eq!(lines[lines.len() - 2], "2 entries, 1 rejected.");
+}
+
+#[test]
+/// Enclosures are parsed and shown.
+fn show_feed_with_enclosures() {
+    let (_dir, cfg) = temp_config();
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("podcast.rss").unwrap()).execute(&cfg).unwrap();
+
+    let output = ShowFeedCmd("local1".into()).execute(&cfg).unwrap();
+    let entry = &output.entries[0].as_ref().unwrap().entry;
+    let enclosure = entry.enclosure.as_ref().unwrap();
+    assert_eq!(enclosure.url, "http://www.example.com/podcast/episode2.mp3");
+    assert_eq!(enclosure.mime_type, "audio/mpeg");
+    assert_eq!(enclosure.length, Some(2048));
+
+    let lines = output.output();
+    assert_eq!(lines[7], "   Enclosure: http://www.example.com/podcast/episode2.mp3 (audio/mpeg, 2048 bytes)");
+    assert_eq!(lines[8], "   Matching filters: none");
+
+    let err = ShowFeedCmd("missing".into()).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "Couldn't find a feed with alias missing.");
+}
+
+#[test]
+/// The database isn't locked while the feed is downloaded.
+fn show_feed_does_not_lock_db_while_fetching() {
+    let (dir, cfg) = temp_config();
+    let feed_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/test_rss_feed_files/two_entries.rss");
+    let (program, started_path, release_path) = temp_blocking_script(dir.path(), Some(&feed_path));
+    example_add_feed_local1(url::Url::parse(&format!("exec:{}", program.to_string_lossy())).unwrap())
+        .execute(&cfg).unwrap();
+
+    let show_cfg = cfg.clone();
+    let show_thread = std::thread::spawn(move || ShowFeedCmd("local1".into()).execute(&show_cfg));
+
+    wait_for_file(&started_path);
+    example_add_feed_local2(url::Url::parse("https://example.com/feed.rss").unwrap()).execute(&cfg).unwrap();
+
+    std::fs::write(&release_path, "").unwrap();
+    let output = show_thread.join().unwrap().unwrap();
+    assert_eq!(output.entries.len(), 2);
+}
@@ -0,0 +1,122 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{AddFilterCmd, DeleteFeedCmd, ExportCmd, ImportCmd, ListFeedsCmd, ListFiltersCmd, TagFeedCmd,
+    UntagFeedCmd, UpdateCmd, UpdateSelectedCmd, UpdateSelection};
+use rss_actions::{Filter, ImportMode};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+fn logged_titles(log_path: &std::path::Path) -> Vec<String> {
+    let log = std::fs::read_to_string(log_path).unwrap_or_default();
+    let _ = std::fs::remove_file(log_path);
+    log.lines()
+        .filter_map(|line| line.strip_prefix("title: "))
+        .map(String::from)
+        .collect()
+}
+
+fn tag(alias: &str, tags: &[&str]) -> TagFeedCmd {
+    TagFeedCmd(alias.into(), tags.iter().map(|&tag| tag.into()).collect())
+}
+
+#[test]
+/// A filter on a tag runs on every feed with the tag and keeps track of each feed separately, so a
+/// feed tagged later still gets all of its entries.
+fn tag_filter_runs_on_each_feed() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local3(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    tag("local1", &["news"]).execute(&cfg).unwrap();
+    let lines = tag("local2", &["news", "vendors"]).execute(&cfg).unwrap().output();
+    assert_eq!(lines, ["Successfully tagged feed local2", "Tags: news, vendors"]);
+
+    let filter = Filter::on_tag("news", vec!["nyc".into()], script_path).unwrap();
+    let lines = AddFilterCmd(filter).execute(&cfg).unwrap().output();
+    assert_eq!(lines[0], "Successfully added filter on tag news");
+    assert!(ListFiltersCmd.execute(&cfg).unwrap().output()[2].contains("\ttag:news\t"));
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 2);
+    assert_eq!(logged_titles(&log_path).len(), 2);
+
+    // Nothing is new on either feed
+    UpdateCmd.execute(&cfg).unwrap();
+    assert!(logged_titles(&log_path).is_empty());
+
+    // A newly tagged feed starts from the beginning, without affecting the others
+    tag("local3", &["news"]).execute(&cfg).unwrap();
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.updates, 1);
+    assert_eq!(logged_titles(&log_path), ["Example entry NYC with random asthmatic words guestbook interspersed"]);
+
+    // The filter's state is kept through an export and import
+    let exported = ExportCmd(None).execute(&cfg).unwrap().document;
+    assert_eq!(exported.filters[0].feed_last_updated.len(), 3);
+    let export_path = dir.path().join("export.json");
+    ExportCmd(Some(export_path.clone())).execute(&cfg).unwrap();
+    let (_dir2, cfg2) = temp_config();
+    ImportCmd(export_path, ImportMode::Merge).execute(&cfg2).unwrap();
+    assert_eq!(ListFeedsCmd.execute(&cfg).unwrap().output(), ListFeedsCmd.execute(&cfg2).unwrap().output());
+    UpdateCmd.execute(&cfg2).unwrap();
+    assert!(logged_titles(&log_path).is_empty());
+}
+
+#[test]
+/// `update --tag` only downloads feeds with the tag.
+fn update_selected_tag() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec![], script_path).execute(&cfg).unwrap();
+    tag("local2", &["vendors"]).execute(&cfg).unwrap();
+
+    let selection = UpdateSelection { tags: vec!["vendors".into()], ..Default::default() };
+    let output = UpdateSelectedCmd(selection).execute(&cfg).unwrap();
+    assert_eq!(output.executed_feeds.len(), 1);
+    assert_eq!(output.executed_feeds[0].0.alias, "local2");
+    assert_eq!(logged_titles(&log_path).len(), 1);
+
+    let selection = UpdateSelection { tags: vec!["nope".into()], ..Default::default() };
+    let err = UpdateSelectedCmd(selection).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "No feed with the tag `nope` was found to update.");
+}
+
+#[test]
+/// Untagging a feed stops filters on the tag from running on it, and tagged feeds can be deleted.
+fn untag_and_delete_feed() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    tag("local1", &["news"]).execute(&cfg).unwrap();
+    AddFilterCmd(Filter::on_tag("news", vec![], script_path.clone()).unwrap()).execute(&cfg).unwrap();
+    let err = AddFilterCmd(Filter::on_tag("news", vec![], script_path).unwrap()).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "You can't add another filter with the same tag, keyword, and script path.");
+
+    let err = UntagFeedCmd("local1".into(), vec!["other".into()]).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "The feed `local1` doesn't have the tag `other`.");
+    let err = tag("local1", &["two words"]).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "A tag must not contain whitespace or commas: two words");
+
+    UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(logged_titles(&log_path).len(), 2);
+
+    let lines = UntagFeedCmd("local1".into(), vec!["news".into()]).execute(&cfg).unwrap().output();
+    assert_eq!(lines, ["Successfully untagged feed local1", "Tags: (none)"]);
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert!(output.executed_filters.is_empty());
+
+    // The filter's state on the feed doesn't keep it from being deleted
+    tag("local1", &["news"]).execute(&cfg).unwrap();
+    DeleteFeedCmd("local1".into()).execute(&cfg).unwrap();
+    assert_eq!(ListFiltersCmd.execute(&cfg).unwrap().filters.len(), 1);
+}
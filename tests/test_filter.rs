@@ -0,0 +1,127 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{EditFilterCmd, EntryMatch, FilterId, FilterSelector, ListFiltersCmd, TestFilterCmd, UpdateCmd};
+use rss_actions::{RSSActionCmd, ConsoleOutput};
+
+use std::path::PathBuf;
+
+fn feed_file(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/test_rss_feed_files").join(name)
+}
+
+#[test]
+/// Keywords can be tried out on a feed before adding a filter, and nothing is run or stored.
+fn test_keywords_on_feed() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["pizza"], script_path).execute(&cfg).unwrap();
+
+    let cmd = TestFilterCmd {
+        filter: FilterSelector::Keywords("local1".into(), vec!["example".into(), "nyc".into()]),
+        from_file: None,
+    };
+    let output = cmd.execute(&cfg).unwrap();
+    assert_eq!(output.entries.len(), 2);
+    assert!(output.entry_errors.is_empty());
+    // Sorted by date, oldest first
+    assert_eq!(output.entries[0].0.title, "Pizza Example marshmallow entry with random listener words interspersed");
+    assert_eq!(output.entries[0].1, EntryMatch::MissingKeywords(vec!["nyc".into()]));
+    assert_eq!(output.entries[1].1, EntryMatch::New);
+
+    let lines = output.output();
+    assert_eq!(lines[0], "Filter on feed local1 with keywords: example, nyc");
+    assert_eq!(lines[1], "Last updated: Never updated");
+    assert!(lines[3].starts_with("no match\t"));
+    assert!(lines[3].ends_with("\tmissing nyc"));
+    assert!(lines[4].starts_with("new\t"));
+    assert!(lines[4].ends_with("\thttp://www.example.com/blog/post/2"));
+    assert_eq!(lines[6], "1 new entries would be passed to the script, 0 matching entries were already seen, 1 entries did not match.");
+
+    assert!(!log_path.exists(), "a script was run");
+    let filters = ListFiltersCmd.execute(&cfg).unwrap().filters;
+    assert_eq!(filters.len(), 1);
+    assert!(filters[0].last_updated.is_none());
+}
+
+#[test]
+/// Testing an existing filter uses its last updated time to tell which entries are new.
+fn test_existing_filter_after_update() {
+    let (dir, cfg) = temp_config();
+    let (script_path, _log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["marshmallow"], script_path).execute(&cfg).unwrap();
+    UpdateCmd.execute(&cfg).unwrap();
+
+    // Only the older entry was matched, so after changing the keywords the newer one is new
+    let cmd = EditFilterCmd {
+        filter: FilterSelector::Id(FilterId(1)),
+        new_keywords: Some(vec!["example".into()]),
+        new_script_path: None,
+        new_action: None,
+        new_name: None,
+        new_max_actions: None,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    };
+    cmd.execute(&cfg).unwrap();
+
+    let cmd = TestFilterCmd {
+        filter: FilterSelector::Id(FilterId(1)),
+        from_file: Some(feed_file("two_entries.rss")),
+    };
+    let output = cmd.execute(&cfg).unwrap();
+    let matches: Vec<_> = output.entries.iter().map(|(_, entry_match)| entry_match.clone()).collect();
+    assert_eq!(matches, [EntryMatch::AlreadySeen, EntryMatch::New]);
+    assert!(output.output()[3].starts_with("seen\t"));
+}
+
+#[test]
+fn test_filter_from_file_without_feed() {
+    let (_dir, cfg) = temp_config();
+
+    let cmd = TestFilterCmd {
+        filter: FilterSelector::Keywords("not_added".into(), vec![]),
+        from_file: Some(feed_file("missing_data.rss")),
+    };
+    let output = cmd.execute(&cfg).unwrap();
+    assert_eq!(output.entries.len(), 1);
+    assert_eq!(output.entry_errors.len(), 1);
+    let lines = output.output();
+    assert_eq!(lines[lines.len() - 2], "1 entries had data errors, so update would skip this feed:");
+
+    let cmd = TestFilterCmd {
+        filter: FilterSelector::Keywords("not_added".into(), vec![]),
+        from_file: None,
+    };
+    assert_eq!(cmd.execute(&cfg).unwrap_err().to_string(), "Couldn't find a feed with alias not_added.");
+}
+
+#[test]
+/// The database isn't locked while the feed is downloaded, so other commands and updates can
+/// write to it in the meantime.
+fn test_filter_does_not_lock_db_while_fetching() {
+    let (dir, cfg) = temp_config();
+    let (program, started_path, release_path) = temp_blocking_script(dir.path(), Some(&feed_file("two_entries.rss")));
+    example_add_feed_local1(url::Url::parse(&format!("exec:{}", program.to_string_lossy())).unwrap())
+        .execute(&cfg).unwrap();
+
+    let test_cfg = cfg.clone();
+    let test_thread = std::thread::spawn(move || TestFilterCmd {
+        filter: FilterSelector::Keywords("local1".into(), vec![]),
+        from_file: None,
+    }.execute(&test_cfg));
+
+    wait_for_file(&started_path);
+    example_add_feed_local2(url::Url::parse("https://example.com/feed.rss").unwrap()).execute(&cfg).unwrap();
+
+    std::fs::write(&release_path, "").unwrap();
+    let output = test_thread.join().unwrap().unwrap();
+    assert_eq!(output.entries.len(), 2);
+}
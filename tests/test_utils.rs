@@ -1,13 +1,18 @@
 // Allow dead code because each test includes this file separately and may not use all functions.
 #![allow(dead_code)]
+// The section headings below are doc comments that aren't attached to anything.
+#![allow(clippy::empty_line_after_doc_comments)]
 
 use rss_actions::*;
 
 use tempfile::{TempDir, tempdir};
 use url::Url;
+// Imported anonymously because it conflicts with rss_actions::Filter
+use warp::Filter as _;
 
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
 
 
 /// This method just makes it easy to get the filename when testing, when we know the path is just
@@ -38,9 +43,7 @@ pub fn temp_config() -> (TempDir, Config) {
     let mut db_path = test_dir.path().to_path_buf();
     db_path.push("rss-actions-test.db");
 
-    let cfg = Config {
-        db_path,
-    };
+    let cfg = Config::new(db_path);
 
     let mut cfg_path = test_dir.path().to_path_buf();
     cfg_path.push("config.toml");
@@ -49,6 +52,36 @@ pub fn temp_config() -> (TempDir, Config) {
     (test_dir, cfg)
 }
 
+// start a local warp server (per-test, in a new thread) on an unused port that serves example rss files and return
+// a url to the location the files are being served from.
+
+pub fn run_rss_files_server() -> Url {
+    // blocking channel to get url from server url with local port from inside thread
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let tokio_runtime = tokio::runtime::Runtime::new().unwrap();
+        tokio_runtime.block_on(_run_static_files_server(tx));
+    });
+    // get the url from the thread
+    rx.recv().unwrap()
+}
+
+async fn _run_static_files_server(tx: Sender<Url>) {
+    let mut test_rss_files_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    test_rss_files_dir.push("tests/test_rss_feed_files/");
+
+    let route = warp::path("feeds").and(warp::fs::dir(test_rss_files_dir));
+
+    let any_port_addr: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let (addr, server) = warp::serve(route).bind_ephemeral(any_port_addr);
+
+    let port = addr.port();
+    let url = Url::parse(&format!("http://127.0.0.1:{}/feeds/", port)).unwrap();
+
+    tx.send(url).unwrap();
+    server.await;
+}
+
 /// Example no-parameter commands
 /// We could write these inline but if they need to change it's nice to uniformly have them behind
 /// convenience functions.
@@ -247,3 +280,39 @@ pub fn temp_log_data_script(exec_dir: &Path) -> (PathBuf, PathBuf) {
 
     (script_path, log_path)
 }
+
+/// Write a script that creates a file when it starts and then waits until the test creates a
+/// release file, so the test can do something while an update or other command is running it. If
+/// `output` is given, the script prints that file once it's released, so it can also be used as an
+/// `exec:` feed program.
+///
+/// The script is written to `exec_dir/blocking_script.sh`. Returns its path, the path of the file
+/// it creates when it starts, and the path of the release file.
+pub fn temp_blocking_script(exec_dir: &Path, output: Option<&Path>) -> (PathBuf, PathBuf, PathBuf) {
+    let script_path = exec_dir.join("blocking_script.sh");
+    let started_path = exec_dir.join("blocking_script_started");
+    let release_path = exec_dir.join("blocking_script_release");
+
+    let mut script_contents = format!("#!/bin/sh\ntouch '{}'\nwhile [ ! -e '{}' ]; do sleep 0.05; done\n",
+        started_path.to_string_lossy(), release_path.to_string_lossy());
+    if let Some(output) = output {
+        script_contents += &format!("cat '{}'\n", output.to_string_lossy());
+    }
+    std::fs::write(&script_path, script_contents).unwrap();
+
+    let mut perms = std::fs::metadata(&script_path).unwrap().permissions();
+    perms.set_mode(0o755);
+    std::fs::set_permissions(&script_path, perms).unwrap();
+
+    (script_path, started_path, release_path)
+}
+
+/// Wait for a file to be created, e.g. by `temp_blocking_script`, failing the test if it takes
+/// longer than 10 seconds.
+pub fn wait_for_file(path: &Path) {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !path.exists() {
+        assert!(std::time::Instant::now() < deadline, "{} was never created", path.display());
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+}
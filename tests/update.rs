@@ -1,7 +1,8 @@
 mod test_utils;
 use test_utils::*;
 
-use rss_actions::{ListFiltersCmd, UpdateCmd};
+use rss_actions::{EditFilterCmd, ListFiltersCmd, UpdateCmd};
+use rss_actions::{FilterId, FilterSelector};
 use rss_actions::{RSSActionCmd, ConsoleOutput};
 
 use std::sync::Arc;
@@ -55,19 +56,8 @@ use std::sync::mpsc::Sender;
 // annoying for your clients as well!
 
 
-// start a local warp server (per-test, in a new thread) on an unused port that serves example rss files and return
-// a url to the location the files are being served from.
-
-fn run_rss_files_server() -> Url {
-    // blocking channel to get url from server url with local port from inside thread
-    let (tx, rx) = std::sync::mpsc::channel();
-    std::thread::spawn(move || {
-        let tokio_runtime = tokio::runtime::Runtime::new().unwrap();
-        tokio_runtime.block_on(_run_static_files_server(tx));
-    });
-    // get the url from the thread
-    rx.recv().unwrap()
-}
+// start a local warp server (per-test, in a new thread) on an unused port that serves dynamically
+// generated rss feeds and return a url to the location the feeds are being served from.
 
 fn run_rss_dynamic_server() -> Url {
     // blocking channel to get url from server url with local port from inside thread
@@ -80,22 +70,6 @@ fn run_rss_dynamic_server() -> Url {
     rx.recv().unwrap()
 }
 
-async fn _run_static_files_server(tx: Sender<Url>) {
-    let mut test_rss_files_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    test_rss_files_dir.push("tests/test_rss_feed_files/");
-
-    let route = warp::path("feeds").and(warp::fs::dir(test_rss_files_dir));
-
-    let any_port_addr: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
-    let (addr, server) = warp::serve(route).bind_ephemeral(any_port_addr);
-
-    let port = addr.port();
-    let url = Url::parse(&format!("http://127.0.0.1:{}/feeds/", port)).unwrap();
-
-    tx.send(url).unwrap();
-    server.await;
-}
-
 /// Creates a server that starts with one entry and adds one new one upon every access. The oldest
 /// entries are first which helps us test that regardless of order we'll always collect the most
 /// recent update.
@@ -833,10 +807,10 @@ fn update_multiple_filters_with_multiple_feeds() {
     let timestamp1: DateTime<Local> = Utc.with_ymd_and_hms(2009, 9, 6, 16, 20, 0).unwrap().into();
     let timestamp2: DateTime<Local> = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap().into();
 
-    let filter_line1 = ["local1", "Example", "data_script.sh", &timestamp1.to_string()].join("\t");
-    let filter_line2 = ["local3", "0, Item", "data_script.sh", &timestamp2.to_string()].join("\t");
-    let filter_line3 = ["local1", "Example, xxx", "data_script.sh", "Never updated"].join("\t");
-    let filter_line4 = ["local3", "2, Item", "data_script.sh", "Never updated"].join("\t");
+    let filter_line1 = ["2", "-", "local1", "Example", "data_script.sh", &timestamp1.to_string()].join("\t");
+    let filter_line2 = ["4", "-", "local3", "0, Item", "data_script.sh", &timestamp2.to_string()].join("\t");
+    let filter_line3 = ["1", "-", "local1", "Example, xxx", "data_script.sh", "Never updated"].join("\t");
+    let filter_line4 = ["3", "-", "local3", "2, Item", "data_script.sh", "Never updated"].join("\t");
     assert_eq!(message.output(),
         vec!["Current filters:", "", &filter_line1, &filter_line2, &filter_line3, &filter_line4]);
     // Update
@@ -861,10 +835,10 @@ fn update_multiple_filters_with_multiple_feeds() {
     let timestamp1: DateTime<Local> = Utc.with_ymd_and_hms(2009, 9, 6, 16, 20, 0).unwrap().into();
     let timestamp2: DateTime<Local> = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap().into();
 
-    let filter_line1 = ["local1", "Example", "data_script.sh", &timestamp1.to_string()].join("\t");
-    let filter_line2 = ["local3", "0, Item", "data_script.sh", &timestamp2.to_string()].join("\t");
-    let filter_line3 = ["local1", "Example, xxx", "data_script.sh", "Never updated"].join("\t");
-    let filter_line4 = ["local3", "2, Item", "data_script.sh", "Never updated"].join("\t");
+    let filter_line1 = ["2", "-", "local1", "Example", "data_script.sh", &timestamp1.to_string()].join("\t");
+    let filter_line2 = ["4", "-", "local3", "0, Item", "data_script.sh", &timestamp2.to_string()].join("\t");
+    let filter_line3 = ["1", "-", "local1", "Example, xxx", "data_script.sh", "Never updated"].join("\t");
+    let filter_line4 = ["3", "-", "local3", "2, Item", "data_script.sh", "Never updated"].join("\t");
     assert_eq!(message.output(),
         vec!["Current filters:", "", &filter_line1, &filter_line2, &filter_line3, &filter_line4]);
 
@@ -893,10 +867,10 @@ fn update_multiple_filters_with_multiple_feeds() {
     let timestamp2: DateTime<Local> = Utc.with_ymd_and_hms(2000, 1, 3, 0, 0, 0).unwrap().into();
     let timestamp3: DateTime<Local> = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap().into();
 
-    let filter_line1 = ["local1", "Example", "data_script.sh", &timestamp1.to_string()].join("\t");
-    let filter_line2 = ["local3", "2, Item", "data_script.sh", &timestamp2.to_string()].join("\t");
-    let filter_line3 = ["local3", "0, Item", "data_script.sh", &timestamp3.to_string()].join("\t");
-    let filter_line4 = ["local1", "Example, xxx", "data_script.sh", "Never updated"].join("\t");
+    let filter_line1 = ["2", "-", "local1", "Example", "data_script.sh", &timestamp1.to_string()].join("\t");
+    let filter_line2 = ["3", "-", "local3", "2, Item", "data_script.sh", &timestamp2.to_string()].join("\t");
+    let filter_line3 = ["4", "-", "local3", "0, Item", "data_script.sh", &timestamp3.to_string()].join("\t");
+    let filter_line4 = ["1", "-", "local1", "Example, xxx", "data_script.sh", "Never updated"].join("\t");
     assert_eq!(message.output(),
         vec!["Current filters:", "", &filter_line1, &filter_line2, &filter_line3, &filter_line4,]);
     // Add new filter
@@ -912,11 +886,11 @@ fn update_multiple_filters_with_multiple_feeds() {
     let timestamp2: DateTime<Local> = Utc.with_ymd_and_hms(2000, 1, 3, 0, 0, 0).unwrap().into();
     let timestamp3: DateTime<Local> = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap().into();
 
-    let filter_line1 = ["local1", "Example", "data_script.sh", &timestamp1.to_string()].join("\t");
-    let filter_line2 = ["local3", "2, Item", "data_script.sh", &timestamp2.to_string()].join("\t");
-    let filter_line3 = ["local3", "0, Item", "data_script.sh", &timestamp3.to_string()].join("\t");
-    let filter_line4 = ["local1", "Example, xxx", "data_script.sh", "Never updated"].join("\t");
-    let filter_line5 = ["local2", "interspersed", "data_script.sh", "Never updated"].join("\t");
+    let filter_line1 = ["2", "-", "local1", "Example", "data_script.sh", &timestamp1.to_string()].join("\t");
+    let filter_line2 = ["3", "-", "local3", "2, Item", "data_script.sh", &timestamp2.to_string()].join("\t");
+    let filter_line3 = ["4", "-", "local3", "0, Item", "data_script.sh", &timestamp3.to_string()].join("\t");
+    let filter_line4 = ["1", "-", "local1", "Example, xxx", "data_script.sh", "Never updated"].join("\t");
+    let filter_line5 = ["5", "-", "local2", "interspersed", "data_script.sh", "Never updated"].join("\t");
     assert_eq!(message.output(),
         vec!["Current filters:", "", &filter_line1, &filter_line2, &filter_line3, &filter_line4, &filter_line5]);
 
@@ -942,11 +916,11 @@ fn update_multiple_filters_with_multiple_feeds() {
 
     // New filter is updated, old ones are not
     let message = ListFiltersCmd.execute(&cfg).unwrap();
-    let filter_line1 = ["local1", "Example", "data_script.sh", &timestamp1.to_string()].join("\t");
-    let filter_line2 = ["local2", "interspersed", "data_script.sh", &timestamp1.to_string()].join("\t");
-    let filter_line3 = ["local3", "2, Item", "data_script.sh", &timestamp2.to_string()].join("\t");
-    let filter_line4 = ["local3", "0, Item", "data_script.sh", &timestamp3.to_string()].join("\t");
-    let filter_line5 = ["local1", "Example, xxx", "data_script.sh", "Never updated"].join("\t");
+    let filter_line1 = ["2", "-", "local1", "Example", "data_script.sh", &timestamp1.to_string()].join("\t");
+    let filter_line2 = ["5", "-", "local2", "interspersed", "data_script.sh", &timestamp1.to_string()].join("\t");
+    let filter_line3 = ["3", "-", "local3", "2, Item", "data_script.sh", &timestamp2.to_string()].join("\t");
+    let filter_line4 = ["4", "-", "local3", "0, Item", "data_script.sh", &timestamp3.to_string()].join("\t");
+    let filter_line5 = ["1", "-", "local1", "Example, xxx", "data_script.sh", "Never updated"].join("\t");
     let expected = vec!["Current filters:", "", &filter_line1, &filter_line2, &filter_line3, &filter_line4, &filter_line5];
     assert_eq!(message.output(), expected,
         "\n---output:\n{}\n\n\n---expected:\n{}", message.output().join("\n"), expected.join("\n"));
@@ -967,11 +941,11 @@ fn update_multiple_filters_with_multiple_feeds() {
 
     // same as previous
     let message = ListFiltersCmd.execute(&cfg).unwrap();
-    let filter_line1 = ["local1", "Example", "data_script.sh", &timestamp1.to_string()].join("\t");
-    let filter_line2 = ["local2", "interspersed", "data_script.sh", &timestamp1.to_string()].join("\t");
-    let filter_line3 = ["local3", "2, Item", "data_script.sh", &timestamp2.to_string()].join("\t");
-    let filter_line4 = ["local3", "0, Item", "data_script.sh", &timestamp3.to_string()].join("\t");
-    let filter_line5 = ["local1", "Example, xxx", "data_script.sh", "Never updated"].join("\t");
+    let filter_line1 = ["2", "-", "local1", "Example", "data_script.sh", &timestamp1.to_string()].join("\t");
+    let filter_line2 = ["5", "-", "local2", "interspersed", "data_script.sh", &timestamp1.to_string()].join("\t");
+    let filter_line3 = ["3", "-", "local3", "2, Item", "data_script.sh", &timestamp2.to_string()].join("\t");
+    let filter_line4 = ["4", "-", "local3", "0, Item", "data_script.sh", &timestamp3.to_string()].join("\t");
+    let filter_line5 = ["1", "-", "local1", "Example, xxx", "data_script.sh", "Never updated"].join("\t");
     let expected = vec!["Current filters:", "", &filter_line1, &filter_line2, &filter_line3, &filter_line4, &filter_line5];
     assert_eq!(message.output(), expected,
         "\n---output:\n{}\n\n\n---expected:\n{}", message.output().join("\n"), expected.join("\n"));
@@ -1065,8 +1039,8 @@ fn update_with_one_feed_failing_succeeds() {
     let message = ListFiltersCmd.execute(&cfg).unwrap();
     let timestamp: DateTime<Local> = Utc.with_ymd_and_hms(2009, 9, 6, 16, 20, 0).unwrap().into();
 
-    let filter_line1 = ["local2", "Example, entry", "data_script.sh", &timestamp.to_string()].join("\t");
-    let filter_line2 = ["local1", "xxx", "data_script.sh", "Never updated"].join("\t");
+    let filter_line1 = ["2", "-", "local2", "Example, entry", "data_script.sh", &timestamp.to_string()].join("\t");
+    let filter_line2 = ["1", "-", "local1", "xxx", "data_script.sh", "Never updated"].join("\t");
     assert_eq!(message.output(),
         vec!["Current filters:", "", &filter_line1, &filter_line2]);
 }
@@ -1124,8 +1098,8 @@ fn update_with_one_feed_bad_data_succeeds() {
     let message = ListFiltersCmd.execute(&cfg).unwrap();
     let timestamp: DateTime<Local> = Utc.with_ymd_and_hms(2009, 9, 6, 16, 20, 0).unwrap().into();
 
-    let filter_line1 = ["local2", "Example, entry", "data_script.sh", &timestamp.to_string()].join("\t");
-    let filter_line2 = ["local1", "xxx", "data_script.sh", "Never updated"].join("\t");
+    let filter_line1 = ["2", "-", "local2", "Example, entry", "data_script.sh", &timestamp.to_string()].join("\t");
+    let filter_line2 = ["1", "-", "local1", "xxx", "data_script.sh", "Never updated"].join("\t");
     assert_eq!(message.output(),
         vec!["Current filters:", "", &filter_line1, &filter_line2]);
 }
@@ -1160,8 +1134,8 @@ fn all_feeds_fail_download_network_message() {
 
     // List filters and check that it says not updated
     let message = ListFiltersCmd.execute(&cfg).unwrap();
-    let filter_line1 = ["local1", "xxx", "false", "Never updated"].join("\t");
-    let filter_line2 = ["local2", "Nothing", "false", "Never updated"].join("\t");
+    let filter_line1 = ["1", "-", "local1", "xxx", "false", "Never updated"].join("\t");
+    let filter_line2 = ["2", "-", "local2", "Nothing", "false", "Never updated"].join("\t");
     assert_eq!(message.output(),
         vec!["Current filters:", "", &filter_line1, &filter_line2]);
 }
@@ -1218,8 +1192,8 @@ fn feed_with_missing_data_does_not_update_filter() {
     let message = ListFiltersCmd.execute(&cfg).unwrap();
     let timestamp: DateTime<Local> = Utc.with_ymd_and_hms(2009, 9, 6, 16, 20, 0).unwrap().into();
 
-    let filter_line1 = ["local2", "Example, entry", "data_script.sh", &timestamp.to_string()].join("\t");
-    let filter_line2 = ["local1", "missing, pubdate", "data_script.sh", "Never updated"].join("\t");
+    let filter_line1 = ["2", "-", "local2", "Example, entry", "data_script.sh", &timestamp.to_string()].join("\t");
+    let filter_line2 = ["1", "-", "local1", "missing, pubdate", "data_script.sh", "Never updated"].join("\t");
     assert_eq!(message.output(), ["Current filters:", "", &filter_line1, &filter_line2]);
 }
 
@@ -1259,7 +1233,87 @@ fn feed_with_failing_script_does_not_update_filter() {
     let message = ListFiltersCmd.execute(&cfg).unwrap();
     let timestamp: DateTime<Local> = Utc.with_ymd_and_hms(2009, 9, 6, 16, 20, 0).unwrap().into();
 
-    let filter_line1 = ["local1", "entry", "true", &timestamp.to_string()].join("\t");
-    let filter_line2 = ["local1", "Example, entry", "false", "Never updated"].join("\t");
+    let filter_line1 = ["2", "-", "local1", "entry", "true", &timestamp.to_string()].join("\t");
+    let filter_line2 = ["1", "-", "local1", "Example, entry", "false", "Never updated"].join("\t");
     assert_eq!(message.output(), ["Current filters:", "", &filter_line1, &filter_line2]);
 }
+
+#[test]
+/// Each filter's last_updated time is saved as soon as its scripts have run, rather than when the
+/// whole update finishes, so that a killed update doesn't run the same scripts again next time.
+fn filter_progress_is_committed_before_update_finishes() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let (blocking_script_path, started_path, release_path) = temp_blocking_script(dir.path(), None);
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], script_path).execute(&cfg).unwrap();
+    example_add_filter_local2(vec![], blocking_script_path).execute(&cfg).unwrap();
+
+    let update_cfg = cfg.clone();
+    let update_thread = std::thread::spawn(move || UpdateCmd.execute(&update_cfg));
+
+    // The first filter is saved by the time the second filter's script is running.
+    wait_for_file(&started_path);
+    let filters = ListFiltersCmd.execute(&cfg).unwrap().filters;
+    let saved_filter = filters.iter().find(|filter| filter.alias == "local1").unwrap();
+    assert!(saved_filter.last_updated.is_some(), "first filter's progress was not committed");
+    assert!(log_path.exists());
+    let blocked_filter = filters.iter().find(|filter| filter.alias == "local2").unwrap();
+    assert!(blocked_filter.last_updated.is_none());
+
+    std::fs::write(&release_path, "").unwrap();
+    let output = update_thread.join().unwrap().unwrap();
+    assert_eq!(output.successes, 2);
+    assert_eq!(output.updates, 2);
+
+    let filters = ListFiltersCmd.execute(&cfg).unwrap().filters;
+    assert!(filters.iter().all(|filter| filter.last_updated.is_some()));
+}
+
+#[test]
+/// Editing a filter while an update is running its scripts doesn't stop the update from saving the
+/// filter's progress or running the other filters.
+fn filter_edited_during_update_is_saved() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let (blocking_script_path, started_path, release_path) = temp_blocking_script(dir.path(), None);
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], blocking_script_path).execute(&cfg).unwrap();
+    example_add_filter_local2(vec![], script_path).execute(&cfg).unwrap();
+
+    let update_cfg = cfg.clone();
+    let update_thread = std::thread::spawn(move || UpdateCmd.execute(&update_cfg));
+
+    // Change the blocked filter's keywords and script while its script is running.
+    wait_for_file(&started_path);
+    EditFilterCmd {
+        filter: FilterSelector::Id(FilterId(1)),
+        new_keywords: Some(vec!["Example".into()]),
+        new_script_path: Some(PathBuf::from("/bin/true")),
+        new_action: None,
+        new_name: None,
+        new_max_actions: None,
+        new_dedup_group: None,
+        new_priority: None,
+        new_stop: None,
+    }.execute(&cfg).unwrap();
+
+    std::fs::write(&release_path, "").unwrap();
+    let output = update_thread.join().unwrap().unwrap();
+    assert_eq!(output.failures, 0, "Filters failed: {:?}", output.executed_filters);
+    assert_eq!(output.successes, 2);
+    assert!(log_path.exists());
+
+    let filters = ListFiltersCmd.execute(&cfg).unwrap().filters;
+    let edited = filters.iter().find(|filter| filter.alias == "local1").unwrap();
+    assert_eq!(edited.keywords, ["Example"]);
+    assert!(edited.last_updated.is_some());
+}
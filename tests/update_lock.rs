@@ -0,0 +1,89 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{DbMigrateCmd, ExportCmd, ImportCmd, ImportMode, UpdateCmd, UpdateRunning};
+use rss_actions::RSSActionCmd;
+
+use std::path::PathBuf;
+
+use chrono::{Duration, Utc};
+
+fn lock_path(db_path: &std::path::Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(".lock");
+    PathBuf::from(path)
+}
+
+#[test]
+/// An update doesn't run while another process holds the lock.
+fn update_does_not_run_while_locked() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], script_path).execute(&cfg).unwrap();
+
+    let started = Utc::now() - Duration::minutes(3);
+    let lock_file = std::fs::File::create(lock_path(&cfg.db_path)).unwrap();
+    lock_file.lock().unwrap();
+    std::fs::write(lock_path(&cfg.db_path), format!("12345\n{}\n", started.to_rfc3339())).unwrap();
+
+    let err = UpdateCmd.execute(&cfg).unwrap_err();
+    let running = err.downcast_ref::<UpdateRunning>().expect("error was not UpdateRunning");
+    assert_eq!(running.pid, Some(12345));
+    assert_eq!(running.started.unwrap().timestamp(), started.timestamp());
+    assert!(err.to_string().starts_with("Another update is already running (pid 12345, started "), "{}", err);
+    assert!(!log_path.exists(), "script ran while another update held the lock");
+
+    drop(lock_file);
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 1);
+    assert!(log_path.exists());
+}
+
+#[test]
+/// A lock file left behind by an update that was killed isn't locked and doesn't block updates.
+fn stale_lock_file_is_ignored() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec![], script_path).execute(&cfg).unwrap();
+
+    std::fs::write(lock_path(&cfg.db_path), "12345\n2020-01-01T00:00:00+00:00\n").unwrap();
+
+    let output = UpdateCmd.execute(&cfg).unwrap();
+    assert_eq!(output.successes, 1);
+    assert!(log_path.exists());
+
+    let contents = std::fs::read_to_string(lock_path(&cfg.db_path)).unwrap();
+    assert_eq!(contents.lines().next().unwrap(), std::process::id().to_string());
+}
+
+#[test]
+/// Importing and migrating the database don't run while an update holds the lock, since they
+/// change the feeds and filters the update is saving.
+fn import_and_migrate_wait_for_update() {
+    let (dir, cfg) = temp_config();
+    example_add_feed1().execute(&cfg).unwrap();
+    let export_path = dir.path().join("export.json");
+    ExportCmd(Some(export_path.clone())).execute(&cfg).unwrap();
+
+    let lock_file = std::fs::File::create(lock_path(&cfg.db_path)).unwrap();
+    lock_file.lock().unwrap();
+
+    let err = ImportCmd(export_path.clone(), ImportMode::Replace).execute(&cfg).unwrap_err();
+    assert!(err.downcast_ref::<UpdateRunning>().is_some(), "Incorrect error: {:#}", err);
+    let err = DbMigrateCmd { check: false }.execute(&cfg).unwrap_err();
+    assert!(err.downcast_ref::<UpdateRunning>().is_some(), "Incorrect error: {:#}", err);
+    // Checking doesn't change anything, so it doesn't need the lock
+    DbMigrateCmd { check: true }.execute(&cfg).unwrap();
+
+    drop(lock_file);
+
+    ImportCmd(export_path, ImportMode::Replace).execute(&cfg).unwrap();
+    DbMigrateCmd { check: false }.execute(&cfg).unwrap();
+}
@@ -0,0 +1,84 @@
+mod test_utils;
+use test_utils::*;
+
+use rss_actions::{FilterId, ListFiltersCmd, UpdateSelectedCmd, UpdateSelection};
+use rss_actions::RSSActionCmd;
+
+#[test]
+/// Only the selected feed is downloaded and only its filters are run.
+fn update_selected_feed() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    // This feed would fail to download, but it isn't selected
+    example_add_feed_local2(base_url.join("does_not_exist.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local3(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["pizza"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec![], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local3(vec!["example"], script_path).execute(&cfg).unwrap();
+
+    let selection = UpdateSelection { feeds: vec!["local1".into()], ..Default::default() };
+    let output = UpdateSelectedCmd(selection).execute(&cfg).unwrap();
+    assert_eq!(output.executed_feeds.len(), 1);
+    assert_eq!(output.executed_feeds[0].0.alias, "local1");
+    assert!(output.executed_feeds[0].1.is_ok());
+    assert_eq!(output.executed_filters.len(), 1);
+    assert_eq!((output.successes, output.failures, output.updates), (1, 0, 1));
+
+    let script_output = std::fs::read_to_string(&log_path).unwrap();
+    assert!(script_output.contains("title: Pizza Example marshmallow"));
+    assert!(!script_output.contains("title: Example entry\n"));
+
+    let filters = ListFiltersCmd.execute(&cfg).unwrap().filters;
+    for filter in filters {
+        assert_eq!(filter.last_updated.is_some(), filter.alias == "local1", "{:?}", filter);
+    }
+}
+
+#[test]
+/// Selecting filters by id only runs those filters, even if others are on the same feed.
+fn update_selected_filters() {
+    let (dir, cfg) = temp_config();
+    let (script_path, log_path) = temp_log_data_script(dir.path());
+
+    let base_url = run_rss_files_server();
+    example_add_feed_local1(base_url.join("two_entries.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_feed_local2(base_url.join("simple_feed.rss").unwrap()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["pizza"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local1(vec!["nyc"], script_path.clone()).execute(&cfg).unwrap();
+    example_add_filter_local2(vec![], script_path).execute(&cfg).unwrap();
+
+    let selection = UpdateSelection { filters: vec![FilterId(2)], ..Default::default() };
+    let output = UpdateSelectedCmd(selection).execute(&cfg).unwrap();
+    assert_eq!(output.executed_feeds.len(), 1);
+    assert_eq!(output.executed_filters.len(), 1);
+    assert_eq!(output.executed_filters[0].0.id, Some(FilterId(2)));
+
+    let script_output = std::fs::read_to_string(&log_path).unwrap();
+    assert!(script_output.contains("title: Example entry NYC"));
+    assert!(!script_output.contains("title: Pizza"));
+
+    // A filter that isn't on a selected feed isn't run
+    let selection = UpdateSelection { feeds: vec!["local2".into()], filters: vec![FilterId(1)], ..Default::default() };
+    let output = UpdateSelectedCmd(selection).execute(&cfg).unwrap();
+    assert!(output.executed_feeds.is_empty());
+    assert!(output.executed_filters.is_empty());
+}
+
+#[test]
+/// Feeds and filters that don't exist are reported rather than updating nothing.
+fn update_selection_must_exist() {
+    let (_dir, cfg) = temp_config();
+    example_add_feed1().execute(&cfg).unwrap();
+    example_add_filter1().execute(&cfg).unwrap();
+
+    let selection = UpdateSelection { feeds: vec!["nope".into()], ..Default::default() };
+    let err = UpdateSelectedCmd(selection).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "No feed was found to update that matches name `nope`");
+
+    let selection = UpdateSelection { filters: vec![FilterId(7)], ..Default::default() };
+    let err = UpdateSelectedCmd(selection).execute(&cfg).unwrap_err();
+    assert_eq!(err.to_string(), "No filter with id 7 was found in the database.");
+}